@@ -1,141 +1,14569 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token};
+use anchor_lang::solana_program::hash::{hash, hashv};
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::system_program;
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer};
 
 declare_id!("your_program_id");
 
+pub const CONFIG_SEED: &[u8] = b"config";
+pub const GENOME_SEED: &[u8] = b"genome";
+pub const ESCROW_SEED: &[u8] = b"escrow";
+pub const SOL_VAULT_SEED: &[u8] = b"sol_vault";
+pub const ACCESS_SEED: &[u8] = b"access";
+pub const RATING_SEED: &[u8] = b"rating";
+pub const SELLER_PROFILE_SEED: &[u8] = b"seller_profile";
+pub const OFFER_SEED: &[u8] = b"offer";
+pub const WHITELIST_SEED: &[u8] = b"whitelist";
+pub const MULTISIG_SEED: &[u8] = b"multisig";
+pub const BID_SEED: &[u8] = b"bid";
+pub const USER_INDEX_SEED: &[u8] = b"user_index";
+pub const BLACKLIST_SEED: &[u8] = b"blacklist";
+pub const PAYOUT_SPLIT_SEED: &[u8] = b"payout_split";
+pub const CATEGORY_INDEX_SEED: &[u8] = b"category_index";
+pub const BUYER_LICENSES_SEED: &[u8] = b"buyer_licenses";
+pub const MARKET_STATS_SEED: &[u8] = b"market_stats";
+pub const BUYER_HISTORY_SEED: &[u8] = b"buyer_history";
+pub const TRANSACTION_SEED: &[u8] = b"txn";
+pub const REFERRAL_SEED: &[u8] = b"referral";
+pub const GRANT_QUOTA_SEED: &[u8] = b"grant_quota";
+pub const EARNINGS_SEED: &[u8] = b"earnings";
+pub const SUBSCRIPTION_POOL_SEED: &[u8] = b"subscription_pool";
+pub const SUBSCRIPTION_PASS_SEED: &[u8] = b"subscription_pass";
+pub const ACCESS_LOG_SEED: &[u8] = b"access_log";
+pub const REFUND_RECORD_SEED: &[u8] = b"refund_record";
+pub const MAX_ID_LEN: usize = 64;
+pub const MAX_METADATA_LEN: usize = 512;
+pub const MAX_COMMENT_LEN: usize = 280;
+pub const MAX_TAGS: usize = 8;
+pub const MAX_TAG_LEN: usize = 32;
+pub const MAX_ENCRYPTED_KEY_LEN: usize = 512;
+pub const MAX_ENCRYPTED_METADATA_LEN: usize = 512;
+pub const BPS_DENOMINATOR: u64 = 10_000;
+pub const MAX_BATCH_SIZE: usize = 10;
+pub const MAX_WHITELIST_LEN: usize = 32;
+pub const MAX_MULTISIG_OWNERS: usize = 10;
+pub const MAX_USER_INDEX_LEN: usize = 32;
+pub const MAX_BLACKLIST_LEN: usize = 64;
+pub const MAX_JURISDICTIONS: usize = 16;
+pub const MAX_BUNDLE_SIZE: usize = 8;
+pub const MAX_PAYOUT_RECIPIENTS: usize = 8;
+pub const MAX_CATEGORY_INDEX_LEN: usize = 32;
+pub const MAX_PREVIEW_URI_LEN: usize = 200;
+pub const MAX_PROVENANCE: usize = 8;
+pub const MAX_ACCESSION_ID_LEN: usize = 32;
+pub const MAX_LISTING_NOTES_LEN: usize = 256;
+/// Times `crank_relist` may reopen one auto-relisting listing.
+pub const MAX_AUTO_RELISTS: u32 = 12;
+/// `SellerProfile::reputation` of a seller rated five stars every time.
+pub const MAX_REPUTATION: u16 = 500;
+/// Weight of a fresh rating in `SellerProfile`'s time-weighted aggregate.
+pub const REPUTATION_WEIGHT_SCALE: u64 = 1_000;
+pub const MAX_STATUS_HISTORY: usize = 16;
+pub const MAX_ALLOWED_MINTS: usize = 16;
+pub const MAX_BUYER_HISTORY_LEN: usize = 16;
+pub const MAX_ACCESS_LOG_LEN: usize = 32;
+/// Decimals of USD amounts in `price_in_usd`.
+pub const USD_DECIMALS: u32 = 6;
+pub const SOL_DECIMALS: u8 = 9;
+/// Oldest oracle price accepted when settling a USD-pegged listing.
+pub const MAX_PRICE_AGE_SECS: u64 = 60;
+/// Fixed-point scale of `SubscriptionPool::revenue_per_genome`.
+pub const POOL_REVENUE_SCALE: u128 = 1_000_000_000_000;
+/// `Genome::format` codes.
+pub const FORMAT_VCF: u8 = 0;
+pub const FORMAT_FASTQ: u8 = 1;
+pub const FORMAT_BAM: u8 = 2;
+pub const FORMAT_CRAM: u8 = 3;
+pub const FORMAT_OTHER: u8 = 4;
+/// `Genome::reference_build` codes.
+pub const REFERENCE_GRCH37: u8 = 0;
+pub const REFERENCE_GRCH38: u8 = 1;
+pub const REFERENCE_T2T_CHM13: u8 = 2;
+pub const REFERENCE_OTHER: u8 = 3;
+/// `Accession::source` codes.
+pub const ACCESSION_NCBI: u8 = 0;
+pub const ACCESSION_EBI: u8 = 1;
+pub const ACCESSION_DDBJ: u8 = 2;
+pub const ACCESSION_OTHER: u8 = 3;
+
+/// Uses a genome's consent permits, and that buyers declare at purchase.
+pub const USE_ANY: u8 = 0;
+pub const USE_RESEARCH: u8 = 1;
+pub const USE_COMMERCIAL: u8 = 2;
+/// Rights a license confers, from viewing only up to onward sharing.
+pub const ACCESS_READ_ONLY: u8 = 0;
+pub const ACCESS_DOWNLOAD: u8 = 1;
+pub const ACCESS_REDISTRIBUTE: u8 = 2;
+/// Reasons `check_purchasable` gives for a listing that cannot be bought,
+/// in the order the gates run. Clients map these to messages, so existing
+/// codes never change meaning; new gates get new codes.
+pub const PURCHASE_OK: u8 = 0;
+pub const PURCHASE_PAUSED: u8 = 1;
+pub const PURCHASE_GENOME_DELETED: u8 = 2;
+pub const PURCHASE_CONSENT_WITHDRAWN: u8 = 3;
+pub const PURCHASE_EMBARGOED: u8 = 4;
+pub const PURCHASE_SELLER_BLACKLISTED: u8 = 5;
+pub const PURCHASE_LISTING_CLOSED: u8 = 6;
+pub const PURCHASE_LISTING_EXPIRED: u8 = 7;
+pub const PURCHASE_AUCTION_OPEN: u8 = 8;
+pub const PURCHASE_HEARTBEAT_STALE: u8 = 9;
+pub const PURCHASE_MIGRATED: u8 = 10;
+/// Listing period bounds a new `Config` starts with.
+pub const DEFAULT_MIN_LISTING_DURATION: i64 = 60 * 60;
+pub const DEFAULT_MAX_LISTING_DURATION: i64 = 365 * 24 * 60 * 60;
+/// How long a closed transaction must sit untouched before the admin may
+/// sweep what is left in its escrow.
+pub const ESCROW_RECOVERY_DELAY: i64 = 180 * 24 * 60 * 60;
+/// How long a genome's recovery authority must wait between starting a
+/// recovery and taking ownership, leaving the owner time to cancel it.
+pub const RECOVERY_TIMELOCK: i64 = 7 * 24 * 60 * 60;
+/// Account layout version of this build, recorded in `Config::version`.
+pub const PROGRAM_VERSION: u8 = 1;
+/// How close to expiry an auto-renewing grant may be renewed by the crank.
+pub const RENEWAL_WINDOW: i64 = 24 * 60 * 60;
+
+/// Builds the `SalePayees` of a sale from an accounts struct that uses the
+/// usual `treasury*`, `creator*`, `seller*` and `payout_split` names. The
+/// `seller:` form takes the seller's destinations and split explicitly, for
+/// sales paying someone other than the listing's `seller` accounts.
+macro_rules! sale_payees {
+    (
+        $accounts:expr,
+        seller: ($seller_token_account:expr, $seller:expr),
+        seller_split: $seller_split:expr,
+        referral: $referral:expr
+    ) => {
+        SalePayees {
+            seller_token_account: $seller_token_account,
+            seller: $seller,
+            treasury_token_account: $accounts.treasury_token_account.as_ref(),
+            treasury: $accounts.treasury.as_ref().map(|t| t.to_account_info()),
+            creator_token_account: $accounts.creator_token_account.as_ref(),
+            creator: $accounts.creator.as_ref().map(|c| c.to_account_info()),
+            seller_split: $seller_split,
+            referral: $referral,
+        }
+    };
+    ($accounts:expr, $split_accounts:expr, $referral:expr) => {
+        sale_payees!(
+            $accounts,
+            seller: (
+                $accounts.seller_token_account.as_ref(),
+                $accounts.seller.as_ref().map(|s| s.to_account_info())
+            ),
+            seller_split: seller_split(
+                &$accounts.genome,
+                $accounts.payout_split.as_ref(),
+                $split_accounts,
+            )?,
+            referral: $referral
+        )
+    };
+}
+
+/// The `ReferralPayee` of a sale from an accounts struct's `referral_profile`,
+/// `referrer_token_account` and `referrer_wallet`.
+macro_rules! referral_payee {
+    ($accounts:expr, $referrer:expr) => {
+        Some(ReferralPayee {
+            referrer: $referrer,
+            profile: $accounts.referral_profile.as_deref_mut(),
+            token_account: $accounts.referrer_token_account.as_ref(),
+            wallet: $accounts
+                .referrer_wallet
+                .as_ref()
+                .map(|r| r.to_account_info()),
+        })
+    };
+}
+
 #[program]
 pub mod genome_program {
     use super::*;
 
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        admin: Pubkey,
+        treasury: Pubkey,
+        arbitrator: Pubkey,
+        fee_bps: u16,
+        dispute_window: i64,
+    ) -> Result<()> {
+        require!(
+            u64::from(fee_bps) <= BPS_DENOMINATOR,
+            GenomeError::InvalidFeeRate
+        );
+        require!(dispute_window >= 0, GenomeError::InvalidDuration);
+
+        let config = &mut ctx.accounts.config;
+        config.admin = admin;
+        config.treasury = treasury;
+        config.arbitrator = arbitrator;
+        config.fee_bps = fee_bps;
+        config.dispute_window = dispute_window;
+        config.cancellation_fee = 0;
+        config.max_genomes_per_user = 0;
+        config.min_duration = DEFAULT_MIN_LISTING_DURATION;
+        config.max_duration = DEFAULT_MAX_LISTING_DURATION;
+        config.revocation_grace_period = 0;
+        config.listing_cooldown = 0;
+        config.collateral_bps = 0;
+        config.yield_enabled = false;
+        config.yield_program = Pubkey::default();
+        config.epoch_volume_cap = 0;
+        config.referral_bps = 0;
+        config.resale_lock = 0;
+        config.heartbeat_ttl = 0;
+        config.conversion_fee_bps = 0;
+        config.sla_slash_bps = 0;
+        config.refund_cooldown = 0;
+        config.reputation_half_life = 0;
+        config.upgrade_program = None;
+        config.upgrade_window_ends = 0;
+        config.version = PROGRAM_VERSION;
+        config.min_client_version = 0;
+        config.paused = false;
+        config.bump = *ctx.bumps.get("config").unwrap();
+        ctx.accounts.blacklist.bump = *ctx.bumps.get("blacklist").unwrap();
+        ctx.accounts.market_stats.bump = *ctx.bumps.get("market_stats").unwrap();
+
+        Ok(())
+    }
+
+    pub fn set_paused(ctx: Context<AdminConfig>, paused: bool) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        config.paused = paused;
+
+        emit!(PauseToggled {
+            admin: ctx.accounts.admin.key(),
+            paused,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Rotates any of the admin, arbitrator and treasury keys, e.g. after a
+    /// key compromise. `None` leaves a key unchanged.
+    pub fn update_config(
+        ctx: Context<AdminConfig>,
+        new_admin: Option<Pubkey>,
+        new_arbitrator: Option<Pubkey>,
+        new_treasury: Option<Pubkey>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.rotate_keys(new_admin, new_arbitrator, new_treasury)?;
+
+        emit!(ConfigUpdated {
+            updated_by: ctx.accounts.admin.key(),
+            admin: config.admin,
+            arbitrator: config.arbitrator,
+            treasury: config.treasury,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the lamports a seller pays the treasury per cancelled listing;
+    /// zero disables the fee.
+    pub fn set_cancellation_fee(ctx: Context<AdminConfig>, cancellation_fee: u64) -> Result<()> {
+        ctx.accounts.config.cancellation_fee = cancellation_fee;
+
+        emit!(CancellationFeeUpdated {
+            admin: ctx.accounts.admin.key(),
+            cancellation_fee,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Caps the live genomes a single owner may register; zero removes the
+    /// cap.
+    pub fn set_max_genomes_per_user(
+        ctx: Context<AdminConfig>,
+        max_genomes_per_user: u32,
+    ) -> Result<()> {
+        ctx.accounts.config.max_genomes_per_user = max_genomes_per_user;
+
+        emit!(GenomeLimitUpdated {
+            admin: ctx.accounts.admin.key(),
+            max_genomes_per_user,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Sets how long, in seconds, a grant revoked through `revoke_access`
+    /// stays usable; zero makes revocation immediate.
+    pub fn set_revocation_grace_period(
+        ctx: Context<AdminConfig>,
+        revocation_grace_period: i64,
+    ) -> Result<()> {
+        require!(revocation_grace_period >= 0, GenomeError::InvalidDuration);
+        ctx.accounts.config.revocation_grace_period = revocation_grace_period;
+
+        emit!(RevocationGracePeriodUpdated {
+            admin: ctx.accounts.admin.key(),
+            revocation_grace_period,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the seconds a seller must wait between listings; zero disables
+    /// the cooldown.
+    pub fn set_listing_cooldown(ctx: Context<AdminConfig>, listing_cooldown: i64) -> Result<()> {
+        require!(listing_cooldown >= 0, GenomeError::InvalidDuration);
+        ctx.accounts.config.listing_cooldown = listing_cooldown;
+
+        emit!(ListingCooldownUpdated {
+            admin: ctx.accounts.admin.key(),
+            listing_cooldown,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the share of an exclusive listing's price, in basis points, that
+    /// sellers post as collateral; zero disables it.
+    pub fn set_collateral_bps(ctx: Context<AdminConfig>, collateral_bps: u16) -> Result<()> {
+        require!(
+            u64::from(collateral_bps) <= BPS_DENOMINATOR,
+            GenomeError::InvalidFeeRate
+        );
+        ctx.accounts.config.collateral_bps = collateral_bps;
+
+        emit!(CollateralUpdated {
+            admin: ctx.accounts.admin.key(),
+            collateral_bps,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Opts in to, or out of, lending idle token escrows to `yield_program`.
+    /// Disabling or re-pointing only affects new deposits; lent-out escrows
+    /// are still redeemed from the program they were lent to.
+    pub fn set_yield_routing(
+        ctx: Context<AdminConfig>,
+        yield_enabled: bool,
+        yield_program: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.yield_enabled = yield_enabled;
+        config.yield_program = yield_program;
+
+        emit!(YieldRoutingUpdated {
+            admin: ctx.accounts.admin.key(),
+            yield_enabled,
+            yield_program,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Caps the volume `execute_transaction` may settle per epoch, halting
+    /// sales until the next epoch once reached; zero disables the cap.
+    pub fn set_epoch_volume_cap(ctx: Context<AdminConfig>, epoch_volume_cap: u64) -> Result<()> {
+        ctx.accounts.config.epoch_volume_cap = epoch_volume_cap;
+
+        emit!(EpochVolumeCapUpdated {
+            admin: ctx.accounts.admin.key(),
+            epoch_volume_cap,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the share of the marketplace fee, in basis points, that goes to
+    /// the referrer of a sale; zero disables referral rewards.
+    pub fn set_referral_bps(ctx: Context<AdminConfig>, referral_bps: u16) -> Result<()> {
+        require!(
+            u64::from(referral_bps) <= BPS_DENOMINATOR,
+            GenomeError::InvalidFeeRate
+        );
+        ctx.accounts.config.referral_bps = referral_bps;
+
+        emit!(ReferralRateUpdated {
+            admin: ctx.accounts.admin.key(),
+            referral_bps,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Sets how long a genome's new owner must wait after a transfer before
+    /// listing it; zero disables the lock.
+    pub fn set_resale_lock(ctx: Context<AdminConfig>, resale_lock: i64) -> Result<()> {
+        require!(resale_lock >= 0, GenomeError::InvalidDuration);
+        ctx.accounts.config.resale_lock = resale_lock;
+
+        emit!(ResaleLockUpdated {
+            admin: ctx.accounts.admin.key(),
+            resale_lock,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the oldest client version the listing, purchase, offer and
+    /// registration instructions accept, forcing upgrades across breaking
+    /// changes. It may not pass the program's own version.
+    pub fn set_min_client_version(ctx: Context<AdminConfig>, min_client_version: u8) -> Result<()> {
+        require!(
+            min_client_version <= ctx.accounts.config.version,
+            GenomeError::InvalidClientVersion
+        );
+        ctx.accounts.config.min_client_version = min_client_version;
+
+        emit!(MinClientVersionUpdated {
+            admin: ctx.accounts.admin.key(),
+            min_client_version,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Sets how long a genome's last heartbeat keeps its listings
+    /// purchasable; zero disables the check.
+    pub fn set_heartbeat_ttl(ctx: Context<AdminConfig>, heartbeat_ttl: i64) -> Result<()> {
+        require!(heartbeat_ttl >= 0, GenomeError::InvalidDuration);
+        ctx.accounts.config.heartbeat_ttl = heartbeat_ttl;
+
+        emit!(HeartbeatTtlUpdated {
+            admin: ctx.accounts.admin.key(),
+            heartbeat_ttl,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the fee, in basis points of the converted amount, buyers pay the
+    /// treasury on top of USD-pegged purchases; zero disables it.
+    pub fn set_conversion_fee(ctx: Context<AdminConfig>, conversion_fee_bps: u16) -> Result<()> {
+        require!(
+            u64::from(conversion_fee_bps) <= BPS_DENOMINATOR,
+            GenomeError::InvalidFeeRate
+        );
+        ctx.accounts.config.conversion_fee_bps = conversion_fee_bps;
+
+        emit!(ConversionFeeUpdated {
+            admin: ctx.accounts.admin.key(),
+            conversion_fee_bps,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the share of remaining collateral, in basis points, paid to a
+    /// buyer for each lapse in a genome's heartbeat; zero disables it.
+    pub fn set_sla_slash_bps(ctx: Context<AdminConfig>, sla_slash_bps: u16) -> Result<()> {
+        require!(
+            u64::from(sla_slash_bps) <= BPS_DENOMINATOR,
+            GenomeError::InvalidFeeRate
+        );
+        ctx.accounts.config.sla_slash_bps = sla_slash_bps;
+
+        emit!(SlaSlashUpdated {
+            admin: ctx.accounts.admin.key(),
+            sla_slash_bps,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Sets how long, in seconds, a buyer refunded by a dispute must wait
+    /// before buying from the same seller again; zero disables it.
+    pub fn set_refund_cooldown(ctx: Context<AdminConfig>, refund_cooldown: i64) -> Result<()> {
+        require!(refund_cooldown >= 0, GenomeError::InvalidDuration);
+        ctx.accounts.config.refund_cooldown = refund_cooldown;
+
+        emit!(RefundCooldownUpdated {
+            admin: ctx.accounts.admin.key(),
+            refund_cooldown,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the half-life, in seconds, over which a seller's ratings lose
+    /// weight in their weighted reputation; zero disables decay.
+    pub fn set_reputation_half_life(
+        ctx: Context<AdminConfig>,
+        reputation_half_life: i64,
+    ) -> Result<()> {
+        require!(reputation_half_life >= 0, GenomeError::InvalidDuration);
+        ctx.accounts.config.reputation_half_life = reputation_half_life;
+
+        emit!(ReputationHalfLifeUpdated {
+            admin: ctx.accounts.admin.key(),
+            reputation_half_life,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Opens a window of `duration` seconds during which the admin may hand
+    /// accounts over to `new_program` with `migrate_to`.
+    pub fn open_upgrade_window(
+        ctx: Context<AdminConfig>,
+        new_program: Pubkey,
+        duration: i64,
+    ) -> Result<()> {
+        require!(duration > 0, GenomeError::InvalidDuration);
+        let now = unix_timestamp()?;
+        let config = &mut ctx.accounts.config;
+        config.upgrade_program = Some(new_program);
+        config.upgrade_window_ends = now.checked_add(duration).ok_or(GenomeError::MathOverflow)?;
+
+        emit!(UpgradeWindowOpened {
+            admin: ctx.accounts.admin.key(),
+            new_program,
+            ends_at: config.upgrade_window_ends,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the shortest and longest listing period, in seconds, that new
+    /// listings may run for.
+    pub fn set_listing_duration_bounds(
+        ctx: Context<AdminConfig>,
+        min_duration: i64,
+        max_duration: i64,
+    ) -> Result<()> {
+        require!(
+            min_duration > 0 && min_duration <= max_duration,
+            GenomeError::InvalidDuration
+        );
+
+        let config = &mut ctx.accounts.config;
+        config.min_duration = min_duration;
+        config.max_duration = max_duration;
+
+        emit!(ListingDurationBoundsUpdated {
+            admin: ctx.accounts.admin.key(),
+            min_duration,
+            max_duration,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Adds an SPL mint to those listings and offers may be paid in.
+    pub fn add_allowed_mint(ctx: Context<AdminConfig>, mint: Pubkey) -> Result<()> {
+        ctx.accounts.config.allow_mint(mint)?;
+
+        emit!(AllowedMintUpdated {
+            admin: ctx.accounts.admin.key(),
+            mint,
+            allowed: true,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Stops new listings and offers in `mint`; open ones are unaffected.
+    pub fn remove_allowed_mint(ctx: Context<AdminConfig>, mint: Pubkey) -> Result<()> {
+        ctx.accounts.config.disallow_mint(mint);
+
+        emit!(AllowedMintUpdated {
+            admin: ctx.accounts.admin.key(),
+            mint,
+            allowed: false,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    pub fn blacklist_seller(ctx: Context<BlacklistSeller>, seller: Pubkey) -> Result<()> {
+        let blacklist = &mut ctx.accounts.blacklist;
+        blacklist.bump = *ctx.bumps.get("blacklist").unwrap();
+        blacklist.add(seller)?;
+
+        emit!(BlacklistUpdated {
+            admin: ctx.accounts.admin.key(),
+            seller,
+            blacklisted: true,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    pub fn unblacklist_seller(ctx: Context<UnblacklistSeller>, seller: Pubkey) -> Result<()> {
+        ctx.accounts.blacklist.remove(seller);
+
+        emit!(BlacklistUpdated {
+            admin: ctx.accounts.admin.key(),
+            seller,
+            blacklisted: false,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Gives an approved nonprofit `grants` free licenses, each lasting
+    /// `access_duration`, replacing whatever quota it had left.
+    pub fn assign_grant_quota(
+        ctx: Context<AssignGrantQuota>,
+        holder: Pubkey,
+        grants: u32,
+        access_duration: i64,
+    ) -> Result<()> {
+        require!(access_duration > 0, GenomeError::InvalidDuration);
+
+        let grant_quota = &mut ctx.accounts.grant_quota;
+        grant_quota.holder = holder;
+        grant_quota.remaining = grants;
+        grant_quota.access_duration = access_duration;
+        grant_quota.bump = *ctx.bumps.get("grant_quota").unwrap();
+
+        emit!(GrantQuotaAssigned {
+            admin: ctx.accounts.admin.key(),
+            holder,
+            grants,
+            access_duration,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Registers a genome and records it on page `index_page` of the owner's
+    /// `UserIndex`.
     pub fn initialize_genome(
         ctx: Context<InitializeGenome>,
         storage_id: String,
+        index_page: u32,
         metadata: String,
+        metadata_schema_version: u16,
+        content_hash: [u8; 32],
+        royalty_bps: u16,
+        category: GenomeCategory,
+        tags: Vec<String>,
+        jurisdiction: Option<[u8; 2]>,
+        file_size_bytes: u64,
+        format: u8,
+        reference_build: u8,
+        preview_uri: String,
+        preview_hash: [u8; 32],
+        permitted_use: u8,
+        client_version: u8,
+        provenance: Vec<Accession>,
     ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        ctx.accounts.config.assert_client_version(client_version)?;
+
         let genome = &mut ctx.accounts.genome;
-        let user = &ctx.accounts.user;
+        genome.set_inner(Genome::register(
+            ctx.accounts.user.key(),
+            GenomeEntry {
+                storage_id,
+                metadata,
+                metadata_schema_version,
+                content_hash,
+                royalty_bps,
+                category,
+                tags,
+                jurisdiction,
+                file_size_bytes,
+                format,
+                reference_build,
+                preview_uri,
+                preview_hash,
+                permitted_use,
+                provenance,
+            },
+            unix_timestamp()?,
+            *ctx.bumps.get("genome").unwrap(),
+        )?);
 
-        genome.storage_id = storage_id;
-        genome.metadata = metadata;
-        genome.owner = user.key();
-        genome.created_at = Clock::get()?.unix_timestamp;
-        genome.deleted = false;
+        let user_index = &mut ctx.accounts.user_index;
+        user_index.open(
+            genome.owner,
+            index_page,
+            *ctx.bumps.get("user_index").unwrap(),
+            ctx.accounts.previous_index.as_deref(),
+        )?;
+        user_index.push(genome.key())?;
+        index_head(user_index, ctx.accounts.index_head.as_deref_mut())?
+            .record_genomes(1, ctx.accounts.config.max_genomes_per_user)?;
+        ctx.accounts.market_stats.record_genomes(1);
+
+        emit!(GenomeInitialized {
+            genome: genome.key(),
+            owner: genome.owner,
+            storage_id: genome.storage_id.clone(),
+            metadata: genome.metadata.clone(),
+            metadata_schema_version,
+            content_hash,
+            royalty_bps,
+            category,
+            tags: genome.tags.clone(),
+            jurisdiction,
+            file_size_bytes,
+            format,
+            reference_build,
+            preview_uri: genome.preview_uri.clone(),
+            preview_hash,
+            permitted_use,
+            timestamp: genome.created_at,
+        });
 
         Ok(())
     }
 
-    pub fn create_transaction(
-        ctx: Context<CreateTransaction>,
-        genome_id: String,
-        price: u64,
-        duration: i64,
+    /// Registers up to `MAX_BATCH_SIZE` genomes in one instruction. The
+    /// genome PDAs are passed as writable `remaining_accounts`, in the same
+    /// order as `entries`; any failure aborts the whole batch. All of them are
+    /// recorded on page `index_page` of the owner's `UserIndex`.
+    pub fn initialize_genomes_batch(
+        ctx: Context<InitializeGenomesBatch>,
+        index_page: u32,
+        entries: Vec<GenomeEntry>,
+        client_version: u8,
     ) -> Result<()> {
-        let transaction = &mut ctx.accounts.transaction;
+        ctx.accounts.config.assert_not_paused()?;
+        ctx.accounts.config.assert_client_version(client_version)?;
+        require!(
+            !entries.is_empty() && entries.len() <= MAX_BATCH_SIZE,
+            GenomeError::InvalidBatchSize
+        );
+        require!(
+            ctx.remaining_accounts.len() == entries.len(),
+            GenomeError::InvalidBatchAccounts
+        );
+
         let user = &ctx.accounts.user;
+        let now = unix_timestamp()?;
+        let space = 8 + Genome::INIT_SPACE;
+        let lamports = Rent::get()?.minimum_balance(space);
 
-        transaction.genome_id = genome_id;
-        transaction.seller = user.key();
-        transaction.price = price;
-        transaction.duration = duration;
-        transaction.status = TransactionStatus::Created;
-        transaction.created_at = Clock::get()?.unix_timestamp;
+        let user_index = &mut ctx.accounts.user_index;
+        user_index.open(
+            user.key(),
+            index_page,
+            *ctx.bumps.get("user_index").unwrap(),
+            ctx.accounts.previous_index.as_deref(),
+        )?;
+        index_head(user_index, ctx.accounts.index_head.as_deref_mut())?.record_genomes(
+            entries.len() as u32,
+            ctx.accounts.config.max_genomes_per_user,
+        )?;
+        ctx.accounts
+            .market_stats
+            .record_genomes(entries.len() as u64);
+
+        for (entry, account) in entries.into_iter().zip(ctx.remaining_accounts.iter()) {
+            let id_hash = hash(entry.storage_id.as_bytes()).to_bytes();
+            let (address, bump) = Pubkey::find_program_address(
+                &[GENOME_SEED, user.key().as_ref(), &id_hash],
+                ctx.program_id,
+            );
+            require_keys_eq!(account.key(), address, GenomeError::InvalidBatchAccounts);
+
+            let genome = Genome::register(user.key(), entry, now, bump)?;
+            system_program::create_account(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::CreateAccount {
+                        from: user.to_account_info(),
+                        to: account.clone(),
+                    },
+                    &[&[GENOME_SEED, user.key().as_ref(), &id_hash, &[bump]]],
+                ),
+                lamports,
+                space as u64,
+                ctx.program_id,
+            )?;
+            genome.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+            user_index.push(address)?;
+
+            emit!(GenomeInitialized {
+                genome: address,
+                owner: genome.owner,
+                storage_id: genome.storage_id,
+                metadata: genome.metadata,
+                metadata_schema_version: genome.metadata_schema_version,
+                content_hash: genome.content_hash,
+                royalty_bps: genome.royalty_bps,
+                category: genome.category,
+                tags: genome.tags,
+                jurisdiction: genome.jurisdiction,
+                file_size_bytes: genome.file_size_bytes,
+                format: genome.format,
+                reference_build: genome.reference_build,
+                preview_uri: genome.preview_uri,
+                preview_hash: genome.preview_hash,
+                permitted_use: genome.permitted_use,
+                timestamp: now,
+            });
+        }
 
         Ok(())
     }
 
-    pub fn execute_transaction(ctx: Context<ExecuteTransaction>) -> Result<()> {
-        let transaction = &mut ctx.accounts.transaction;
-        let buyer = &ctx.accounts.buyer;
+    /// Registers a corrected re-sequencing of `previous_genome` as a new
+    /// genome account and marks the predecessor superseded.
+    pub fn new_version(
+        ctx: Context<NewVersion>,
+        storage_id: String,
+        metadata: String,
+        content_hash: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
 
-        require!(
-            transaction.status == TransactionStatus::Created,
-            GenomeError::InvalidTransactionStatus
-        );
+        let previous_genome = &mut ctx.accounts.previous_genome;
+        let genome = &mut ctx.accounts.genome;
+        let now = unix_timestamp()?;
 
-        transaction.buyer = Some(buyer.key());
-        transaction.status = TransactionStatus::Executed;
-        transaction.executed_at = Some(Clock::get()?.unix_timestamp);
+        previous_genome.assert_live()?;
+        genome.set_inner(previous_genome.successor(
+            previous_genome.key(),
+            storage_id,
+            metadata,
+            content_hash,
+            now,
+            *ctx.bumps.get("genome").unwrap(),
+        )?);
+        previous_genome.superseded_by = Some(genome.key());
+        previous_genome.updated_at = now;
+
+        emit!(GenomeVersioned {
+            genome: genome.key(),
+            previous_version: previous_genome.key(),
+            owner: genome.owner,
+            version: genome.version,
+            content_hash,
+            timestamp: now,
+        });
 
         Ok(())
     }
 
-    pub fn cancel_transaction(ctx: Context<CancelTransaction>) -> Result<()> {
-        let transaction = &mut ctx.accounts.transaction;
-        
+    pub fn verify_hash(ctx: Context<VerifyHash>, hash: [u8; 32]) -> Result<()> {
         require!(
-            transaction.status == TransactionStatus::Created,
-            GenomeError::InvalidTransactionStatus
+            ctx.accounts.genome.content_hash == hash,
+            GenomeError::HashMismatch
         );
+        Ok(())
+    }
 
-        transaction.status = TransactionStatus::Cancelled;
+    pub fn delete_genome(ctx: Context<DeleteGenome>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let genome = &mut ctx.accounts.genome;
+
+        genome.assert_owner(ctx.accounts.authority.key())?;
+        genome.soft_delete()?;
+        genome.updated_at = unix_timestamp()?;
+
+        if let Some(user_index) = ctx.accounts.user_index.as_deref_mut() {
+            user_index.remove(&genome.key())?;
+            index_head(user_index, ctx.accounts.index_head.as_deref_mut())?.release_genome();
+        }
+
+        emit!(GenomeDeleted {
+            genome: genome.key(),
+            owner: genome.owner,
+            timestamp: genome.updated_at,
+        });
 
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-pub struct InitializeGenome<'info> {
-    #[account(init, payer = user, space = 1000)]
-    pub genome: Account<'info, Genome>,
-    #[account(mut)]
-    pub user: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    /// Closes the genome account and returns its rent to the owner. The
+    /// genome leaves the owner's index and the subscription pool on the way
+    /// out, and its unpaid pool share is paid to the owner.
+    pub fn close_genome(ctx: Context<CloseGenome>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
 
-#[derive(Accounts)]
-pub struct CreateTransaction<'info> {
-    #[account(init, payer = user, space = 1000)]
-    pub transaction: Account<'info, Transaction>,
-    #[account(mut)]
-    pub user: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+        let genome = &mut ctx.accounts.genome;
 
-#[derive(Accounts)]
-pub struct ExecuteTransaction<'info> {
-    #[account(mut)]
-    pub transaction: Account<'info, Transaction>,
-    pub buyer: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+        genome.assert_live()?;
+        genome.assert_closable()?;
 
-#[derive(Accounts)]
-pub struct CancelTransaction<'info> {
-    #[account(mut)]
-    pub transaction: Account<'info, Transaction>,
-    pub authority: Signer<'info>,
-}
+        if let Some(user_index) = ctx.accounts.user_index.as_deref_mut() {
+            user_index.remove(&genome.key())?;
+            index_head(user_index, ctx.accounts.index_head.as_deref_mut())?.release_genome();
+        }
 
-#[account]
-pub struct Genome {
-    pub storage_id: String,
-    pub metadata: String,
-    pub owner: Pubkey,
-    pub created_at: i64,
-    pub deleted: bool,
-}
+        if genome.subscription_pool {
+            let (Some(pool), Some(pool_vault)) =
+                (ctx.accounts.pool.as_mut(), ctx.accounts.pool_vault.as_ref())
+            else {
+                return err!(GenomeError::SubscriptionPoolMissing);
+            };
+            let paid_out = pool.leave(genome)?;
+            Escrow::for_pool(
+                pool,
+                &ctx.accounts.token_program,
+                &ctx.accounts.system_program,
+                pool_vault,
+            )
+            .pay(None, Some(ctx.accounts.owner.to_account_info()), paid_out)?;
+        }
 
-#[account]
-pub struct Transaction {
-    pub genome_id: String,
-    pub seller: Pubkey,
-    pub buyer: Option<Pubkey>,
-    pub price: u64,
-    pub duration: i64,
-    pub status: TransactionStatus,
-    pub created_at: i64,
-    pub executed_at: Option<i64>,
-}
+        emit!(GenomeClosed {
+            genome: genome.key(),
+            owner: genome.owner,
+            timestamp: unix_timestamp()?,
+        });
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
-pub enum TransactionStatus {
-    Created,
-    Executed,
-    Cancelled,
-}
+        Ok(())
+    }
 
-#[error_code]
+    /// Resizes a genome account created with the old fixed `space = 1000` to
+    /// its exact size. Freed rent is refunded to the owner; if the layout has
+    /// since outgrown the account, the owner pays for the extra space.
+    pub fn migrate_genome(ctx: Context<MigrateGenome>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let genome = &ctx.accounts.genome;
+        let info = genome.to_account_info();
+        let old_len = info.data_len();
+        let new_len = migration_target(old_len, 8 + genome.try_to_vec()?.len())?;
+        let rent = Rent::get()?.minimum_balance(new_len);
+
+        let rent_paid = rent.saturating_sub(info.lamports());
+        if rent_paid > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.owner.to_account_info(),
+                        to: info.clone(),
+                    },
+                ),
+                rent_paid,
+            )?;
+        }
+        info.realloc(new_len, false)?;
+        let refunded = info.lamports().saturating_sub(rent);
+        **info.try_borrow_mut_lamports()? -= refunded;
+        **ctx.accounts.owner.try_borrow_mut_lamports()? += refunded;
+
+        emit!(GenomeMigrated {
+            genome: genome.key(),
+            owner: genome.owner,
+            old_len: old_len as u32,
+            new_len: new_len as u32,
+            refunded,
+            rent_paid,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Replaces the metadata and, when supplied, its schema version, the
+    /// category and tags.
+    pub fn update_metadata(
+        ctx: Context<UpdateMetadata>,
+        new_metadata: String,
+        metadata_schema_version: Option<u16>,
+        category: Option<GenomeCategory>,
+        tags: Option<Vec<String>>,
+        preview_uri: Option<String>,
+        preview_hash: Option<[u8; 32]>,
+    ) -> Result<()> {
+        patch_genome(
+            ctx,
+            GenomePatch {
+                metadata: Some(new_metadata),
+                metadata_schema_version,
+                category,
+                tags,
+                preview_uri,
+                preview_hash,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Updates any of a genome's descriptive fields in one instruction;
+    /// `None` leaves a field unchanged.
+    pub fn update_genome(ctx: Context<UpdateMetadata>, patch: GenomePatch) -> Result<()> {
+        patch_genome(ctx, patch)
+    }
+
+    /// Puts the genome under embargo until `embargo_until`. The metadata is
+    /// wiped from the account and only `metadata_hash` is kept, so nothing
+    /// about the dataset can be read until the owner reveals it.
+    pub fn seal_metadata(
+        ctx: Context<UpdateMetadata>,
+        embargo_until: i64,
+        metadata_hash: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let genome = &mut ctx.accounts.genome;
+        let now = unix_timestamp()?;
+
+        genome.assert_owner(ctx.accounts.authority.key())?;
+        genome.assert_live()?;
+        require!(!genome.listing_active(), GenomeError::AlreadyListed);
+        genome.seal(embargo_until, metadata_hash, now)?;
+        genome.updated_at = now;
+
+        emit!(MetadataSealed {
+            genome: genome.key(),
+            owner: genome.owner,
+            embargo_until,
+            metadata_hash,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Reveals sealed metadata, which must match the hash committed to at
+    /// sealing. The owner may lift the embargo early; once it has run out
+    /// anyone holding the metadata may publish it.
+    pub fn lift_embargo(ctx: Context<UpdateMetadata>, metadata: String) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let genome = &mut ctx.accounts.genome;
+        let authority = ctx.accounts.authority.key();
+        let now = unix_timestamp()?;
+
+        if genome.embargoed(now) {
+            genome.assert_owner(authority)?;
+        }
+        genome.unseal(metadata)?;
+        genome.updated_at = now;
+
+        emit!(EmbargoLifted {
+            genome: genome.key(),
+            authority,
+            metadata: genome.metadata.clone(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Attests that the genome's off-chain data is still hosted.
+    pub fn heartbeat(ctx: Context<UpdateMetadata>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let genome = &mut ctx.accounts.genome;
+        genome.assert_owner(ctx.accounts.authority.key())?;
+        genome.assert_live()?;
+        genome.last_heartbeat = unix_timestamp()?;
+
+        emit!(HeartbeatRecorded {
+            genome: genome.key(),
+            owner: genome.owner,
+            timestamp: genome.last_heartbeat,
+        });
+
+        Ok(())
+    }
+
+    /// Records further public database accessions the genome derives from.
+    /// Existing entries cannot be edited or removed.
+    pub fn add_provenance(ctx: Context<UpdateMetadata>, accessions: Vec<Accession>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let genome = &mut ctx.accounts.genome;
+        genome.assert_owner(ctx.accounts.authority.key())?;
+        genome.assert_live()?;
+        genome.add_provenance(accessions.clone())?;
+        genome.updated_at = unix_timestamp()?;
+
+        emit!(ProvenanceAdded {
+            genome: genome.key(),
+            owner: genome.owner,
+            accessions,
+            timestamp: genome.updated_at,
+        });
+
+        Ok(())
+    }
+
+    pub fn transfer_ownership(ctx: Context<TransferOwnership>, new_owner: Pubkey) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let genome = &mut ctx.accounts.genome;
+
+        genome.authorize(
+            ctx.accounts.authority.key(),
+            ctx.accounts.multisig.as_deref_mut(),
+            MultisigAction::TransferOwnership { new_owner },
+        )?;
+        genome.assert_live()?;
+
+        let previous_owner = genome.owner;
+        genome.transfer_to(new_owner)?;
+        genome.updated_at = unix_timestamp()?;
+        genome.acquired_at = genome.updated_at;
+
+        emit!(OwnershipTransferred {
+            genome: genome.key(),
+            previous_owner,
+            new_owner,
+            timestamp: genome.updated_at,
+        });
+
+        Ok(())
+    }
+
+    /// Lets `delegate`, e.g. a marketplace agent, list the genome on the
+    /// owner's behalf; `None` removes the delegate. Delegates cannot transfer
+    /// or delete the genome.
+    pub fn set_delegate(ctx: Context<SetDelegate>, delegate: Option<Pubkey>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let genome = &mut ctx.accounts.genome;
+
+        genome.authorize(
+            ctx.accounts.authority.key(),
+            ctx.accounts.multisig.as_deref_mut(),
+            MultisigAction::SetDelegate { delegate },
+        )?;
+        genome.assert_live()?;
+
+        genome.delegate = delegate;
+        genome.updated_at = unix_timestamp()?;
+
+        emit!(DelegateUpdated {
+            genome: genome.key(),
+            owner: genome.owner,
+            delegate,
+            timestamp: genome.updated_at,
+        });
+
+        Ok(())
+    }
+
+    /// Names the key that may recover the genome if the owner loses
+    /// theirs; `None` turns recovery off. Any recovery in progress is
+    /// cancelled.
+    pub fn set_recovery_authority(
+        ctx: Context<UpdateMetadata>,
+        recovery_authority: Option<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let genome = &mut ctx.accounts.genome;
+        genome.assert_owner(ctx.accounts.authority.key())?;
+        genome.assert_live()?;
+        genome.recovery_authority = recovery_authority;
+        genome.pending_recovery = None;
+        genome.updated_at = unix_timestamp()?;
+
+        emit!(RecoveryAuthorityUpdated {
+            genome: genome.key(),
+            owner: genome.owner,
+            recovery_authority,
+            timestamp: genome.updated_at,
+        });
+
+        Ok(())
+    }
+
+    /// First step of recovering a genome whose owner lost their key: the
+    /// recovery authority proposes `new_owner`, who takes over once
+    /// `RECOVERY_TIMELOCK` passes unless the owner cancels first.
+    pub fn initiate_recovery(ctx: Context<RecoverGenome>, new_owner: Pubkey) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let genome = &mut ctx.accounts.genome;
+        let now = unix_timestamp()?;
+
+        genome.assert_live()?;
+        genome.initiate_recovery(ctx.accounts.recovery_authority.key(), new_owner, now)?;
+
+        emit!(RecoveryInitiated {
+            genome: genome.key(),
+            owner: genome.owner,
+            recovery_authority: ctx.accounts.recovery_authority.key(),
+            new_owner,
+            unlocks_at: now + RECOVERY_TIMELOCK,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn finalize_recovery(ctx: Context<RecoverGenome>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let genome = &mut ctx.accounts.genome;
+        let now = unix_timestamp()?;
+
+        genome.assert_live()?;
+        let previous_owner =
+            genome.finalize_recovery(ctx.accounts.recovery_authority.key(), now)?;
+        genome.updated_at = now;
+        genome.acquired_at = now;
+
+        emit!(OwnershipTransferred {
+            genome: genome.key(),
+            previous_owner,
+            new_owner: genome.owner,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Lets the owner stop a recovery they did not ask for.
+    pub fn cancel_recovery(ctx: Context<UpdateMetadata>) -> Result<()> {
+        let genome = &mut ctx.accounts.genome;
+        genome.assert_owner(ctx.accounts.authority.key())?;
+        let recovery = genome.cancel_recovery()?;
+
+        emit!(RecoveryCancelled {
+            genome: genome.key(),
+            owner: genome.owner,
+            new_owner: recovery.new_owner,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Splits the seller's proceeds from every sale of the genome among
+    /// co-owners by basis-point share. Shares must total `BPS_DENOMINATOR`;
+    /// an empty `shares` removes the split.
+    pub fn set_payout_split(ctx: Context<SetPayoutSplit>, shares: Vec<PayoutShare>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let genome = &mut ctx.accounts.genome;
+
+        genome.authorize(
+            ctx.accounts.authority.key(),
+            ctx.accounts.multisig.as_deref_mut(),
+            MultisigAction::SetPayoutSplit {
+                shares_hash: hash(&shares.try_to_vec()?).to_bytes(),
+            },
+        )?;
+        genome.assert_live()?;
+
+        let payout_split = &mut ctx.accounts.payout_split;
+        if shares.is_empty() {
+            genome.payout_split = None;
+        } else {
+            validate_payout_shares(&shares)?;
+            genome.payout_split = Some(payout_split.key());
+        }
+        payout_split.genome = genome.key();
+        payout_split.shares = shares;
+        payout_split.bump = *ctx.bumps.get("payout_split").unwrap();
+        genome.updated_at = unix_timestamp()?;
+
+        emit!(PayoutSplitUpdated {
+            genome: genome.key(),
+            owner: genome.owner,
+            shares: payout_split.shares.clone(),
+            timestamp: genome.updated_at,
+        });
+
+        Ok(())
+    }
+
+    /// Puts a genome under M-of-N governance. From then on listing and
+    /// transferring it need `threshold` approvals via `approve_action`.
+    pub fn create_multisig(
+        ctx: Context<CreateMultisig>,
+        owners: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let genome = &mut ctx.accounts.genome;
+        require!(genome.multisig.is_none(), GenomeError::MultisigMismatch);
+
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.set_inner(MultisigOwner::new(
+            genome.key(),
+            owners,
+            threshold,
+            *ctx.bumps.get("multisig").unwrap(),
+        )?);
+        genome.multisig = Some(multisig.key());
+
+        emit!(MultisigCreated {
+            multisig: multisig.key(),
+            genome: genome.key(),
+            owners: multisig.owners.clone(),
+            threshold,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    pub fn approve_action(ctx: Context<ApproveAction>, action: MultisigAction) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let multisig = &mut ctx.accounts.multisig;
+        let approver = ctx.accounts.approver.key();
+        multisig.approve(approver, action.clone())?;
+
+        emit!(ActionApproved {
+            multisig: multisig.key(),
+            genome: multisig.genome,
+            approver,
+            action,
+            approvals: multisig.approvals.len() as u8,
+            threshold: multisig.threshold,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Opens page `page` of the catalog of open listings in `category`.
+    /// Anyone may pay for a page; a later page can only be opened once the
+    /// one before it is full.
+    pub fn open_category_page(
+        ctx: Context<OpenCategoryPage>,
+        category: GenomeCategory,
+        page: u32,
+    ) -> Result<()> {
+        ctx.accounts.category_index.open(
+            category,
+            page,
+            *ctx.bumps.get("category_index").unwrap(),
+            ctx.accounts.previous_page.as_deref(),
+        )
+    }
+
+    /// Lists a genome. Clients pick a fresh `nonce` for each listing they
+    /// intend and reuse it when retrying: a retry of a call that already
+    /// landed fails with Anchor's account-in-use error rather than opening
+    /// a duplicate listing.
+    pub fn create_transaction(
+        ctx: Context<CreateTransaction>,
+        nonce: u64,
+        genome_id: String,
+        terms: ListingTerms,
+        client_version: u8,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_client_version(client_version)?;
+        create_listing(ctx, nonce, genome_id, Vec::new(), terms)
+    }
+
+    /// Lists several genomes of one seller as a single bundle sold for
+    /// `terms.price`. `genome_ids[0]` is the `genome` account; the rest are
+    /// passed as `remaining_accounts` in the same order.
+    pub fn create_bundle_transaction(
+        ctx: Context<CreateTransaction>,
+        nonce: u64,
+        genome_ids: Vec<String>,
+        terms: ListingTerms,
+        client_version: u8,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_client_version(client_version)?;
+        validate_bundle(&genome_ids)?;
+        let genome_id = genome_ids[0].clone();
+        create_listing(ctx, nonce, genome_id, genome_ids, terms)
+    }
+
+    /// Opens a new listing for the genome of a cancelled or expired one,
+    /// carrying its restrictions forward with a new price and access
+    /// duration. The listing period keeps its original length from now.
+    pub fn relist_transaction(
+        ctx: Context<RelistTransaction>,
+        nonce: u64,
+        new_price: u64,
+        new_duration: i64,
+        client_version: u8,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        ctx.accounts.config.assert_client_version(client_version)?;
+
+        let previous = &ctx.accounts.previous_transaction;
+        let genome = &mut ctx.accounts.genome;
+        let transaction = &mut ctx.accounts.transaction;
+        let seller = &ctx.accounts.seller;
+        let now = unix_timestamp()?;
+
+        let terms = previous.relisting_terms(new_price, new_duration, now)?;
+        terms.validate(now)?;
+        ctx.accounts
+            .config
+            .assert_listing_duration(terms.listing_expires_at - now)?;
+        genome.assert_live()?;
+        genome.assert_resale_unlocked(ctx.accounts.config.resale_lock, now)?;
+        genome.assert_not_embargoed(now)?;
+        genome.assert_consent()?;
+        ctx.accounts.blacklist.assert_allowed(&genome.owner)?;
+        genome.authorize_listing(
+            seller.key(),
+            ctx.accounts.multisig.as_deref_mut(),
+            MultisigAction::CreateTransaction {
+                price: terms.price,
+                access_duration: terms.access_duration,
+            },
+        )?;
+
+        genome.open_listing()?;
+
+        let seller_profile = &mut ctx.accounts.seller_profile;
+        seller_profile.open(genome.owner, *ctx.bumps.get("seller_profile").unwrap());
+        seller_profile.start_listing(now, ctx.accounts.config.listing_cooldown)?;
+        seller_profile.record_listing()?;
+        ctx.accounts.market_stats.record_listing();
+
+        ctx.accounts.category_index.push(transaction.key())?;
+        transaction.category_index = ctx.accounts.category_index.key();
+        transaction.genome_id = previous.genome_id.clone();
+        transaction.genome_ids = previous.genome_ids.clone();
+        transaction.genome = genome.key();
+        transaction.seller = genome.owner;
+        transaction.nonce = nonce;
+        transaction.bump = *ctx.bumps.get("transaction").unwrap();
+        transaction.apply_terms(terms);
+        transaction.whitelist = previous.whitelist;
+        transaction.relist_count = previous
+            .relist_count
+            .checked_add(1)
+            .ok_or(GenomeError::MathOverflow)?;
+        transaction.original_listing = Some(previous.original_listing.unwrap_or(previous.key()));
+        transaction.set_status(TransactionStatus::Created, now);
+        transaction.created_at = now;
+        transaction.payment_mint = resolve_payment_mint(
+            ctx.accounts.mint.as_ref().map(|mint| mint.key()),
+            ctx.accounts.escrow_vault.is_some(),
+            ctx.accounts.sol_vault.is_some(),
+        )?;
+        ctx.accounts
+            .config
+            .assert_mint_allowed(transaction.payment_mint)?;
+        require!(
+            transaction.payment_mint == previous.payment_mint,
+            GenomeError::MintMismatch
+        );
+        transaction.payment_decimals = ctx
+            .accounts
+            .mint
+            .as_ref()
+            .map_or(SOL_DECIMALS, |mint| mint.decimals);
+        transaction.escrow_bump = match transaction.payment_mint {
+            Some(_) => *ctx.bumps.get("escrow_vault").unwrap(),
+            None => *ctx.bumps.get("sol_vault").unwrap(),
+        };
+
+        let escrow = Escrow::new(
+            transaction,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        );
+        escrow.fund_rent(seller)?;
+        transaction.collateral_amount =
+            transaction.required_collateral(ctx.accounts.config.collateral_bps)?;
+        if transaction.collateral_amount > 0 {
+            escrow.collect(
+                seller,
+                ctx.accounts.seller_token_account.as_ref(),
+                transaction.collateral_amount,
+            )?;
+        }
+
+        emit!(Relisted {
+            transaction: transaction.key(),
+            previous_listing: previous.key(),
+            original_listing: transaction.original_listing.unwrap(),
+            genome: transaction.genome,
+            seller: transaction.seller,
+            relist_count: transaction.relist_count,
+            old_price: previous.price,
+            new_price: transaction.price,
+            old_access_duration: previous.access_duration,
+            new_access_duration: transaction.access_duration,
+            listing_expires_at: transaction.listing_expires_at,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_price(ctx: Context<UpdatePrice>, new_price: u64) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let transaction = &mut ctx.accounts.transaction;
+
+        transaction.assert_seller(ctx.accounts.authority.key())?;
+        require!(
+            transaction.status == TransactionStatus::Created,
+            GenomeError::InvalidTransactionStatus
+        );
+        require!(transaction.auction.is_none(), GenomeError::AuctionListing);
+        require!(
+            transaction.depositor.is_none(),
+            GenomeError::EscrowAlreadyFunded
+        );
+        require!(new_price > 0, GenomeError::InvalidPrice);
+
+        let old_price = transaction.price;
+        transaction.price = new_price;
+
+        emit!(PriceUpdated {
+            transaction: transaction.key(),
+            seller: transaction.seller,
+            old_price,
+            new_price,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Replaces the seller's notes on a listing that has not sold yet.
+    pub fn update_listing_notes(
+        ctx: Context<UpdateListingNotes>,
+        listing_notes: String,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let transaction = &mut ctx.accounts.transaction;
+
+        transaction.assert_seller(ctx.accounts.authority.key())?;
+        require!(
+            transaction.status == TransactionStatus::Created,
+            GenomeError::InvalidTransactionStatus
+        );
+        validate_len(&listing_notes, MAX_LISTING_NOTES_LEN)?;
+        transaction.listing_notes = listing_notes;
+
+        emit!(ListingNotesUpdated {
+            transaction: transaction.key(),
+            seller: transaction.seller,
+            listing_notes: transaction.listing_notes.clone(),
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Keeps an open listing available for `additional_duration` more
+    /// seconds, within the configured maximum listing period.
+    pub fn extend_listing(ctx: Context<ExtendListing>, additional_duration: i64) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let transaction = &mut ctx.accounts.transaction;
+        let now = unix_timestamp()?;
+
+        transaction.assert_seller(ctx.accounts.authority.key())?;
+        require!(
+            transaction.status == TransactionStatus::Created,
+            GenomeError::InvalidTransactionStatus
+        );
+        require!(transaction.auction.is_none(), GenomeError::AuctionListing);
+        require!(transaction.listing_open(now), GenomeError::ListingExpired);
+
+        let old_expires_at = transaction.listing_expires_at;
+        transaction.extend_listing(additional_duration, ctx.accounts.config.max_duration)?;
+
+        emit!(ListingExtended {
+            transaction: transaction.key(),
+            seller: transaction.seller,
+            old_expires_at,
+            new_expires_at: transaction.listing_expires_at,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Reopens an expired auto-relisting listing for another period of the
+    /// same length at the same price. Callable by anyone, up to
+    /// `MAX_AUTO_RELISTS` times per listing.
+    pub fn crank_relist(ctx: Context<CrankRelist>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let transaction = &mut ctx.accounts.transaction;
+        let now = unix_timestamp()?;
+
+        transaction.auto_relist(now)?;
+
+        emit!(RelistedByKeeper {
+            transaction: transaction.key(),
+            seller: transaction.seller,
+            keeper: ctx.accounts.keeper.key(),
+            auto_relists: transaction.auto_relists,
+            listing_expires_at: transaction.listing_expires_at,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn deposit(ctx: Context<Deposit>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let transaction = &mut ctx.accounts.transaction;
+        let buyer = &ctx.accounts.buyer;
+
+        require!(
+            transaction.status == TransactionStatus::Created,
+            GenomeError::InvalidTransactionStatus
+        );
+        require!(
+            transaction.depositor.is_none(),
+            GenomeError::EscrowAlreadyFunded
+        );
+        require!(transaction.auction.is_none(), GenomeError::AuctionListing);
+        require!(
+            transaction.listing_open(unix_timestamp()?),
+            GenomeError::ListingExpired
+        );
+        transaction.assert_buyer_allowed(buyer.key(), ctx.accounts.whitelist.as_deref())?;
+        transaction.assert_token_gate(ctx.accounts.gate_token_account.as_deref())?;
+
+        Escrow::new(
+            transaction,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        )
+        .collect(
+            buyer,
+            ctx.accounts.buyer_token_account.as_ref(),
+            transaction.price,
+        )?;
+
+        transaction.depositor = Some(buyer.key());
+
+        emit!(EscrowDeposited {
+            transaction: transaction.key(),
+            buyer: buyer.key(),
+            amount: transaction.price,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    pub fn place_bid(ctx: Context<PlaceBid>, amount: u64) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let transaction = &mut ctx.accounts.transaction;
+        let bidder = &ctx.accounts.bidder;
+        let now = unix_timestamp()?;
+
+        require!(
+            transaction.status == TransactionStatus::Created,
+            GenomeError::InvalidTransactionStatus
+        );
+        transaction.assert_buyer_allowed(bidder.key(), ctx.accounts.whitelist.as_deref())?;
+        transaction.assert_token_gate(ctx.accounts.gate_token_account.as_deref())?;
+        let auction = transaction
+            .auction
+            .clone()
+            .ok_or(GenomeError::NotAnAuction)?;
+        require!(auction.reveal_ends_at.is_none(), GenomeError::SealedAuction);
+        require!(transaction.listing_open(now), GenomeError::AuctionEnded);
+        require!(amount >= transaction.minimum_bid()?, GenomeError::BidTooLow);
+
+        // Each bidder's escrowed total lives in their `Bid` account; raising
+        // a bid only collects the difference. Outbid funds are reclaimed with
+        // `withdraw_bid` rather than pushed back here, so a bidder cannot
+        // block outbids by closing their refund account.
+        let bid = &mut ctx.accounts.bid;
+        bid.transaction = transaction.key();
+        bid.bidder = bidder.key();
+        bid.bump = *ctx.bumps.get("bid").unwrap();
+        let top_up = bid.raise_to(amount)?;
+
+        Escrow::new(
+            transaction,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        )
+        .collect(bidder, ctx.accounts.bidder_token_account.as_ref(), top_up)?;
+
+        let previous_bidder = transaction.depositor;
+        transaction.price = amount;
+        transaction.depositor = Some(bidder.key());
+        transaction.auction = Some(Auction {
+            bid_count: auction.bid_count.saturating_add(1),
+            ..auction
+        });
+
+        emit!(BidPlaced {
+            transaction: transaction.key(),
+            bidder: bidder.key(),
+            amount,
+            previous_bidder,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Returns an outbid bidder's escrowed funds. The current top bidder
+    /// cannot withdraw; their bid pays the seller if the auction executes,
+    /// and is released if the seller cancels.
+    pub fn withdraw_bid(ctx: Context<WithdrawBid>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let transaction = &ctx.accounts.transaction;
+        let bid = &ctx.accounts.bid;
+
+        transaction.assert_live()?;
+        bid.assert_withdrawable(transaction)?;
+
+        Escrow::new(
+            transaction,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        )
+        .pay(
+            ctx.accounts.bidder_token_account.as_ref(),
+            Some(ctx.accounts.bidder.to_account_info()),
+            bid.amount,
+        )?;
+
+        emit!(BidWithdrawn {
+            transaction: transaction.key(),
+            bidder: bid.bidder,
+            amount: bid.amount,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Enters a sealed bid: only `bid_commitment(amount, nonce)` is stored
+    /// and nothing is escrowed until the bid is revealed. Committing again
+    /// before bidding closes replaces the earlier commitment.
+    pub fn commit_bid(ctx: Context<CommitBid>, commitment: [u8; 32]) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let transaction = &ctx.accounts.transaction;
+        let bidder = &ctx.accounts.bidder;
+        let now = unix_timestamp()?;
+
+        require!(
+            transaction.status == TransactionStatus::Created,
+            GenomeError::InvalidTransactionStatus
+        );
+        transaction.assert_buyer_allowed(bidder.key(), ctx.accounts.whitelist.as_deref())?;
+        transaction.assert_token_gate(ctx.accounts.gate_token_account.as_deref())?;
+        let auction = transaction
+            .auction
+            .as_ref()
+            .ok_or(GenomeError::NotAnAuction)?;
+        require!(
+            auction.reveal_ends_at.is_some(),
+            GenomeError::NotSealedAuction
+        );
+        require!(transaction.listing_open(now), GenomeError::AuctionEnded);
+
+        let bid = &mut ctx.accounts.bid;
+        bid.transaction = transaction.key();
+        bid.bidder = bidder.key();
+        bid.bump = *ctx.bumps.get("bid").unwrap();
+        bid.commitment = Some(commitment);
+
+        emit!(BidCommitted {
+            transaction: transaction.key(),
+            bidder: bidder.key(),
+            commitment,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Opens a sealed bid once bidding has closed and escrows its amount.
+    /// A revealed bid must beat the top revealed bid like an open bid would;
+    /// bids never revealed cannot win and can be closed with `withdraw_bid`.
+    pub fn reveal_bid(ctx: Context<RevealBid>, amount: u64, nonce: [u8; 32]) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let transaction = &mut ctx.accounts.transaction;
+        let bidder = &ctx.accounts.bidder;
+        let now = unix_timestamp()?;
+
+        require!(
+            transaction.status == TransactionStatus::Created,
+            GenomeError::InvalidTransactionStatus
+        );
+        transaction.assert_reveal_open(now)?;
+        require!(amount >= transaction.minimum_bid()?, GenomeError::BidTooLow);
+        ctx.accounts.bid.reveal(amount, &nonce)?;
+
+        Escrow::new(
+            transaction,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        )
+        .collect(bidder, ctx.accounts.bidder_token_account.as_ref(), amount)?;
+
+        let previous_bidder = transaction.depositor;
+        transaction.price = amount;
+        transaction.depositor = Some(bidder.key());
+        if let Some(auction) = transaction.auction.as_mut() {
+            auction.bid_count = auction.bid_count.saturating_add(1);
+        }
+
+        emit!(BidRevealed {
+            transaction: transaction.key(),
+            bidder: bidder.key(),
+            amount,
+            previous_bidder,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// `terms_hash` must match the listing's license terms and is recorded on
+    /// the access grant as the buyer's acknowledgment of them, together with
+    /// the jurisdiction the buyer declares they are purchasing from.
+    pub fn execute_transaction(
+        ctx: Context<ExecuteTransaction>,
+        terms_hash: [u8; 32],
+        buyer_jurisdiction: [u8; 2],
+        history_page: u32,
+        declared_use: u8,
+        quantity: u64,
+        referrer: Option<Pubkey>,
+        client_version: u8,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_client_version(client_version)?;
+        execute_sale(
+            ctx,
+            terms_hash,
+            buyer_jurisdiction,
+            history_page,
+            declared_use,
+            quantity,
+            referrer,
+            false,
+        )
+    }
+
+    /// First phase of a two-phase purchase: like `execute_transaction`, but
+    /// the payment stays in escrow with the transaction `Locked` until the
+    /// buyer calls `confirm_receipt`, or the seller claims it once the
+    /// dispute window passes unconfirmed. Exclusive listings only.
+    pub fn lock_transaction(
+        ctx: Context<ExecuteTransaction>,
+        terms_hash: [u8; 32],
+        buyer_jurisdiction: [u8; 2],
+        history_page: u32,
+        declared_use: u8,
+        quantity: u64,
+        referrer: Option<Pubkey>,
+        client_version: u8,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_client_version(client_version)?;
+        execute_sale(
+            ctx,
+            terms_hash,
+            buyer_jurisdiction,
+            history_page,
+            declared_use,
+            quantity,
+            referrer,
+            true,
+        )
+    }
+
+    /// Second phase of a two-phase purchase: the buyer confirms the data was
+    /// received, releasing the escrowed payment to the seller.
+    pub fn confirm_receipt(ctx: Context<ConfirmReceipt>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let transaction = &mut ctx.accounts.transaction;
+        let now = unix_timestamp()?;
+
+        require!(
+            transaction.buyer == Some(ctx.accounts.buyer.key()),
+            GenomeError::Unauthorized
+        );
+        require!(
+            transaction.status == TransactionStatus::Locked,
+            GenomeError::InvalidTransactionStatus
+        );
+
+        let split = SaleSplit::for_sale(&ctx.accounts.config, &ctx.accounts.genome, transaction)?
+            .with_referral(transaction.referrer, ctx.accounts.config.referral_bps)?;
+        let collateral = transaction.release_collateral();
+        let escrow = Escrow::new(
+            transaction,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        );
+        settle_sale(
+            &escrow,
+            sale_payees!(
+                ctx.accounts,
+                ctx.remaining_accounts,
+                referral_payee!(ctx.accounts, transaction.referrer)
+            ),
+            &split,
+            collateral,
+        )?;
+        transaction.set_status(TransactionStatus::Executed, now);
+        transaction.settled = true;
+        ctx.accounts.genome.record_settlement();
+
+        emit!(ReceiptConfirmed {
+            transaction: transaction.key(),
+            buyer: ctx.accounts.buyer.key(),
+            seller: transaction.seller,
+            amount: split.seller_amount,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn open_dispute(ctx: Context<OpenDispute>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let transaction = &mut ctx.accounts.transaction;
+        let buyer = &ctx.accounts.buyer;
+        let now = unix_timestamp()?;
+
+        require!(
+            transaction.buyer == Some(buyer.key()),
+            GenomeError::Unauthorized
+        );
+        require!(
+            transaction.awaiting_settlement(),
+            GenomeError::InvalidTransactionStatus
+        );
+        require!(
+            now <= transaction.dispute_deadline(ctx.accounts.config.dispute_window)?,
+            GenomeError::DisputeWindowClosed
+        );
+
+        transaction.set_status(TransactionStatus::Disputed, now);
+
+        emit!(DisputeOpened {
+            transaction: transaction.key(),
+            buyer: buyer.key(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Compensates a licensed buyer out of the seller's collateral when the
+    /// genome's heartbeat has lapsed, once per lapse. Only collateral still
+    /// held in escrow, i.e. before the sale settles, can be claimed.
+    pub fn claim_sla_breach(ctx: Context<ClaimSlaBreach>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let transaction = &mut ctx.accounts.transaction;
+        let buyer = &ctx.accounts.buyer;
+        let now = unix_timestamp()?;
+
+        transaction.assert_live()?;
+        ctx.accounts.genome.assert_live()?;
+        ctx.accounts.access_grant.assert_active(now)?;
+        let (amount, breached_at) = transaction.slash_for_breach(
+            ctx.accounts.genome.last_heartbeat,
+            ctx.accounts.config.heartbeat_ttl,
+            ctx.accounts.config.sla_slash_bps,
+            now,
+        )?;
+        Escrow::new(
+            transaction,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        )
+        .pay(
+            ctx.accounts.buyer_token_account.as_ref(),
+            Some(buyer.to_account_info()),
+            amount,
+        )?;
+
+        emit!(SlaBreachClaimed {
+            transaction: transaction.key(),
+            genome: transaction.genome,
+            buyer: buyer.key(),
+            amount,
+            breached_at,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Rules on a disputed sale. On a refund, `remaining_accounts` holds the
+    /// buyer's grant for each further genome of a bundle, in listing order,
+    /// and every one is revoked; otherwise any payout split's recipients.
+    pub fn resolve_dispute(ctx: Context<ResolveDispute>, refund_buyer: bool) -> Result<()> {
+        let transaction = &mut ctx.accounts.transaction;
+        let now = unix_timestamp()?;
+
+        require!(
+            transaction.status == TransactionStatus::Disputed,
+            GenomeError::InvalidTransactionStatus
+        );
+
+        // The seller's collateral follows the ruling: slashed to the buyer on
+        // a refund, returned to the seller otherwise.
+        let collateral = transaction.release_collateral();
+        let escrow = Escrow::new(
+            transaction,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        );
+        if refund_buyer {
+            escrow.pay(
+                ctx.accounts.buyer_token_account.as_ref(),
+                ctx.accounts.buyer.as_ref().map(|b| b.to_account_info()),
+                transaction
+                    .price
+                    .checked_add(collateral)
+                    .ok_or(GenomeError::MathOverflow)?,
+            )?;
+            ctx.accounts.access_grant.revoked = true;
+            revoke_bundle_grants(
+                transaction,
+                ctx.accounts.access_grant.buyer,
+                ctx.remaining_accounts,
+                ctx.program_id,
+            )?;
+            ctx.accounts.refund_record.record_refund(
+                ctx.accounts.access_grant.buyer,
+                transaction.seller,
+                now,
+                *ctx.bumps.get("refund_record").unwrap(),
+            );
+            transaction.set_status(TransactionStatus::Refunded, now);
+        } else {
+            let split =
+                SaleSplit::for_sale(&ctx.accounts.config, &ctx.accounts.genome, transaction)?
+                    .with_referral(transaction.referrer, ctx.accounts.config.referral_bps)?;
+            settle_sale(
+                &escrow,
+                sale_payees!(
+                    ctx.accounts,
+                    ctx.remaining_accounts,
+                    referral_payee!(ctx.accounts, transaction.referrer)
+                ),
+                &split,
+                collateral,
+            )?;
+            transaction.set_status(TransactionStatus::Executed, now);
+        }
+        transaction.settled = true;
+        ctx.accounts.genome.record_settlement();
+
+        emit!(DisputeResolved {
+            transaction: transaction.key(),
+            arbitrator: ctx.accounts.arbitrator.key(),
+            refund_buyer,
+            amount: transaction.price,
+            collateral,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn claim_settlement(ctx: Context<ClaimSettlement>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let transaction = &mut ctx.accounts.transaction;
+        let now = unix_timestamp()?;
+
+        transaction.assert_seller(ctx.accounts.authority.key())?;
+        transaction.assert_live()?;
+        transaction.assert_claimable(ctx.accounts.config.dispute_window, now)?;
+
+        let split = SaleSplit::for_sale(&ctx.accounts.config, &ctx.accounts.genome, transaction)?
+            .with_referral(transaction.referrer, ctx.accounts.config.referral_bps)?;
+        let collateral = transaction.release_collateral();
+        let escrow = Escrow::new(
+            transaction,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        );
+        settle_sale(
+            &escrow,
+            sale_payees!(
+                ctx.accounts,
+                ctx.remaining_accounts,
+                referral_payee!(ctx.accounts, transaction.referrer)
+            ),
+            &split,
+            collateral,
+        )?;
+        transaction.set_status(TransactionStatus::Executed, now);
+        transaction.settled = true;
+        ctx.accounts.genome.record_settlement();
+
+        emit!(SettlementClaimed {
+            transaction: transaction.key(),
+            seller: transaction.seller,
+            amount: split.seller_amount,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn submit_rating(ctx: Context<SubmitRating>, stars: u8, comment: String) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let transaction = &ctx.accounts.transaction;
+        let buyer = &ctx.accounts.buyer;
+        let now = unix_timestamp()?;
+
+        require!(
+            transaction.status != TransactionStatus::Refunded,
+            GenomeError::InvalidTransactionStatus
+        );
+        validate_len(&comment, MAX_COMMENT_LEN)?;
+
+        let seller_profile = &mut ctx.accounts.seller_profile;
+        seller_profile.open(
+            transaction.seller,
+            *ctx.bumps.get("seller_profile").unwrap(),
+        );
+        seller_profile.record_rating(stars)?;
+        seller_profile.record_weighted_rating(
+            stars,
+            ctx.accounts.config.reputation_half_life,
+            now,
+        )?;
+
+        let rating = &mut ctx.accounts.rating;
+        rating.transaction = transaction.key();
+        rating.seller = transaction.seller;
+        rating.buyer = buyer.key();
+        rating.stars = stars;
+        rating.comment = comment;
+        rating.created_at = now;
+        rating.bump = *ctx.bumps.get("rating").unwrap();
+
+        emit!(RatingSubmitted {
+            transaction: rating.transaction,
+            seller: rating.seller,
+            buyer: rating.buyer,
+            stars,
+            rating_sum: seller_profile.rating_sum,
+            rating_count: seller_profile.rating_count,
+            timestamp: rating.created_at,
+        });
+
+        Ok(())
+    }
+
+    /// Sends `amount` straight to a seller as a tip, in SPL tokens when both
+    /// token accounts are supplied and in SOL otherwise.
+    pub fn tip(ctx: Context<Tip>, amount: u64) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let tipper = &ctx.accounts.tipper;
+        let seller = &ctx.accounts.seller;
+
+        require!(amount > 0, GenomeError::InvalidAmount);
+        require_keys_neq!(tipper.key(), seller.key(), GenomeError::InvalidRecipient);
+
+        let mint = match (
+            ctx.accounts.tipper_token_account.as_ref(),
+            ctx.accounts.seller_token_account.as_ref(),
+        ) {
+            (Some(source), Some(destination)) => {
+                require_keys_eq!(source.mint, destination.mint, GenomeError::MintMismatch);
+                token::transfer(
+                    CpiContext::new(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: source.to_account_info(),
+                            to: destination.to_account_info(),
+                            authority: tipper.to_account_info(),
+                        },
+                    ),
+                    amount,
+                )?;
+                Some(source.mint)
+            }
+            (None, None) => {
+                system_program::transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        system_program::Transfer {
+                            from: tipper.to_account_info(),
+                            to: seller.to_account_info(),
+                        },
+                    ),
+                    amount,
+                )?;
+                None
+            }
+            _ => return err!(GenomeError::InvalidPaymentAccounts),
+        };
+
+        let seller_profile = &mut ctx.accounts.seller_profile;
+        seller_profile.open(seller.key(), *ctx.bumps.get("seller_profile").unwrap());
+        seller_profile.record_tip(amount)?;
+
+        emit!(TipSent {
+            tipper: tipper.key(),
+            seller: seller.key(),
+            mint,
+            amount,
+            tips_received: seller_profile.tips_received,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Opens the seller's `Earnings` balance for one payment mint, or native
+    /// SOL when `mint` is omitted.
+    pub fn open_earnings(ctx: Context<OpenEarnings>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let earnings = &mut ctx.accounts.earnings;
+        earnings.seller = ctx.accounts.seller.key();
+        earnings.payment_mint = resolve_payment_mint(
+            ctx.accounts.mint.as_ref().map(|mint| mint.key()),
+            ctx.accounts.earnings_vault.is_some(),
+            ctx.accounts.earnings_sol_vault.is_some(),
+        )?;
+        ctx.accounts
+            .config
+            .assert_mint_allowed(earnings.payment_mint)?;
+        (earnings.vault, earnings.vault_bump) = match earnings.payment_mint {
+            Some(_) => (
+                ctx.accounts.earnings_vault.as_ref().unwrap().key(),
+                *ctx.bumps.get("earnings_vault").unwrap(),
+            ),
+            None => (
+                ctx.accounts.earnings_sol_vault.as_ref().unwrap().key(),
+                *ctx.bumps.get("earnings_sol_vault").unwrap(),
+            ),
+        };
+        earnings.bump = *ctx.bumps.get("earnings").unwrap();
+
+        Escrow::for_earnings(
+            earnings,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.earnings_vault.as_ref(),
+            ctx.accounts.earnings_sol_vault.as_ref(),
+        )
+        .fund_rent(&ctx.accounts.seller)?;
+
+        Ok(())
+    }
+
+    /// Pulls `amount` of the seller's accrued proceeds out of their
+    /// `Earnings` vault.
+    pub fn withdraw_earnings(ctx: Context<WithdrawEarnings>, amount: u64) -> Result<()> {
+        let earnings = &mut ctx.accounts.earnings;
+        earnings.withdraw(amount)?;
+
+        Escrow::for_earnings(
+            earnings,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.earnings_vault.as_ref(),
+            ctx.accounts.earnings_sol_vault.as_ref(),
+        )
+        .pay(
+            ctx.accounts.seller_token_account.as_ref(),
+            Some(ctx.accounts.seller.to_account_info()),
+            amount,
+        )?;
+
+        emit!(EarningsWithdrawn {
+            earnings: earnings.key(),
+            seller: earnings.seller,
+            amount,
+            remaining: earnings.balance,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Logs a seller's aggregate stats and returns them to the caller.
+    pub fn get_seller_stats(ctx: Context<GetSellerStats>) -> Result<SellerStats> {
+        let stats = ctx.accounts.seller_profile.stats();
+        msg!(
+            "seller {}: {} sales, volume {}, {} active listings, rating {}/{}",
+            ctx.accounts.seller_profile.seller,
+            stats.total_sales,
+            stats.total_volume,
+            stats.active_listings,
+            stats.rating_sum,
+            stats.rating_count
+        );
+        msg!("tips received: {}", stats.tips_received);
+        Ok(stats)
+    }
+
+    /// Returns a seller's reputation with older ratings counting for less,
+    /// decayed to the current time.
+    pub fn get_weighted_reputation(
+        ctx: Context<GetWeightedReputation>,
+    ) -> Result<WeightedReputation> {
+        let profile = &ctx.accounts.seller_profile;
+        let weighted = profile
+            .weighted_reputation(ctx.accounts.config.reputation_half_life, unix_timestamp()?);
+        msg!(
+            "seller {}: weighted rating {} over weight {}",
+            profile.seller,
+            weighted.reputation,
+            weighted.weight
+        );
+        Ok(weighted)
+    }
+
+    /// Returns one page of a buyer's purchases, oldest first.
+    pub fn get_purchase_history(ctx: Context<GetPurchaseHistory>) -> Result<Vec<PurchaseRecord>> {
+        let history = &ctx.accounts.buyer_history;
+        msg!(
+            "buyer {} page {}: {} purchases",
+            history.buyer,
+            history.page,
+            history.records.len()
+        );
+        Ok(history.records.clone())
+    }
+
+    /// Returns one page of the grants issued for a genome, oldest first.
+    pub fn get_access_log(ctx: Context<GetAccessLog>) -> Result<Vec<AccessRecord>> {
+        let log = &ctx.accounts.access_log;
+        msg!(
+            "genome {} page {}: {} grants",
+            log.genome,
+            log.page,
+            log.records.len()
+        );
+        Ok(log.records.clone())
+    }
+
+    /// Returns the statuses a transaction has passed through, oldest first.
+    pub fn get_status_history(ctx: Context<GetStatusHistory>) -> Result<Vec<StatusChange>> {
+        let transaction = &ctx.accounts.transaction;
+        msg!(
+            "transaction {}: {} status changes",
+            transaction.key(),
+            transaction.status_history.len()
+        );
+        Ok(transaction.status_history.clone())
+    }
+
+    /// Logs the marketplace-wide counters and returns them to the caller.
+    pub fn get_market_stats(ctx: Context<GetMarketStats>) -> Result<MarketStats> {
+        let stats = &ctx.accounts.market_stats;
+        msg!(
+            "market: {} genomes, {} sales, volume {}, {} active listings",
+            stats.total_genomes,
+            stats.total_transactions,
+            stats.total_volume,
+            stats.active_listings
+        );
+        Ok((**stats).clone())
+    }
+
+    /// Charges the listing price again, split as a sale, and extends the
+    /// grant by up to one access period from its expiry (or from now, if it
+    /// has already lapsed).
+    pub fn renew_access(ctx: Context<RenewAccess>, additional_duration: i64) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let transaction = &ctx.accounts.transaction;
+        let buyer = &ctx.accounts.buyer;
+        let now = unix_timestamp()?;
+
+        transaction.assert_renewable()?;
+        ctx.accounts.genome.assert_live()?;
+        require!(
+            additional_duration > 0 && additional_duration <= transaction.access_duration,
+            GenomeError::InvalidDuration
+        );
+
+        let split = SaleSplit::for_sale(&ctx.accounts.config, &ctx.accounts.genome, transaction)?;
+        let escrow = Escrow::new(
+            transaction,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        );
+        escrow.collect(
+            buyer,
+            ctx.accounts.buyer_token_account.as_ref(),
+            transaction.price,
+        )?;
+        settle_sale(
+            &escrow,
+            sale_payees!(ctx.accounts, ctx.remaining_accounts, None),
+            &split,
+            0,
+        )?;
+
+        let access_grant = &mut ctx.accounts.access_grant;
+        let expires_at = access_grant.renew(now, additional_duration)?;
+
+        emit!(AccessRenewed {
+            access_grant: access_grant.key(),
+            transaction: transaction.key(),
+            buyer: buyer.key(),
+            price: transaction.price,
+            expires_at,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Opts a grant in or out of auto-renewal. To renew, the buyer must also
+    /// approve the listing's escrow vault as delegate on their token account
+    /// for at least the listing price.
+    pub fn set_auto_renew(ctx: Context<SetAutoRenew>, auto_renew: bool) -> Result<()> {
+        let access_grant = &mut ctx.accounts.access_grant;
+        access_grant.auto_renew = auto_renew;
+
+        emit!(AutoRenewUpdated {
+            access_grant: access_grant.key(),
+            buyer: access_grant.buyer,
+            auto_renew,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless crank renewing an auto-renewing grant near expiry for
+    /// another `access_duration`, paid from the buyer's delegated token
+    /// account. If the delegation or balance no longer covers the price,
+    /// auto-renew is switched off and the grant is left to expire.
+    pub fn process_renewal(ctx: Context<ProcessRenewal>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let transaction = &ctx.accounts.transaction;
+        let now = unix_timestamp()?;
+
+        require!(
+            ctx.accounts.access_grant.renewal_due(now),
+            GenomeError::RenewalNotDue
+        );
+        transaction.assert_renewable()?;
+        ctx.accounts.genome.assert_live()?;
+
+        let escrow = Escrow::new(
+            transaction,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            Some(&ctx.accounts.escrow_vault),
+            None,
+        );
+        if !escrow.collect_delegated(&ctx.accounts.buyer_token_account, transaction.price)? {
+            let access_grant = &mut ctx.accounts.access_grant;
+            access_grant.auto_renew = false;
+
+            emit!(RenewalFailed {
+                access_grant: access_grant.key(),
+                transaction: transaction.key(),
+                buyer: access_grant.buyer,
+                expires_at: access_grant.expires_at,
+                timestamp: now,
+            });
+
+            return Ok(());
+        }
+
+        let split = SaleSplit::for_sale(&ctx.accounts.config, &ctx.accounts.genome, transaction)?;
+        // Renewals are paid from a delegated token account, so only token
+        // payees are passed.
+        settle_sale(
+            &escrow,
+            SalePayees {
+                seller_token_account: ctx.accounts.seller_token_account.as_ref(),
+                seller: None,
+                treasury_token_account: ctx.accounts.treasury_token_account.as_ref(),
+                treasury: None,
+                creator_token_account: ctx.accounts.creator_token_account.as_ref(),
+                creator: None,
+                seller_split: seller_split(
+                    &ctx.accounts.genome,
+                    ctx.accounts.payout_split.as_ref(),
+                    ctx.remaining_accounts,
+                )?,
+                referral: None,
+            },
+            &split,
+            0,
+        )?;
+
+        let access_grant = &mut ctx.accounts.access_grant;
+        let expires_at = access_grant.renew(now, transaction.access_duration)?;
+
+        emit!(AccessRenewed {
+            access_grant: access_grant.key(),
+            transaction: transaction.key(),
+            buyer: access_grant.buyer,
+            price: transaction.price,
+            expires_at,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Hands the buyer the genome's decryption key, encrypted off-chain to
+    /// the buyer's public key. Delivering again replaces the key.
+    pub fn deliver_key(ctx: Context<DeliverKey>, encrypted_key: Vec<u8>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let access_grant = &mut ctx.accounts.access_grant;
+        let now = unix_timestamp()?;
+        access_grant.deliver_key(encrypted_key, now)?;
+
+        emit!(KeyDelivered {
+            access_grant: access_grant.key(),
+            genome: access_grant.genome,
+            buyer: access_grant.buyer,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn confirm_delivery(ctx: Context<ConfirmDelivery>, data_hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let access_grant = &mut ctx.accounts.access_grant;
+        let now = unix_timestamp()?;
+        access_grant.confirm_delivery(data_hash, now)?;
+
+        emit!(DeliveryConfirmed {
+            access_grant: access_grant.key(),
+            genome: access_grant.genome,
+            buyer: access_grant.buyer,
+            data_hash,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Asks the seller to convert the licensed data into `target_format`,
+    /// escrowing `fee` lamports until they deliver it.
+    pub fn request_conversion(
+        ctx: Context<RequestConversion>,
+        target_format: u8,
+        fee: u64,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let access_grant = &mut ctx.accounts.access_grant;
+        let buyer = &ctx.accounts.buyer;
+        let now = unix_timestamp()?;
+
+        access_grant.assert_active(now)?;
+        access_grant.request_conversion(ctx.accounts.genome.format, target_format, fee)?;
+        access_grant.conversion_bump = *ctx.bumps.get("conversion_vault").unwrap();
+
+        let escrow = Escrow::for_conversion(
+            access_grant,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            &ctx.accounts.conversion_vault,
+        );
+        escrow.fund_rent(buyer)?;
+        escrow.collect(buyer, None, fee)?;
+
+        emit!(ConversionRequested {
+            access_grant: access_grant.key(),
+            genome: access_grant.genome,
+            buyer: buyer.key(),
+            target_format,
+            fee,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Delivers a requested conversion, recording the converted file's hash
+    /// and releasing the escrowed fee to the genome owner.
+    pub fn fulfill_conversion(
+        ctx: Context<FulfillConversion>,
+        result_hash: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let access_grant = &mut ctx.accounts.access_grant;
+        let owner = &ctx.accounts.owner;
+        let now = unix_timestamp()?;
+
+        let fee = access_grant.fulfill_conversion(result_hash)?;
+        Escrow::for_conversion(
+            access_grant,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            &ctx.accounts.conversion_vault,
+        )
+        .pay(None, Some(owner.to_account_info()), fee)?;
+
+        emit!(ConversionFulfilled {
+            access_grant: access_grant.key(),
+            genome: access_grant.genome,
+            owner: owner.key(),
+            target_format: access_grant.conversion_format,
+            result_hash,
+            fee,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Issues free grants lasting `duration` to a cohort of buyers the owner
+    /// has already settled with off-chain. `remaining_accounts` holds each
+    /// buyer's grant PDA, in `buyers` order; every grant can later be
+    /// revoked on its own.
+    pub fn grant_access_batch(
+        ctx: Context<GrantAccessBatch>,
+        buyers: Vec<Pubkey>,
+        duration: i64,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let genome = &mut ctx.accounts.genome;
+        let now = unix_timestamp()?;
+
+        genome.assert_live()?;
+        let grants = cohort_grants(genome.key(), &buyers, now, duration)?;
+        require!(
+            ctx.remaining_accounts.len() == grants.len(),
+            GenomeError::InvalidBatchAccounts
+        );
+        let records = grants
+            .iter()
+            .map(|grant| AccessRecord {
+                buyer: grant.buyer,
+                timestamp: now,
+                access_level: grant.access_level,
+            })
+            .collect();
+        for (grant, account) in grants.into_iter().zip(ctx.remaining_accounts) {
+            create_grant_account(
+                grant,
+                account,
+                &ctx.accounts.owner,
+                &ctx.accounts.system_program,
+                ctx.program_id,
+            )?;
+        }
+
+        let page = genome.access_log_page;
+        ctx.accounts
+            .access_log
+            .open(genome.key(), page, *ctx.bumps.get("access_log").unwrap());
+        if let Some(next) = ctx.accounts.next_access_log.as_deref_mut() {
+            next.open(
+                genome.key(),
+                page + 1,
+                *ctx.bumps.get("next_access_log").unwrap(),
+            );
+        }
+        log_access(
+            genome,
+            &mut ctx.accounts.access_log,
+            ctx.accounts.next_access_log.as_deref_mut(),
+            records,
+        )?;
+
+        emit!(CohortAccessGranted {
+            genome: genome.key(),
+            owner: genome.owner,
+            buyers,
+            expires_at: now + duration,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Opts the genome in or out of free nonprofit access.
+    pub fn set_nonprofit_access(ctx: Context<UpdateMetadata>, enabled: bool) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let genome = &mut ctx.accounts.genome;
+        genome.assert_owner(ctx.accounts.authority.key())?;
+        genome.assert_live()?;
+        genome.nonprofit_access = enabled;
+
+        emit!(NonprofitAccessUpdated {
+            genome: genome.key(),
+            owner: genome.owner,
+            enabled,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Records that the data subject has withdrawn consent, blocking any new
+    /// listing or sale of the genome. Grants passed in `remaining_accounts`
+    /// are revoked too, after the configured grace period; others keep
+    /// running until they expire.
+    pub fn withdraw_consent(ctx: Context<UpdateMetadata>) -> Result<()> {
+        let genome = &mut ctx.accounts.genome;
+        let owner = ctx.accounts.authority.key();
+        let now = unix_timestamp()?;
+
+        genome.assert_owner(owner)?;
+        require!(genome.consent_active, GenomeError::ConsentWithdrawn);
+        require!(
+            ctx.remaining_accounts.len() <= MAX_BATCH_SIZE,
+            GenomeError::InvalidBatchSize
+        );
+        genome.consent_active = false;
+        genome.updated_at = now;
+
+        let grace = ctx.accounts.config.revocation_grace_period;
+        let mut grants_revoked = 0u32;
+        for info in ctx.remaining_accounts {
+            require!(info.is_writable, GenomeError::InvalidBatchAccounts);
+            let mut access_grant = Account::<AccessGrant>::try_from(info)?;
+            require_keys_eq!(
+                access_grant.genome,
+                genome.key(),
+                GenomeError::GenomeMismatch
+            );
+            if access_grant.revoked {
+                continue;
+            }
+            let effective_at = access_grant.revoke(now, grace)?;
+            access_grant.exit(ctx.program_id)?;
+            grants_revoked += 1;
+
+            emit!(AccessRevoked {
+                access_grant: access_grant.key(),
+                genome: access_grant.genome,
+                buyer: access_grant.buyer,
+                revoked_by: owner,
+                effective_at,
+                timestamp: now,
+            });
+        }
+
+        emit!(ConsentWithdrawn {
+            genome: genome.key(),
+            owner,
+            grants_revoked,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Issues a research license on an opted-in genome to a quota holder,
+    /// free of charge, using up one of its grants.
+    pub fn claim_free_access(ctx: Context<ClaimFreeAccess>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let genome = &mut ctx.accounts.genome;
+        let grant_quota = &mut ctx.accounts.grant_quota;
+        let now = unix_timestamp()?;
+
+        genome.assert_live()?;
+        require!(
+            genome.nonprofit_access,
+            GenomeError::NonprofitAccessDisabled
+        );
+        genome.assert_not_embargoed(now)?;
+        genome.assert_consent()?;
+        genome.assert_use(USE_RESEARCH)?;
+        grant_quota.consume()?;
+
+        let access_grant = &mut ctx.accounts.access_grant;
+        access_grant.reissue(now)?;
+        access_grant.genome = genome.key();
+        access_grant.transaction = grant_quota.key();
+        access_grant.buyer = grant_quota.holder;
+        access_grant.granted_at = now;
+        access_grant.expires_at = now
+            .checked_add(grant_quota.access_duration)
+            .ok_or(GenomeError::MathOverflow)?;
+        access_grant.declared_use = USE_RESEARCH;
+        access_grant.access_level = ACCESS_READ_ONLY;
+        access_grant.bump = *ctx.bumps.get("access_grant").unwrap();
+
+        let access_log = &mut ctx.accounts.access_log;
+        access_log.open(
+            genome.key(),
+            genome.access_log_page,
+            *ctx.bumps.get("access_log").unwrap(),
+        );
+        log_access(
+            genome,
+            access_log,
+            None,
+            vec![AccessRecord {
+                buyer: access_grant.buyer,
+                timestamp: now,
+                access_level: access_grant.access_level,
+            }],
+        )?;
+
+        emit!(FreeAccessClaimed {
+            genome: genome.key(),
+            holder: grant_quota.holder,
+            access_grant: access_grant.key(),
+            expires_at: access_grant.expires_at,
+            remaining: grant_quota.remaining,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Fails unless `buyer` holds an unexpired, unrevoked grant for
+    /// `genome`. Meant to be invoked by other programs; its accounts and
+    /// errors (`AccessExpired`, `AccessRevoked` and Anchor's seed check on a
+    /// missing grant) are kept stable.
+    pub fn assert_valid_access(ctx: Context<AssertValidAccess>) -> Result<()> {
+        ctx.accounts.access_grant.assert_active(unix_timestamp()?)
+    }
+
+    /// Succeeds if the caller holds an active grant for the genome, or a
+    /// subscription pass while the genome is in the subscription pool.
+    pub fn check_access(ctx: Context<VerifyAccess>) -> Result<()> {
+        let now = unix_timestamp()?;
+        if let Some(grant) = &ctx.accounts.access_grant {
+            return grant.assert_active(now);
+        }
+        let pass = ctx
+            .accounts
+            .subscription_pass
+            .as_ref()
+            .ok_or(GenomeError::NoAccessProvided)?;
+        pass.assert_covers(&Account::<Genome>::try_from(&ctx.accounts.genome)?, now)
+    }
+
+    /// Reports whether a grant is currently usable and for how long, for
+    /// services gating downloads. Unlike `check_access` it never fails on an
+    /// expired or revoked grant.
+    pub fn get_access_status(ctx: Context<CheckAccess>) -> Result<AccessStatus> {
+        let grant = &ctx.accounts.access_grant;
+        let status = grant.status(unix_timestamp()?);
+        msg!(
+            "access {} for {}: valid {}, {}s remaining, revoked {}, key delivered {}",
+            grant.genome,
+            grant.buyer,
+            status.valid,
+            status.remaining_seconds,
+            status.revoked,
+            status.key_delivered
+        );
+        Ok(status)
+    }
+
+    /// Runs the gates every purchase of the listing must pass, so clients
+    /// can tell whether to offer it without repeating them. Checks tied to
+    /// a particular buyer, such as whitelists and token gates, are left out.
+    pub fn check_purchasable(ctx: Context<CheckPurchasable>) -> Result<PurchaseCheck> {
+        let check = PurchaseCheck::evaluate(
+            &ctx.accounts.config,
+            &ctx.accounts.genome,
+            &ctx.accounts.transaction,
+            &ctx.accounts.blacklist,
+            unix_timestamp()?,
+        );
+        msg!(
+            "listing {}: purchasable {}, reason {}",
+            ctx.accounts.transaction.key(),
+            check.purchasable,
+            check.reason
+        );
+        Ok(check)
+    }
+
+    /// Logs and returns the genome's encrypted metadata to a holder of an
+    /// active grant, who decrypts it off-chain.
+    pub fn get_encrypted_metadata(ctx: Context<GetEncryptedMetadata>) -> Result<Vec<u8>> {
+        let grant = &ctx.accounts.access_grant;
+        grant.assert_active(unix_timestamp()?)?;
+
+        let encrypted_metadata = ctx.accounts.genome.encrypted_metadata.clone();
+        msg!(
+            "encrypted metadata of {} for {}: {} bytes",
+            grant.genome,
+            grant.buyer,
+            encrypted_metadata.len()
+        );
+        Ok(encrypted_metadata)
+    }
+
+    /// Creates the subscription pool on first use and sets what a pass
+    /// costs, in lamports, and how long it lasts. A zero price stops sales.
+    pub fn configure_subscription_pool(
+        ctx: Context<ConfigureSubscriptionPool>,
+        pass_price: u64,
+        pass_duration: i64,
+    ) -> Result<()> {
+        require!(pass_duration > 0, GenomeError::InvalidDuration);
+
+        let pool = &mut ctx.accounts.pool;
+        pool.pass_price = pass_price;
+        pool.pass_duration = pass_duration;
+        pool.vault_bump = *ctx.bumps.get("pool_vault").unwrap();
+        pool.bump = *ctx.bumps.get("pool").unwrap();
+        Escrow::for_pool(
+            pool,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            &ctx.accounts.pool_vault,
+        )
+        .fund_rent(&ctx.accounts.admin)?;
+
+        emit!(SubscriptionPoolConfigured {
+            admin: ctx.accounts.admin.key(),
+            pass_price,
+            pass_duration,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Opts the genome in or out of the subscription pool. Leaving pays out
+    /// the owner's share of pass revenue earned so far.
+    pub fn set_subscription_pool(ctx: Context<SetSubscriptionPool>, enabled: bool) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let genome = &mut ctx.accounts.genome;
+        let pool = &mut ctx.accounts.pool;
+
+        genome.assert_live()?;
+        let paid_out = if enabled {
+            pool.join(genome)?;
+            0
+        } else {
+            pool.leave(genome)?
+        };
+        Escrow::for_pool(
+            pool,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            &ctx.accounts.pool_vault,
+        )
+        .pay(None, Some(ctx.accounts.owner.to_account_info()), paid_out)?;
+
+        emit!(SubscriptionPoolMembershipUpdated {
+            genome: genome.key(),
+            owner: genome.owner,
+            enabled,
+            paid_out,
+            participants: pool.participants,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Buys or extends the caller's subscription pass. The price is shared
+    /// among the genomes in the pool at the time of purchase.
+    pub fn purchase_subscription_pass(ctx: Context<PurchaseSubscriptionPass>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let pool = &mut ctx.accounts.pool;
+        let pass = &mut ctx.accounts.pass;
+        let buyer = &ctx.accounts.buyer;
+        let now = unix_timestamp()?;
+
+        require!(pool.pass_price > 0, GenomeError::SubscriptionsDisabled);
+        pool.record_sale(pool.pass_price)?;
+        pass.holder = buyer.key();
+        pass.bump = *ctx.bumps.get("pass").unwrap();
+        pass.extend(now, pool.pass_duration)?;
+        Escrow::for_pool(
+            pool,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            &ctx.accounts.pool_vault,
+        )
+        .collect(buyer, None, pool.pass_price)?;
+
+        emit!(SubscriptionPassPurchased {
+            pass: pass.key(),
+            holder: pass.holder,
+            price: pool.pass_price,
+            expires_at: pass.expires_at,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Pays the genome owner their share of pass revenue accrued since the
+    /// last claim.
+    pub fn claim_pool_revenue(ctx: Context<ClaimPoolRevenue>) -> Result<()> {
+        let genome = &mut ctx.accounts.genome;
+        let pool = &ctx.accounts.pool;
+
+        require!(genome.subscription_pool, GenomeError::NotInSubscriptionPool);
+        let amount = pool.settle(genome)?;
+        Escrow::for_pool(
+            pool,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            &ctx.accounts.pool_vault,
+        )
+        .pay(None, Some(ctx.accounts.owner.to_account_info()), amount)?;
+
+        emit!(PoolRevenueClaimed {
+            genome: genome.key(),
+            owner: genome.owner,
+            amount,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    /// Revokes a grant after the configured grace period, or at once when
+    /// `immediate` is set for compliance takedowns.
+    pub fn revoke_access(ctx: Context<RevokeAccess>, immediate: bool) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let access_grant = &mut ctx.accounts.access_grant;
+        let authority = ctx.accounts.authority.key();
+        let now = unix_timestamp()?;
+
+        if authority != ctx.accounts.config.admin {
+            ctx.accounts.genome.assert_owner(authority)?;
+        }
+        require!(!access_grant.revoked, GenomeError::AccessRevoked);
+
+        let grace = if immediate {
+            0
+        } else {
+            ctx.accounts.config.revocation_grace_period
+        };
+        let effective_at = access_grant.revoke(now, grace)?;
+
+        emit!(AccessRevoked {
+            access_grant: access_grant.key(),
+            genome: access_grant.genome,
+            buyer: access_grant.buyer,
+            revoked_by: authority,
+            effective_at,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Ends a buyer's still-active license early, refunding the unused share
+    /// of the price. Proceeds still held in escrow are split between buyer
+    /// and seller; once they have been paid out the seller funds the refund.
+    pub fn cancel_active_license(ctx: Context<CancelActiveLicense>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let transaction = &mut ctx.accounts.transaction;
+        let seller = &ctx.accounts.seller;
+        let now = unix_timestamp()?;
+
+        transaction.assert_seller(seller.key())?;
+        transaction.assert_live()?;
+        ctx.accounts.genome.assert_live()?;
+        require!(
+            !matches!(
+                transaction.status,
+                TransactionStatus::Disputed | TransactionStatus::Refunded
+            ),
+            GenomeError::InvalidTransactionStatus
+        );
+
+        let access_grant = &mut ctx.accounts.access_grant;
+        let refund =
+            access_grant.unused_refund(transaction.price, transaction.access_duration, now)?;
+
+        let escrow = Escrow::new(
+            transaction,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        );
+        if transaction.settled {
+            escrow.collect(seller, ctx.accounts.seller_token_account.as_ref(), refund)?;
+        } else {
+            let split = SaleSplit::for_proceeds(
+                &ctx.accounts.config,
+                &ctx.accounts.genome,
+                transaction,
+                transaction.price - refund,
+            )?
+            .with_referral(transaction.referrer, ctx.accounts.config.referral_bps)?;
+            settle_sale(
+                &escrow,
+                sale_payees!(
+                    ctx.accounts,
+                    seller: (
+                        ctx.accounts.seller_token_account.as_ref(),
+                        Some(seller.to_account_info())
+                    ),
+                    seller_split: seller_split(
+                        &ctx.accounts.genome,
+                        ctx.accounts.payout_split.as_ref(),
+                        ctx.remaining_accounts,
+                    )?,
+                    referral: referral_payee!(ctx.accounts, transaction.referrer)
+                ),
+                &split,
+                0,
+            )?;
+            transaction.settled = true;
+            ctx.accounts.genome.record_settlement();
+        }
+        escrow.pay(
+            ctx.accounts.buyer_token_account.as_ref(),
+            ctx.accounts.buyer.as_ref().map(|b| b.to_account_info()),
+            refund,
+        )?;
+        access_grant.revoked = true;
+
+        emit!(LicenseCancelled {
+            access_grant: access_grant.key(),
+            transaction: transaction.key(),
+            buyer: access_grant.buyer,
+            refund_amount: refund,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Buys back a sold license: the genome owner refunds the buyer the full
+    /// price from their own funds and the grant is revoked. Only for settled
+    /// sales whose license has not yet expired.
+    pub fn recall_license(ctx: Context<RecallLicense>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let transaction = &ctx.accounts.transaction;
+        let owner = &ctx.accounts.owner;
+        let now = unix_timestamp()?;
+
+        transaction.assert_live()?;
+        ctx.accounts.genome.assert_live()?;
+        require!(
+            transaction.settled
+                && !matches!(
+                    transaction.status,
+                    TransactionStatus::Disputed | TransactionStatus::Refunded
+                ),
+            GenomeError::InvalidTransactionStatus
+        );
+
+        let access_grant = &mut ctx.accounts.access_grant;
+        access_grant.assert_active(now)?;
+
+        let escrow = Escrow::new(
+            transaction,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        );
+        escrow.collect(
+            owner,
+            ctx.accounts.owner_token_account.as_ref(),
+            transaction.price,
+        )?;
+        escrow.pay(
+            ctx.accounts.buyer_token_account.as_ref(),
+            ctx.accounts.buyer.as_ref().map(|b| b.to_account_info()),
+            transaction.price,
+        )?;
+        access_grant.revoked = true;
+
+        emit!(LicenseRecalled {
+            access_grant: access_grant.key(),
+            transaction: transaction.key(),
+            genome: transaction.genome,
+            owner: owner.key(),
+            buyer: access_grant.buyer,
+            refund_amount: transaction.price,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_transaction(ctx: Context<CancelTransaction>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let transaction = &mut ctx.accounts.transaction;
+        
+        // The admin may also take down listings of a blacklisted seller.
+        let authority = ctx.accounts.authority.key();
+        let seller_blacklisted = ctx
+            .accounts
+            .blacklist
+            .as_ref()
+            .map_or(false, |blacklist| blacklist.contains(&transaction.seller));
+        let admin_takedown = seller_blacklisted && authority == ctx.accounts.config.admin;
+        if !admin_takedown {
+            transaction.assert_seller(authority)?;
+        }
+        require!(
+            transaction.status == TransactionStatus::Created,
+            GenomeError::InvalidTransactionStatus
+        );
+
+        let cancellation_fee = if admin_takedown {
+            0
+        } else {
+            charge_cancellation_fee(
+                &ctx.accounts.config,
+                &ctx.accounts.authority,
+                ctx.accounts.treasury.as_ref(),
+                &ctx.accounts.system_program,
+                1,
+            )?
+        };
+
+        // Auction bids, including the top one, are reclaimed individually
+        // with `withdraw_bid` once the listing is cancelled.
+        if transaction.auction.is_some() {
+            transaction.depositor = None;
+        }
+
+        let refunded_to = transaction.depositor;
+        if let Some(depositor) = refunded_to {
+            Escrow::new(
+                transaction,
+                &ctx.accounts.token_program,
+                &ctx.accounts.system_program,
+                ctx.accounts.escrow_vault.as_ref(),
+                ctx.accounts.sol_vault.as_ref(),
+            )
+            .pay_to(
+                depositor,
+                ctx.accounts.depositor_token_account.as_ref(),
+                ctx.accounts.depositor.as_ref().map(|d| d.to_account_info()),
+                transaction.price,
+            )?;
+            transaction.depositor = None;
+        }
+        let collateral = transaction.release_collateral();
+        Escrow::new(
+            transaction,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        )
+        .pay(
+            ctx.accounts.seller_token_account.as_ref(),
+            ctx.accounts.seller.as_ref().map(|s| s.to_account_info()),
+            collateral,
+        )?;
+
+        let now = unix_timestamp()?;
+        transaction.set_status(TransactionStatus::Cancelled, now);
+        let genome = &mut ctx.accounts.genome;
+        genome.active_listings = genome.active_listings.saturating_sub(1);
+        ctx.accounts.seller_profile.record_closed_listing();
+        ctx.accounts.market_stats.record_closed_listing();
+        ctx.accounts.category_index.remove(&transaction.key())?;
+
+        emit!(TransactionCancelled {
+            transaction: transaction.key(),
+            genome: transaction.genome,
+            seller: transaction.seller,
+            refunded_to,
+            refund_amount: if refunded_to.is_some() {
+                transaction.price
+            } else {
+                0
+            },
+            cancellation_fee,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Cancels up to `MAX_BATCH_SIZE` listings in one instruction. Each
+    /// transaction, its genome and its category index page are passed as a
+    /// writable triple in `remaining_accounts`. Listings the caller does not
+    /// own, that are no longer open, or that hold a buyer's deposit or the
+    /// seller's collateral (which need the payout accounts of
+    /// `cancel_transaction`) are skipped rather than failing the batch.
+    pub fn cancel_transactions_batch(ctx: Context<CancelTransactionsBatch>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let accounts = ctx.remaining_accounts;
+        require!(accounts.len() % 3 == 0, GenomeError::InvalidBatchAccounts);
+        let requested = accounts.len() / 3;
+        require!(
+            requested > 0 && requested <= MAX_BATCH_SIZE,
+            GenomeError::InvalidBatchSize
+        );
+
+        let seller = ctx.accounts.seller.key();
+        let now = unix_timestamp()?;
+        let mut cancelled = 0u32;
+        for triple in accounts.chunks(3) {
+            // An account that is not a listing is skipped like any other
+            // ineligible entry rather than failing the whole batch.
+            let Ok(mut transaction) = Account::<Transaction>::try_from(&triple[0]) else {
+                continue;
+            };
+            if !triple[0].is_writable || !transaction.batch_cancellable(seller) {
+                continue;
+            }
+            let mut genome = Account::<Genome>::try_from(&triple[1])?;
+            require_keys_eq!(
+                genome.key(),
+                transaction.genome,
+                GenomeError::GenomeMismatch
+            );
+            let mut category_index = Account::<CategoryIndex>::try_from(&triple[2])?;
+            require_keys_eq!(
+                category_index.key(),
+                transaction.category_index,
+                GenomeError::CategoryIndexMismatch
+            );
+
+            // Auction bids are reclaimed individually with `withdraw_bid`.
+            transaction.depositor = None;
+            transaction.set_status(TransactionStatus::Cancelled, now);
+            genome.active_listings = genome.active_listings.saturating_sub(1);
+            ctx.accounts.seller_profile.record_closed_listing();
+            ctx.accounts.market_stats.record_closed_listing();
+            category_index.remove(&transaction.key())?;
+            transaction.exit(ctx.program_id)?;
+            genome.exit(ctx.program_id)?;
+            category_index.exit(ctx.program_id)?;
+            cancelled += 1;
+        }
+
+        let cancellation_fee = charge_cancellation_fee(
+            &ctx.accounts.config,
+            &ctx.accounts.seller,
+            ctx.accounts.treasury.as_ref(),
+            &ctx.accounts.system_program,
+            u64::from(cancelled),
+        )?;
+
+        emit!(TransactionsCancelled {
+            seller,
+            cancelled,
+            skipped: requested as u32 - cancelled,
+            cancellation_fee,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Retires a listing whose period ran out without a sale, refunding any
+    /// deposit. Callable by anyone so stale listings can be cleaned up.
+    pub fn expire_transaction(ctx: Context<ExpireTransaction>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let transaction = &mut ctx.accounts.transaction;
+        let now = unix_timestamp()?;
+
+        transaction.assert_expirable(now)?;
+
+        // An auction that closed below its reserve settles as unsold; the
+        // top bid is released to `withdraw_bid` with the others.
+        if transaction.auction.is_some() {
+            transaction.depositor = None;
+        }
+
+        let refunded_to = transaction.depositor;
+        if let Some(depositor) = refunded_to {
+            Escrow::new(
+                transaction,
+                &ctx.accounts.token_program,
+                &ctx.accounts.system_program,
+                ctx.accounts.escrow_vault.as_ref(),
+                ctx.accounts.sol_vault.as_ref(),
+            )
+            .pay_to(
+                depositor,
+                ctx.accounts.depositor_token_account.as_ref(),
+                ctx.accounts.depositor.as_ref().map(|d| d.to_account_info()),
+                transaction.price,
+            )?;
+            transaction.depositor = None;
+        }
+        let collateral = transaction.release_collateral();
+        Escrow::new(
+            transaction,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        )
+        .pay(
+            ctx.accounts.seller_token_account.as_ref(),
+            ctx.accounts.seller.as_ref().map(|s| s.to_account_info()),
+            collateral,
+        )?;
+
+        transaction.set_status(TransactionStatus::Expired, now);
+        let genome = &mut ctx.accounts.genome;
+        genome.active_listings = genome.active_listings.saturating_sub(1);
+        ctx.accounts.seller_profile.record_closed_listing();
+        ctx.accounts.market_stats.record_closed_listing();
+        ctx.accounts.category_index.remove(&transaction.key())?;
+
+        emit!(TransactionExpired {
+            transaction: transaction.key(),
+            genome: transaction.genome,
+            seller: transaction.seller,
+            refunded_to,
+            refund_amount: if refunded_to.is_some() {
+                transaction.price
+            } else {
+                0
+            },
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Closes a finished listing and its escrow vault, returning their rent
+    /// to the seller. Licenses issued under it can no longer be renewed,
+    /// rated or relisted through it once it is gone.
+    pub fn close_transaction(ctx: Context<CloseTransaction>) -> Result<()> {
+        let transaction = &ctx.accounts.transaction;
+        let now = unix_timestamp()?;
+
+        transaction.assert_closable()?;
+        Escrow::new(
+            transaction,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        )
+        .close(ctx.accounts.seller.to_account_info())?;
+
+        emit!(TransactionClosed {
+            transaction: transaction.key(),
+            genome: transaction.genome,
+            seller: transaction.seller,
+            status: transaction.status.clone(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Last-resort sweep of funds stranded in a closed transaction's escrow
+    /// to `to`, once `ESCROW_RECOVERY_DELAY` has passed. Admin only.
+    pub fn admin_recover_escrow(ctx: Context<AdminRecoverEscrow>, to: Pubkey) -> Result<()> {
+        let transaction = &ctx.accounts.transaction;
+        let now = unix_timestamp()?;
+
+        transaction.assert_recoverable(now)?;
+
+        let escrow = Escrow::new(
+            transaction,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        );
+        let amount = escrow.balance()?;
+        escrow.pay_to(
+            to,
+            ctx.accounts.recipient_token_account.as_ref(),
+            ctx.accounts.recipient.as_ref().map(|r| r.to_account_info()),
+            amount,
+        )?;
+
+        emit!(EscrowRecovered {
+            transaction: transaction.key(),
+            admin: ctx.accounts.admin.key(),
+            to,
+            amount,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Hands a genome, and optionally one of its listings, over to the
+    /// program announced by `open_upgrade_window`. Both are marked migrated
+    /// and rejected by this program from then on. The listing's escrow moves
+    /// to the new program's vault for it, the PDA with the same seeds under
+    /// `new_program`. Admin only, while the window is open.
+    pub fn migrate_to(ctx: Context<MigrateTo>, new_program: Pubkey) -> Result<()> {
+        let now = unix_timestamp()?;
+        ctx.accounts
+            .config
+            .assert_upgrade_window(new_program, now)?;
+
+        let genome = &mut ctx.accounts.genome;
+        genome.migrate(new_program)?;
+
+        let mut escrow_moved = 0;
+        if let Some(transaction) = ctx.accounts.transaction.as_mut() {
+            transaction.migrate(new_program, now)?;
+            // The vaults carry no seeds constraint as the listing is
+            // optional; only the listing's own PDAs can sign for them.
+            let escrow = Escrow::new(
+                transaction,
+                &ctx.accounts.token_program,
+                &ctx.accounts.system_program,
+                ctx.accounts.escrow_vault.as_ref(),
+                ctx.accounts.sol_vault.as_ref(),
+            );
+            escrow_moved = escrow.balance()?;
+            let vault_seed = match transaction.payment_mint {
+                Some(_) => ESCROW_SEED,
+                None => SOL_VAULT_SEED,
+            };
+            let (new_vault, _) = Pubkey::find_program_address(
+                &[vault_seed, transaction.key().as_ref()],
+                &new_program,
+            );
+            escrow.pay_to(
+                new_vault,
+                ctx.accounts.new_escrow_vault.as_ref(),
+                ctx.accounts
+                    .new_sol_vault
+                    .as_ref()
+                    .map(|v| v.to_account_info()),
+                escrow_moved,
+            )?;
+        }
+
+        emit!(MigratedToProgram {
+            genome: genome.key(),
+            transaction: ctx.accounts.transaction.as_ref().map(|t| t.key()),
+            admin: ctx.accounts.admin.key(),
+            new_program,
+            escrow_moved,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Lends a token escrow's balance to the configured yield program. The
+    /// program's own accounts follow in `remaining_accounts`. Admin only.
+    pub fn deposit_escrow_yield(ctx: Context<DepositEscrowYield>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        require!(
+            ctx.accounts.config.yield_enabled,
+            GenomeError::YieldDisabled
+        );
+
+        let transaction = &mut ctx.accounts.transaction;
+        let escrow_vault = &mut ctx.accounts.escrow_vault;
+        let now = unix_timestamp()?;
+
+        transaction.assert_live()?;
+        require!(transaction.yield_principal == 0, GenomeError::EscrowInYield);
+        let amount = escrow_vault.amount;
+        require!(amount > 0, GenomeError::InsufficientFunds);
+
+        invoke_yield_program(
+            &ctx.accounts.yield_program,
+            "deposit",
+            Some(amount),
+            escrow_vault,
+            ctx.remaining_accounts,
+            &[
+                ESCROW_SEED,
+                transaction.key().as_ref(),
+                &[transaction.escrow_bump],
+            ],
+        )?;
+        escrow_vault.reload()?;
+        transaction.yield_principal = amount
+            .checked_sub(escrow_vault.amount)
+            .ok_or(GenomeError::MathOverflow)?;
+        transaction.yield_program = ctx.accounts.yield_program.key();
+
+        emit!(EscrowYieldDeposited {
+            transaction: transaction.key(),
+            yield_program: ctx.accounts.yield_program.key(),
+            amount: transaction.yield_principal,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Redeems a lent-out escrow in full, restoring the principal to escrow
+    /// and sending any yield to the treasury. Callable by anyone, even while
+    /// paused, so the principal can always be brought back.
+    pub fn redeem_escrow_yield(ctx: Context<RedeemEscrowYield>) -> Result<()> {
+        let transaction = &mut ctx.accounts.transaction;
+        let escrow_vault = &mut ctx.accounts.escrow_vault;
+        let now = unix_timestamp()?;
+
+        let principal = transaction.yield_principal;
+        require!(principal > 0, GenomeError::NothingLentOut);
+        let before = escrow_vault.amount;
+
+        invoke_yield_program(
+            &ctx.accounts.yield_program,
+            "withdraw",
+            None,
+            escrow_vault,
+            ctx.remaining_accounts,
+            &[
+                ESCROW_SEED,
+                transaction.key().as_ref(),
+                &[transaction.escrow_bump],
+            ],
+        )?;
+        escrow_vault.reload()?;
+        let earned = yield_earned(principal, escrow_vault.amount.saturating_sub(before))?;
+        transaction.yield_principal = 0;
+
+        Escrow::new(
+            transaction,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            Some(&ctx.accounts.escrow_vault),
+            None,
+        )
+        .pay(ctx.accounts.treasury_token_account.as_ref(), None, earned)?;
+
+        emit!(EscrowYieldRedeemed {
+            transaction: transaction.key(),
+            principal,
+            yield_amount: earned,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn add_to_whitelist(ctx: Context<AddToWhitelist>, buyer: Pubkey) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let whitelist = &mut ctx.accounts.whitelist;
+        whitelist.seller = ctx.accounts.seller.key();
+        whitelist.bump = *ctx.bumps.get("whitelist").unwrap();
+        whitelist.add(buyer)?;
+
+        emit!(WhitelistUpdated {
+            whitelist: whitelist.key(),
+            seller: whitelist.seller,
+            buyer,
+            added: true,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    pub fn remove_from_whitelist(ctx: Context<RemoveFromWhitelist>, buyer: Pubkey) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let whitelist = &mut ctx.accounts.whitelist;
+        whitelist.remove(buyer);
+
+        emit!(WhitelistUpdated {
+            whitelist: whitelist.key(),
+            seller: whitelist.seller,
+            buyer,
+            added: false,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    pub fn make_offer(
+        ctx: Context<MakeOffer>,
+        amount: u64,
+        access_duration: i64,
+        expires_at: i64,
+        declared_use: u8,
+        access_level: u8,
+        client_version: u8,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        ctx.accounts.config.assert_client_version(client_version)?;
+
+        let genome = &ctx.accounts.genome;
+        let buyer = &ctx.accounts.buyer;
+        let now = unix_timestamp()?;
+
+        genome.assert_live()?;
+        genome.assert_use(declared_use)?;
+        validate_access_level(access_level)?;
+        require_keys_neq!(genome.owner, buyer.key(), GenomeError::Unauthorized);
+        require!(amount > 0, GenomeError::InvalidPrice);
+        require!(access_duration > 0, GenomeError::InvalidDuration);
+        require!(expires_at > now, GenomeError::InvalidDuration);
+
+        let offer = &mut ctx.accounts.offer;
+        offer.genome = genome.key();
+        offer.buyer = buyer.key();
+        offer.amount = amount;
+        offer.access_duration = access_duration;
+        offer.created_at = now;
+        offer.expires_at = expires_at;
+        offer.declared_use = declared_use;
+        offer.access_level = access_level;
+        offer.payment_mint = resolve_payment_mint(
+            ctx.accounts.mint.as_ref().map(|mint| mint.key()),
+            ctx.accounts.escrow_vault.is_some(),
+            ctx.accounts.sol_vault.is_some(),
+        )?;
+        ctx.accounts
+            .config
+            .assert_mint_allowed(offer.payment_mint)?;
+        assert_offer_floor(
+            genome,
+            ctx.accounts.listing.as_deref(),
+            amount,
+            offer.payment_mint,
+        )?;
+        offer.escrow_bump = match offer.payment_mint {
+            Some(_) => *ctx.bumps.get("escrow_vault").unwrap(),
+            None => *ctx.bumps.get("sol_vault").unwrap(),
+        };
+        offer.bump = *ctx.bumps.get("offer").unwrap();
+
+        let escrow = Escrow::for_offer(
+            offer,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        );
+        escrow.fund_rent(buyer)?;
+        escrow.collect(buyer, ctx.accounts.buyer_token_account.as_ref(), amount)?;
+
+        emit!(OfferMade {
+            offer: offer.key(),
+            genome: offer.genome,
+            buyer: offer.buyer,
+            payment_mint: offer.payment_mint,
+            amount,
+            expires_at,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Accepts the buyer's offer, settling it immediately at the offered
+    /// amount with no dispute window since the owner chose to accept it, and
+    /// refunds the competing ones. The genome's open listing, if any, is
+    /// cancelled. The first `rejected_offers` groups of four
+    /// `remaining_accounts` are those offers, each with its escrow vault,
+    /// buyer wallet and buyer token account (repeat the wallet for SOL
+    /// offers); any payout split's recipients follow.
+    pub fn accept_offer(ctx: Context<AcceptOffer>, rejected_offers: u8) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let offer = &ctx.accounts.offer;
+        let genome = &ctx.accounts.genome;
+        let now = unix_timestamp()?;
+
+        genome.assert_live()?;
+        genome.assert_consent()?;
+        offer.assert_open(now)?;
+        assert_offer_floor(
+            genome,
+            ctx.accounts.listing.as_deref(),
+            offer.amount,
+            offer.payment_mint,
+        )?;
+        let rejected_accounts = usize::from(rejected_offers) * 4;
+        require!(
+            ctx.remaining_accounts.len() >= rejected_accounts,
+            GenomeError::InvalidBatchAccounts
+        );
+        let (rejected_accounts, split_accounts) =
+            ctx.remaining_accounts.split_at(rejected_accounts);
+
+        let split =
+            SaleSplit::for_genome(&ctx.accounts.config, genome, genome.owner, offer.amount)?;
+        settle_sale(
+            &Escrow::for_offer(
+                offer,
+                &ctx.accounts.token_program,
+                &ctx.accounts.system_program,
+                ctx.accounts.escrow_vault.as_ref(),
+                ctx.accounts.sol_vault.as_ref(),
+            ),
+            sale_payees!(
+                ctx.accounts,
+                seller: (
+                    ctx.accounts.owner_token_account.as_ref(),
+                    Some(ctx.accounts.owner.to_account_info())
+                ),
+                seller_split: seller_split(
+                    &ctx.accounts.genome,
+                    ctx.accounts.payout_split.as_ref(),
+                    split_accounts,
+                )?,
+                referral: None
+            ),
+            &split,
+            0,
+        )?;
+        refund_offers(
+            genome.key(),
+            offer.key(),
+            rejected_accounts,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.program_id,
+            now,
+        )?;
+
+        if let Some(listing) = ctx.accounts.listing.as_mut() {
+            let (Some(category_index), Some(seller_profile), Some(market_stats)) = (
+                ctx.accounts.category_index.as_deref_mut(),
+                ctx.accounts.seller_profile.as_deref_mut(),
+                ctx.accounts.market_stats.as_deref_mut(),
+            ) else {
+                return err!(GenomeError::ListingAccountsMissing);
+            };
+            // Auction bids are reclaimed individually with `withdraw_bid`.
+            if listing.auction.is_some() {
+                listing.depositor = None;
+            }
+            let escrow = Escrow::new(
+                listing,
+                &ctx.accounts.token_program,
+                &ctx.accounts.system_program,
+                ctx.accounts.listing_escrow_vault.as_ref(),
+                ctx.accounts.listing_sol_vault.as_ref(),
+            );
+            if let Some(depositor) = listing.depositor {
+                escrow.pay_to(
+                    depositor,
+                    ctx.accounts.depositor_token_account.as_ref(),
+                    ctx.accounts.depositor.as_ref().map(|d| d.to_account_info()),
+                    listing.price,
+                )?;
+            }
+            escrow.pay(
+                ctx.accounts.listing_owner_token_account.as_ref(),
+                Some(ctx.accounts.owner.to_account_info()),
+                listing.collateral_amount,
+            )?;
+            listing.depositor = None;
+            listing.release_collateral();
+            listing.set_status(TransactionStatus::Cancelled, now);
+            ctx.accounts.genome.active_listings =
+                ctx.accounts.genome.active_listings.saturating_sub(1);
+            seller_profile.record_closed_listing();
+            market_stats.record_closed_listing();
+            category_index.remove(&listing.key())?;
+        }
+
+        let access_grant = &mut ctx.accounts.access_grant;
+        access_grant.reissue(now)?;
+        access_grant.genome = offer.genome;
+        access_grant.transaction = offer.key();
+        access_grant.buyer = offer.buyer;
+        access_grant.granted_at = now;
+        access_grant.expires_at = now
+            .checked_add(offer.access_duration)
+            .ok_or(GenomeError::MathOverflow)?;
+        access_grant.declared_use = offer.declared_use;
+        access_grant.access_level = offer.access_level;
+        access_grant.bump = *ctx.bumps.get("access_grant").unwrap();
+
+        let genome = &mut ctx.accounts.genome;
+        let access_log = &mut ctx.accounts.access_log;
+        access_log.open(
+            genome.key(),
+            genome.access_log_page,
+            *ctx.bumps.get("access_log").unwrap(),
+        );
+        log_access(
+            genome,
+            access_log,
+            None,
+            vec![AccessRecord {
+                buyer: offer.buyer,
+                timestamp: now,
+                access_level: offer.access_level,
+            }],
+        )?;
+
+        emit!(OfferAccepted {
+            offer: offer.key(),
+            genome: offer.genome,
+            seller: genome.owner,
+            buyer: offer.buyer,
+            amount: offer.amount,
+            fee: split.fee,
+            royalty: split.royalty,
+            rejected: rejected_offers,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn reject_offer(ctx: Context<RejectOffer>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let offer = &ctx.accounts.offer;
+
+        Escrow::for_offer(
+            offer,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        )
+        .pay(
+            ctx.accounts.buyer_token_account.as_ref(),
+            Some(ctx.accounts.buyer.to_account_info()),
+            offer.amount,
+        )?;
+
+        emit!(OfferRejected {
+            offer: offer.key(),
+            genome: offer.genome,
+            buyer: offer.buyer,
+            refund_amount: offer.amount,
+            timestamp: unix_timestamp()?,
+        });
+
+        Ok(())
+    }
+
+    pub fn reclaim_offer(ctx: Context<ReclaimOffer>) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+
+        let offer = &ctx.accounts.offer;
+        let now = unix_timestamp()?;
+
+        require!(offer.is_expired(now), GenomeError::OfferNotExpired);
+
+        Escrow::for_offer(
+            offer,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        )
+        .pay(
+            ctx.accounts.buyer_token_account.as_ref(),
+            Some(ctx.accounts.buyer.to_account_info()),
+            offer.amount,
+        )?;
+
+        emit!(OfferExpired {
+            offer: offer.key(),
+            genome: offer.genome,
+            buyer: offer.buyer,
+            refund_amount: offer.amount,
+            expires_at: offer.expires_at,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+/// Opens a listing for `create_transaction` and `create_bundle_transaction`;
+/// `genome_ids` is empty unless the listing is a bundle.
+fn create_listing(
+    ctx: Context<CreateTransaction>,
+    nonce: u64,
+    genome_id: String,
+    genome_ids: Vec<String>,
+    terms: ListingTerms,
+) -> Result<()> {
+    ctx.accounts.config.assert_not_paused()?;
+    validate_len(&genome_id, MAX_ID_LEN)?;
+
+    let genome = &mut ctx.accounts.genome;
+    let transaction = &mut ctx.accounts.transaction;
+    let user = &ctx.accounts.user;
+    let now = unix_timestamp()?;
+
+    terms.validate(now)?;
+    ctx.accounts
+        .config
+        .assert_listing_duration(terms.listing_expires_at - now)?;
+    genome.assert_live()?;
+    genome.assert_resale_unlocked(ctx.accounts.config.resale_lock, now)?;
+    genome.assert_not_embargoed(now)?;
+    genome.assert_consent()?;
+    ctx.accounts.blacklist.assert_allowed(&genome.owner)?;
+    genome.authorize_listing(
+        user.key(),
+        ctx.accounts.multisig.as_deref_mut(),
+        MultisigAction::CreateTransaction {
+            price: terms.price,
+            access_duration: terms.access_duration,
+        },
+    )?;
+    require!(
+        genome_id == genome.key().to_string(),
+        GenomeError::GenomeMismatch
+    );
+    if !genome_ids.is_empty() {
+        assert_bundle_genomes(genome, &genome_ids[1..], ctx.remaining_accounts)?;
+    }
+
+    genome.open_listing()?;
+
+    let seller_profile = &mut ctx.accounts.seller_profile;
+    seller_profile.open(genome.owner, *ctx.bumps.get("seller_profile").unwrap());
+    seller_profile.start_listing(now, ctx.accounts.config.listing_cooldown)?;
+    seller_profile.record_listing()?;
+    ctx.accounts.market_stats.record_listing();
+
+    ctx.accounts.category_index.push(transaction.key())?;
+    transaction.category_index = ctx.accounts.category_index.key();
+    transaction.genome_id = genome_id;
+    transaction.genome_ids = genome_ids;
+    transaction.genome = genome.key();
+    transaction.seller = genome.owner;
+    transaction.nonce = nonce;
+    transaction.bump = *ctx.bumps.get("transaction").unwrap();
+    transaction.apply_terms(terms);
+    transaction.whitelist = ctx.accounts.whitelist.as_ref().map(|w| w.key());
+    transaction.set_status(TransactionStatus::Created, now);
+    transaction.created_at = now;
+    transaction.payment_mint = resolve_payment_mint(
+        ctx.accounts.mint.as_ref().map(|mint| mint.key()),
+        ctx.accounts.escrow_vault.is_some(),
+        ctx.accounts.sol_vault.is_some(),
+    )?;
+    ctx.accounts
+        .config
+        .assert_mint_allowed(transaction.payment_mint)?;
+    transaction.payment_decimals = ctx
+        .accounts
+        .mint
+        .as_ref()
+        .map_or(SOL_DECIMALS, |mint| mint.decimals);
+    transaction.escrow_bump = match transaction.payment_mint {
+        Some(_) => *ctx.bumps.get("escrow_vault").unwrap(),
+        None => *ctx.bumps.get("sol_vault").unwrap(),
+    };
+
+    let escrow = Escrow::new(
+        transaction,
+        &ctx.accounts.token_program,
+        &ctx.accounts.system_program,
+        ctx.accounts.escrow_vault.as_ref(),
+        ctx.accounts.sol_vault.as_ref(),
+    );
+    escrow.fund_rent(user)?;
+    transaction.collateral_amount =
+        transaction.required_collateral(ctx.accounts.config.collateral_bps)?;
+    if transaction.collateral_amount > 0 {
+        escrow.collect(
+            user,
+            ctx.accounts.user_token_account.as_ref(),
+            transaction.collateral_amount,
+        )?;
+    }
+
+    emit!(TransactionCreated {
+        transaction: transaction.key(),
+        genome: transaction.genome,
+        seller: transaction.seller,
+        payment_mint: transaction.payment_mint,
+        price: transaction.price,
+        access_duration: transaction.access_duration,
+        listing_expires_at: transaction.listing_expires_at,
+        terms_hash: transaction.terms_hash,
+        genome_ids: transaction.genome_ids.clone(),
+        collateral_amount: transaction.collateral_amount,
+        listing_notes: transaction.listing_notes.clone(),
+        timestamp: transaction.created_at,
+    });
+
+    Ok(())
+}
+
+/// Checks an offer on `genome` against the floor of `listing`, which must be
+/// one of its open listings and is required while it has any.
+fn assert_offer_floor(
+    genome: &Genome,
+    listing: Option<&Transaction>,
+    amount: u64,
+    payment_mint: Option<Pubkey>,
+) -> Result<()> {
+    let Some(listing) = listing else {
+        require!(genome.active_listings == 0, GenomeError::ListingRequired);
+        return Ok(());
+    };
+    require!(
+        listing.status == TransactionStatus::Created,
+        GenomeError::InvalidTransactionStatus
+    );
+    listing.assert_offer_floor(amount, payment_mint)
+}
+
+/// Refunds and closes each competing offer described by a group of
+/// `accept_offer`'s remaining accounts.
+fn refund_offers<'info>(
+    genome: Pubkey,
+    accepted: Pubkey,
+    accounts: &[AccountInfo<'info>],
+    token_program: &Program<'info, Token>,
+    system_program: &Program<'info, System>,
+    program_id: &Pubkey,
+    now: i64,
+) -> Result<()> {
+    for group in accounts.chunks(4) {
+        require!(group[0].is_writable, GenomeError::InvalidBatchAccounts);
+        let offer = Account::<Offer>::try_from(&group[0])?;
+        offer.assert_rejectable(offer.key(), genome, accepted)?;
+        let buyer = SystemAccount::try_from(&group[2])?;
+
+        let (token_vault, sol_vault, buyer_token_account) = match offer.payment_mint {
+            Some(_) => (
+                Some(Account::<TokenAccount>::try_from(&group[1])?),
+                None,
+                Some(Account::<TokenAccount>::try_from(&group[3])?),
+            ),
+            None => (None, Some(SystemAccount::try_from(&group[1])?), None),
+        };
+        let vault_seed = match offer.payment_mint {
+            Some(_) => ESCROW_SEED,
+            None => SOL_VAULT_SEED,
+        };
+        let vault = Pubkey::create_program_address(
+            &[vault_seed, offer.key().as_ref(), &[offer.escrow_bump]],
+            program_id,
+        )
+        .map_err(|_| error!(GenomeError::InvalidPaymentAccounts))?;
+        require_keys_eq!(group[1].key(), vault, GenomeError::InvalidPaymentAccounts);
+
+        Escrow::for_offer(
+            &offer,
+            token_program,
+            system_program,
+            token_vault.as_ref(),
+            sol_vault.as_ref(),
+        )
+        .pay_to(
+            offer.buyer,
+            buyer_token_account.as_ref(),
+            Some(buyer.to_account_info()),
+            offer.amount,
+        )?;
+
+        emit!(OfferRejected {
+            offer: offer.key(),
+            genome,
+            buyer: offer.buyer,
+            refund_amount: offer.amount,
+            timestamp: now,
+        });
+        offer.close(buyer.to_account_info())?;
+    }
+    Ok(())
+}
+
+/// Shared body of `update_metadata` and `update_genome`.
+fn patch_genome(ctx: Context<UpdateMetadata>, patch: GenomePatch) -> Result<()> {
+    ctx.accounts.config.assert_not_paused()?;
+
+    let genome = &mut ctx.accounts.genome;
+
+    genome.assert_owner(ctx.accounts.authority.key())?;
+    genome.assert_live()?;
+
+    genome.apply_patch(patch)?;
+    genome.updated_at = unix_timestamp()?;
+
+    emit!(MetadataUpdated {
+        genome: genome.key(),
+        owner: genome.owner,
+        metadata: genome.metadata.clone(),
+        metadata_schema_version: genome.metadata_schema_version,
+        category: genome.category,
+        tags: genome.tags.clone(),
+        preview_uri: genome.preview_uri.clone(),
+        preview_hash: genome.preview_hash,
+        file_size_bytes: genome.file_size_bytes,
+        format: genome.format,
+        reference_build: genome.reference_build,
+        timestamp: genome.updated_at,
+    });
+
+    Ok(())
+}
+
+/// Shared body of `execute_transaction` and `lock_transaction`. With
+/// `hold_for_receipt` the sale is left `Locked` and unsettled instead of
+/// following the dispute-window rules.
+fn execute_sale(
+    ctx: Context<ExecuteTransaction>,
+    terms_hash: [u8; 32],
+    buyer_jurisdiction: [u8; 2],
+    history_page: u32,
+    declared_use: u8,
+    quantity: u64,
+    referrer: Option<Pubkey>,
+    hold_for_receipt: bool,
+) -> Result<()> {
+    ctx.accounts.config.assert_not_paused()?;
+
+    let transaction = &mut ctx.accounts.transaction;
+    let buyer = &ctx.accounts.buyer;
+    let now = unix_timestamp()?;
+
+    transaction.begin_execution()?;
+    require!(
+        transaction.depositor == Some(buyer.key()),
+        GenomeError::EscrowNotFunded
+    );
+    transaction.assert_terms(&terms_hash)?;
+    transaction.assert_jurisdiction(&buyer_jurisdiction)?;
+    ctx.accounts.genome.assert_use(declared_use)?;
+    ctx.accounts
+        .genome
+        .assert_heartbeat_fresh(ctx.accounts.config.heartbeat_ttl, now)?;
+    ctx.accounts.genome.assert_consent()?;
+    if ctx.accounts.refund_record.owner == ctx.program_id {
+        Account::<RefundRecord>::try_from(&ctx.accounts.refund_record)?
+            .assert_cooled_down(ctx.accounts.config.refund_cooldown, now)?;
+    }
+    transaction.set_referrer(referrer, buyer.key())?;
+    if let (Some(referrer), Some(profile)) =
+        (referrer, ctx.accounts.referral_profile.as_deref_mut())
+    {
+        profile.open(referrer, *ctx.bumps.get("referral_profile").unwrap());
+    }
+    if hold_for_receipt {
+        require!(transaction.is_exclusive(), GenomeError::InvalidLicenseCount);
+    }
+    transaction.assert_buyer_allowed(buyer.key(), ctx.accounts.whitelist.as_deref())?;
+    transaction.assert_token_gate(ctx.accounts.gate_token_account.as_deref())?;
+    transaction.assert_buyer_reputation(ctx.accounts.buyer_profile.as_deref())?;
+    if transaction.max_per_buyer > 0 {
+        let buyer_licenses = ctx
+            .accounts
+            .buyer_licenses
+            .as_deref_mut()
+            .ok_or(GenomeError::BuyerLicensesMissing)?;
+        buyer_licenses.transaction = transaction.key();
+        buyer_licenses.buyer = buyer.key();
+        buyer_licenses.bump = *ctx.bumps.get("buyer_licenses").unwrap();
+        buyer_licenses.record(transaction.max_per_buyer)?;
+    }
+    transaction.assert_purchasable(now)?;
+
+    // USD-pegged listings are repriced at the oracle rate and unit-priced
+    // ones by the quantity bought; the buyer's deposit is topped up or
+    // partly refunded to match.
+    let mut amount = transaction.take_units(quantity)?;
+    if let Some(price_in_usd) = transaction.price_in_usd {
+        let price_feed = ctx
+            .accounts
+            .price_feed
+            .as_ref()
+            .ok_or(GenomeError::OracleMismatch)?;
+        let converted =
+            oracle_token_amount(price_feed, price_in_usd, transaction.payment_decimals, now)?;
+        let (total, conversion_fee) =
+            with_conversion_fee(converted, ctx.accounts.config.conversion_fee_bps)?;
+        transaction.conversion_fee = conversion_fee;
+        amount = Some(total);
+    }
+    if let Some(amount) = amount {
+        let escrow = Escrow::new(
+            transaction,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        );
+        if amount > transaction.price {
+            escrow.collect(
+                buyer,
+                ctx.accounts.buyer_token_account.as_ref(),
+                amount - transaction.price,
+            )?;
+        } else {
+            escrow.pay(
+                ctx.accounts.buyer_token_account.as_ref(),
+                Some(buyer.to_account_info()),
+                transaction.price - amount,
+            )?;
+        }
+        transaction.price = amount;
+    }
+
+    let genome = &mut ctx.accounts.genome;
+    let split = SaleSplit::for_sale(&ctx.accounts.config, genome, transaction)?
+        .with_referral(transaction.referrer, ctx.accounts.config.referral_bps)?;
+    // Bundle grant accounts come first, then any payout split recipients.
+    let bundle_accounts = transaction.genome_ids.len().saturating_sub(1) * 2;
+    require!(
+        ctx.remaining_accounts.len() >= bundle_accounts,
+        GenomeError::InvalidBatchAccounts
+    );
+    let (bundle_accounts, split_accounts) = ctx.remaining_accounts.split_at(bundle_accounts);
+
+    // With a dispute window configured, proceeds of exclusive sales stay
+    // in escrow until the seller claims them or an arbitrator resolves a
+    // dispute. Non-exclusive licenses always settle immediately.
+    let settle_now = !hold_for_receipt
+        && (ctx.accounts.config.dispute_window == 0 || !transaction.is_exclusive());
+    if settle_now {
+        // With no dispute possible the collateral has nothing left to back.
+        let collateral = transaction.release_collateral();
+        // Sellers with an `Earnings` balance are paid into it and withdraw
+        // later, so a bad payout destination cannot block the sale.
+        let (seller_token_account, seller_wallet) =
+            match ctx.accounts.seller_earnings.as_deref_mut() {
+                Some(earnings) if genome.payout_split.is_none() => {
+                    earnings.accrue(
+                        split
+                            .seller_amount
+                            .checked_add(collateral)
+                            .ok_or(GenomeError::MathOverflow)?,
+                    )?;
+                    (
+                        ctx.accounts.earnings_vault.as_ref(),
+                        ctx.accounts
+                            .earnings_sol_vault
+                            .as_ref()
+                            .map(|v| v.to_account_info()),
+                    )
+                }
+                _ => (
+                    ctx.accounts.seller_token_account.as_ref(),
+                    ctx.accounts.seller.as_ref().map(|s| s.to_account_info()),
+                ),
+            };
+        let escrow = Escrow::new(
+            transaction,
+            &ctx.accounts.token_program,
+            &ctx.accounts.system_program,
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.sol_vault.as_ref(),
+        );
+        settle_sale(
+            &escrow,
+            sale_payees!(
+                ctx.accounts,
+                seller: (seller_token_account, seller_wallet),
+                seller_split: seller_split(
+                    genome,
+                    ctx.accounts.payout_split.as_ref(),
+                    split_accounts,
+                )?,
+                referral: referral_payee!(ctx.accounts, transaction.referrer)
+            ),
+            &split,
+            collateral,
+        )?;
+    }
+
+    let executed_at = now;
+    transaction.complete_execution(buyer.key(), executed_at, settle_now)?;
+    if !settle_now {
+        genome.record_unsettled_sale()?;
+    }
+    if hold_for_receipt {
+        transaction.set_status(TransactionStatus::Locked, now);
+    }
+    let seller_profile = &mut ctx.accounts.seller_profile;
+    seller_profile.record_sale(transaction.price)?;
+    let market_stats = &mut ctx.accounts.market_stats;
+    market_stats.record_sale(transaction.price);
+    market_stats.record_epoch_volume(
+        current_epoch()?,
+        transaction.price,
+        ctx.accounts.config.epoch_volume_cap,
+    )?;
+    if transaction.status != TransactionStatus::Created {
+        genome.active_listings = genome.active_listings.saturating_sub(1);
+        seller_profile.record_closed_listing();
+        market_stats.record_closed_listing();
+        ctx.accounts.category_index.remove(&transaction.key())?;
+    }
+
+    let access_grant = &mut ctx.accounts.access_grant;
+    access_grant.reissue(executed_at)?;
+    access_grant.genome = transaction.genome;
+    access_grant.transaction = transaction.key();
+    access_grant.buyer = buyer.key();
+    access_grant.granted_at = executed_at;
+    access_grant.expires_at = executed_at
+        .checked_add(transaction.access_duration)
+        .ok_or(GenomeError::MathOverflow)?;
+    access_grant.terms_hash = terms_hash;
+    access_grant.terms_accepted_at = executed_at;
+    access_grant.buyer_jurisdiction = buyer_jurisdiction;
+    access_grant.auto_renew = false;
+    access_grant.declared_use = declared_use;
+    access_grant.access_level = transaction.access_level;
+    access_grant.bump = *ctx.bumps.get("access_grant").unwrap();
+
+    let buyer_history = &mut ctx.accounts.buyer_history;
+    buyer_history.open(
+        buyer.key(),
+        history_page,
+        *ctx.bumps.get("buyer_history").unwrap(),
+        ctx.accounts.previous_history.as_deref(),
+    )?;
+    buyer_history.record(PurchaseRecord {
+        genome: transaction.genome,
+        transaction: transaction.key(),
+        price: transaction.price,
+        purchased_at: executed_at,
+        expires_at: access_grant.expires_at,
+    })?;
+
+    let access_log = &mut ctx.accounts.access_log;
+    access_log.open(
+        genome.key(),
+        genome.access_log_page,
+        *ctx.bumps.get("access_log").unwrap(),
+    );
+    log_access(
+        genome,
+        access_log,
+        None,
+        vec![AccessRecord {
+            buyer: buyer.key(),
+            timestamp: executed_at,
+            access_level: access_grant.access_level,
+        }],
+    )?;
+
+    if !transaction.genome_ids.is_empty() {
+        let genomes = issue_bundle_grants(
+            transaction,
+            access_grant,
+            buyer,
+            &ctx.accounts.system_program,
+            bundle_accounts,
+            ctx.program_id,
+        )?;
+        emit!(BundleAccessGranted {
+            transaction: transaction.key(),
+            buyer: buyer.key(),
+            genomes,
+            expires_at: access_grant.expires_at,
+            timestamp: executed_at,
+        });
+    }
+
+    emit!(TransactionExecuted {
+        transaction: transaction.key(),
+        genome: transaction.genome,
+        seller: transaction.seller,
+        buyer: buyer.key(),
+        price: transaction.price,
+        fee: split.fee,
+        royalty: split.royalty,
+        licenses_issued: transaction.licenses_issued,
+        buyer_jurisdiction,
+        declared_use,
+        access_level: transaction.access_level,
+        referrer,
+        timestamp: executed_at,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [CONFIG_SEED],
+        bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Blacklist::INIT_SPACE,
+        seeds = [BLACKLIST_SEED],
+        bump,
+    )]
+    pub blacklist: Account<'info, Blacklist>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MarketStats::INIT_SPACE,
+        seeds = [MARKET_STATS_SEED],
+        bump,
+    )]
+    pub market_stats: Account<'info, MarketStats>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AdminConfig<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ GenomeError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+/// `init_if_needed` covers deployments whose config predates the blacklist.
+#[derive(Accounts)]
+pub struct BlacklistSeller<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ GenomeError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + Blacklist::INIT_SPACE,
+        seeds = [BLACKLIST_SEED],
+        bump,
+    )]
+    pub blacklist: Account<'info, Blacklist>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnblacklistSeller<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ GenomeError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [BLACKLIST_SEED], bump = blacklist.bump)]
+    pub blacklist: Account<'info, Blacklist>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(holder: Pubkey)]
+pub struct AssignGrantQuota<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ GenomeError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + GrantQuota::INIT_SPACE,
+        seeds = [GRANT_QUOTA_SEED, holder.as_ref()],
+        bump,
+    )]
+    pub grant_quota: Account<'info, GrantQuota>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Genomes live at a PDA of the owner and the SHA-256 of `storage_id`, so
+/// clients can derive the address offline. The id is hashed because seeds
+/// are limited to 32 bytes and storage ids (e.g. IPFS CIDs) are longer.
+#[derive(Accounts)]
+#[instruction(storage_id: String, index_page: u32)]
+pub struct InitializeGenome<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [MARKET_STATS_SEED], bump = market_stats.bump)]
+    pub market_stats: Account<'info, MarketStats>,
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Genome::INIT_SPACE,
+        seeds = [GENOME_SEED, user.key().as_ref(), &hash(storage_id.as_bytes()).to_bytes()],
+        bump,
+    )]
+    pub genome: Account<'info, Genome>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserIndex::INIT_SPACE,
+        seeds = [USER_INDEX_SEED, user.key().as_ref(), &index_page.to_le_bytes()],
+        bump,
+    )]
+    pub user_index: Account<'info, UserIndex>,
+    /// Required when `index_page` is opened after the first page.
+    #[account(
+        seeds = [USER_INDEX_SEED, user.key().as_ref(), &index_page.saturating_sub(1).to_le_bytes()],
+        bump = previous_index.bump,
+    )]
+    pub previous_index: Option<Account<'info, UserIndex>>,
+    /// Page 0 of the owner's index, which keeps their genome count; required
+    /// when `index_page` is a later page.
+    #[account(
+        mut,
+        seeds = [USER_INDEX_SEED, user.key().as_ref(), &0u32.to_le_bytes()],
+        bump = index_head.bump,
+    )]
+    pub index_head: Option<Account<'info, UserIndex>>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(index_page: u32)]
+pub struct InitializeGenomesBatch<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [MARKET_STATS_SEED], bump = market_stats.bump)]
+    pub market_stats: Account<'info, MarketStats>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserIndex::INIT_SPACE,
+        seeds = [USER_INDEX_SEED, user.key().as_ref(), &index_page.to_le_bytes()],
+        bump,
+    )]
+    pub user_index: Account<'info, UserIndex>,
+    /// Required when `index_page` is opened after the first page.
+    #[account(
+        seeds = [USER_INDEX_SEED, user.key().as_ref(), &index_page.saturating_sub(1).to_le_bytes()],
+        bump = previous_index.bump,
+    )]
+    pub previous_index: Option<Account<'info, UserIndex>>,
+    /// Page 0 of the owner's index, which keeps their genome count; required
+    /// when `index_page` is a later page.
+    #[account(
+        mut,
+        seeds = [USER_INDEX_SEED, user.key().as_ref(), &0u32.to_le_bytes()],
+        bump = index_head.bump,
+    )]
+    pub index_head: Option<Account<'info, UserIndex>>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(storage_id: String)]
+pub struct NewVersion<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, has_one = owner @ GenomeError::NotOwner)]
+    pub previous_genome: Account<'info, Genome>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Genome::INIT_SPACE,
+        seeds = [GENOME_SEED, owner.key().as_ref(), &hash(storage_id.as_bytes()).to_bytes()],
+        bump,
+    )]
+    pub genome: Account<'info, Genome>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyHash<'info> {
+    pub genome: Account<'info, Genome>,
+}
+
+#[derive(Accounts)]
+pub struct DeleteGenome<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub genome: Account<'info, Genome>,
+    /// The index page listing `genome`; omitted for genomes that are not
+    /// indexed under their current owner, e.g. after a transfer.
+    #[account(
+        mut,
+        seeds = [USER_INDEX_SEED, genome.owner.as_ref(), &user_index.page.to_le_bytes()],
+        bump = user_index.bump,
+    )]
+    pub user_index: Option<Account<'info, UserIndex>>,
+    /// Page 0 of the owner's index, when `user_index` is a later page.
+    #[account(
+        mut,
+        seeds = [USER_INDEX_SEED, genome.owner.as_ref(), &0u32.to_le_bytes()],
+        bump = index_head.bump,
+    )]
+    pub index_head: Option<Account<'info, UserIndex>>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseGenome<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, close = owner, has_one = owner @ GenomeError::NotOwner)]
+    pub genome: Account<'info, Genome>,
+    /// The index page listing `genome`; omitted for genomes that are not
+    /// indexed under their current owner, e.g. after a transfer.
+    #[account(
+        mut,
+        seeds = [USER_INDEX_SEED, genome.owner.as_ref(), &user_index.page.to_le_bytes()],
+        bump = user_index.bump,
+    )]
+    pub user_index: Option<Account<'info, UserIndex>>,
+    /// Page 0 of the owner's index, when `user_index` is a later page.
+    #[account(
+        mut,
+        seeds = [USER_INDEX_SEED, genome.owner.as_ref(), &0u32.to_le_bytes()],
+        bump = index_head.bump,
+    )]
+    pub index_head: Option<Account<'info, UserIndex>>,
+    /// Required while the genome is in the subscription pool.
+    #[account(mut, seeds = [SUBSCRIPTION_POOL_SEED], bump = pool.bump)]
+    pub pool: Option<Account<'info, SubscriptionPool>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, pool.as_ref().map(|p| p.key()).unwrap_or_default().as_ref()],
+        bump,
+    )]
+    pub pool_vault: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateGenome<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, has_one = owner @ GenomeError::NotOwner)]
+    pub genome: Account<'info, Genome>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMetadata<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub genome: Account<'info, Genome>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetDelegate<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub genome: Account<'info, Genome>,
+    #[account(
+        mut,
+        constraint = Some(multisig.key()) == genome.multisig @ GenomeError::MultisigMismatch,
+    )]
+    pub multisig: Option<Account<'info, MultisigOwner>>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPayoutSplit<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub genome: Account<'info, Genome>,
+    #[account(
+        mut,
+        constraint = Some(multisig.key()) == genome.multisig @ GenomeError::MultisigMismatch,
+    )]
+    pub multisig: Option<Account<'info, MultisigOwner>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + PayoutSplit::INIT_SPACE,
+        seeds = [PAYOUT_SPLIT_SEED, genome.key().as_ref()],
+        bump,
+    )]
+    pub payout_split: Account<'info, PayoutSplit>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TransferOwnership<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub genome: Account<'info, Genome>,
+    #[account(
+        mut,
+        constraint = Some(multisig.key()) == genome.multisig @ GenomeError::MultisigMismatch,
+    )]
+    pub multisig: Option<Account<'info, MultisigOwner>>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RecoverGenome<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub genome: Account<'info, Genome>,
+    pub recovery_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateMultisig<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, has_one = owner @ GenomeError::NotOwner)]
+    pub genome: Account<'info, Genome>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + MultisigOwner::INIT_SPACE,
+        seeds = [MULTISIG_SEED, genome.key().as_ref()],
+        bump,
+    )]
+    pub multisig: Account<'info, MultisigOwner>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveAction<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [MULTISIG_SEED, multisig.genome.as_ref()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, MultisigOwner>,
+    pub approver: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(category: GenomeCategory, page: u32)]
+pub struct OpenCategoryPage<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + CategoryIndex::INIT_SPACE,
+        seeds = [CATEGORY_INDEX_SEED, &category.seed(), &page.to_le_bytes()],
+        bump,
+    )]
+    pub category_index: Account<'info, CategoryIndex>,
+    /// Required for every page after the first.
+    #[account(
+        seeds = [CATEGORY_INDEX_SEED, &category.seed(), &page.saturating_sub(1).to_le_bytes()],
+        bump = previous_page.bump,
+    )]
+    pub previous_page: Option<Account<'info, CategoryIndex>>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Listings live at a PDA of the genome, its owner and a client-chosen
+/// `nonce`, so each is derivable and a nonce cannot be listed twice.
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct CreateTransaction<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [BLACKLIST_SEED], bump = blacklist.bump)]
+    pub blacklist: Account<'info, Blacklist>,
+    #[account(mut)]
+    pub genome: Account<'info, Genome>,
+    /// Non-full page of the catalog for the genome's category.
+    #[account(
+        mut,
+        seeds = [CATEGORY_INDEX_SEED, &genome.category.seed(), &category_index.page.to_le_bytes()],
+        bump = category_index.bump,
+    )]
+    pub category_index: Account<'info, CategoryIndex>,
+    #[account(
+        mut,
+        constraint = Some(multisig.key()) == genome.multisig @ GenomeError::MultisigMismatch,
+    )]
+    pub multisig: Option<Account<'info, MultisigOwner>>,
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Transaction::INIT_SPACE,
+        seeds = [TRANSACTION_SEED, genome.key().as_ref(), genome.owner.as_ref(), &nonce.to_le_bytes()],
+        bump,
+    )]
+    pub transaction: Account<'info, Transaction>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + SellerProfile::INIT_SPACE,
+        seeds = [SELLER_PROFILE_SEED, genome.owner.as_ref()],
+        bump,
+    )]
+    pub seller_profile: Account<'info, SellerProfile>,
+    #[account(mut, seeds = [MARKET_STATS_SEED], bump = market_stats.bump)]
+    pub market_stats: Account<'info, MarketStats>,
+    /// Token escrow for SPL listings; omitted along with `mint` for native SOL.
+    #[account(
+        init,
+        payer = user,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = escrow_vault,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    pub mint: Option<Account<'info, Mint>>,
+    /// Lamport escrow for native SOL listings.
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, transaction.key().as_ref()],
+        bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    /// Restricts the listing to the seller's whitelisted buyers when supplied.
+    #[account(seeds = [WHITELIST_SEED, genome.owner.as_ref()], bump = whitelist.bump)]
+    pub whitelist: Option<Account<'info, Whitelist>>,
+    /// Funds the seller's collateral on SPL listings.
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key() @ GenomeError::InvalidTokenAccount,
+        constraint = Some(user_token_account.mint) == mint.as_ref().map(|m| m.key()) @ GenomeError::MintMismatch,
+    )]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct RelistTransaction<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [BLACKLIST_SEED], bump = blacklist.bump)]
+    pub blacklist: Account<'info, Blacklist>,
+    #[account(
+        has_one = seller @ GenomeError::Unauthorized,
+        has_one = genome @ GenomeError::GenomeMismatch,
+    )]
+    pub previous_transaction: Account<'info, Transaction>,
+    #[account(mut)]
+    pub genome: Account<'info, Genome>,
+    /// Non-full page of the catalog for the genome's category.
+    #[account(
+        mut,
+        seeds = [CATEGORY_INDEX_SEED, &genome.category.seed(), &category_index.page.to_le_bytes()],
+        bump = category_index.bump,
+    )]
+    pub category_index: Account<'info, CategoryIndex>,
+    #[account(
+        mut,
+        constraint = Some(multisig.key()) == genome.multisig @ GenomeError::MultisigMismatch,
+    )]
+    pub multisig: Option<Account<'info, MultisigOwner>>,
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + Transaction::INIT_SPACE,
+        seeds = [TRANSACTION_SEED, genome.key().as_ref(), genome.owner.as_ref(), &nonce.to_le_bytes()],
+        bump,
+    )]
+    pub transaction: Account<'info, Transaction>,
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = 8 + SellerProfile::INIT_SPACE,
+        seeds = [SELLER_PROFILE_SEED, genome.owner.as_ref()],
+        bump,
+    )]
+    pub seller_profile: Account<'info, SellerProfile>,
+    #[account(mut, seeds = [MARKET_STATS_SEED], bump = market_stats.bump)]
+    pub market_stats: Account<'info, MarketStats>,
+    /// Token escrow, for listings of the previous listing's SPL mint.
+    #[account(
+        init,
+        payer = seller,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = escrow_vault,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    pub mint: Option<Account<'info, Mint>>,
+    /// Lamport escrow for native SOL listings.
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, transaction.key().as_ref()],
+        bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    /// Funds the seller's collateral on SPL listings.
+    #[account(
+        mut,
+        constraint = seller_token_account.owner == seller.key() @ GenomeError::InvalidTokenAccount,
+        constraint = Some(seller_token_account.mint) == mint.as_ref().map(|m| m.key()) @ GenomeError::MintMismatch,
+    )]
+    pub seller_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePrice<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub transaction: Account<'info, Transaction>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateListingNotes<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub transaction: Account<'info, Transaction>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExtendListing<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub transaction: Account<'info, Transaction>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CrankRelist<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub transaction: Account<'info, Transaction>,
+    pub keeper: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub transaction: Account<'info, Transaction>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    #[account(
+        constraint = Some(whitelist.key()) == transaction.whitelist @ GenomeError::BuyerNotAllowed,
+    )]
+    pub whitelist: Option<Account<'info, Whitelist>>,
+    /// Buyer's holding of the listing's gating token, if it has one.
+    #[account(
+        constraint = gate_token_account.owner == buyer.key() @ GenomeError::InvalidTokenAccount,
+    )]
+    pub gate_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == buyer.key() @ GenomeError::InvalidTokenAccount,
+        constraint = Some(buyer_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub buyer_token_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub transaction: Account<'info, Transaction>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    #[account(
+        constraint = Some(whitelist.key()) == transaction.whitelist @ GenomeError::BuyerNotAllowed,
+    )]
+    pub whitelist: Option<Account<'info, Whitelist>>,
+    /// Buyer's holding of the listing's gating token, if it has one.
+    #[account(
+        constraint = gate_token_account.owner == bidder.key() @ GenomeError::InvalidTokenAccount,
+    )]
+    pub gate_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = bidder,
+        space = 8 + Bid::INIT_SPACE,
+        seeds = [BID_SEED, transaction.key().as_ref(), bidder.key().as_ref()],
+        bump,
+    )]
+    pub bid: Account<'info, Bid>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    #[account(
+        mut,
+        constraint = bidder_token_account.owner == bidder.key() @ GenomeError::InvalidTokenAccount,
+        constraint = Some(bidder_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub bidder_token_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CommitBid<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    pub transaction: Account<'info, Transaction>,
+    #[account(
+        constraint = Some(whitelist.key()) == transaction.whitelist @ GenomeError::BuyerNotAllowed,
+    )]
+    pub whitelist: Option<Account<'info, Whitelist>>,
+    /// Buyer's holding of the listing's gating token, if it has one.
+    #[account(
+        constraint = gate_token_account.owner == bidder.key() @ GenomeError::InvalidTokenAccount,
+    )]
+    pub gate_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = bidder,
+        space = 8 + Bid::INIT_SPACE,
+        seeds = [BID_SEED, transaction.key().as_ref(), bidder.key().as_ref()],
+        bump,
+    )]
+    pub bid: Account<'info, Bid>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevealBid<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub transaction: Account<'info, Transaction>,
+    #[account(
+        mut,
+        seeds = [BID_SEED, transaction.key().as_ref(), bidder.key().as_ref()],
+        bump = bid.bump,
+    )]
+    pub bid: Account<'info, Bid>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    #[account(
+        mut,
+        constraint = bidder_token_account.owner == bidder.key() @ GenomeError::InvalidTokenAccount,
+        constraint = Some(bidder_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub bidder_token_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawBid<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    pub transaction: Account<'info, Transaction>,
+    #[account(
+        mut,
+        close = bidder,
+        seeds = [BID_SEED, transaction.key().as_ref(), bidder.key().as_ref()],
+        bump = bid.bump,
+    )]
+    pub bid: Account<'info, Bid>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    #[account(
+        mut,
+        constraint = bidder_token_account.owner == bidder.key() @ GenomeError::InvalidTokenAccount,
+        constraint = Some(bidder_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub bidder_token_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    terms_hash: [u8; 32],
+    buyer_jurisdiction: [u8; 2],
+    history_page: u32,
+    declared_use: u8,
+    quantity: u64,
+    referrer: Option<Pubkey>,
+)]
+pub struct ExecuteTransaction<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub transaction: Account<'info, Transaction>,
+    #[account(mut, address = transaction.genome @ GenomeError::GenomeMismatch)]
+    pub genome: Account<'info, Genome>,
+    #[account(
+        mut,
+        address = transaction.category_index @ GenomeError::CategoryIndexMismatch,
+    )]
+    pub category_index: Account<'info, CategoryIndex>,
+    /// Required when the genome splits its proceeds; the recipients' wallets
+    /// or token accounts follow in `remaining_accounts`, in share order.
+    #[account(
+        constraint = Some(payout_split.key()) == genome.payout_split @ GenomeError::PayoutSplitMismatch,
+    )]
+    pub payout_split: Option<Account<'info, PayoutSplit>>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    /// Keyed by genome and buyer; re-issued once the buyer's previous
+    /// license for the genome has expired or been revoked.
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + AccessGrant::INIT_SPACE,
+        seeds = [ACCESS_SEED, transaction.genome.as_ref(), buyer.key().as_ref()],
+        bump,
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+    /// Licenses this buyer holds from the listing; required when it caps
+    /// licenses per buyer.
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + BuyerLicenses::INIT_SPACE,
+        seeds = [BUYER_LICENSES_SEED, transaction.key().as_ref(), buyer.key().as_ref()],
+        bump,
+    )]
+    pub buyer_licenses: Option<Account<'info, BuyerLicenses>>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + BuyerHistory::INIT_SPACE,
+        seeds = [BUYER_HISTORY_SEED, buyer.key().as_ref(), &history_page.to_le_bytes()],
+        bump,
+    )]
+    pub buyer_history: Account<'info, BuyerHistory>,
+    /// Required when `history_page` is opened after the first page.
+    #[account(
+        seeds = [BUYER_HISTORY_SEED, buyer.key().as_ref(), &history_page.saturating_sub(1).to_le_bytes()],
+        bump = previous_history.bump,
+    )]
+    pub previous_history: Option<Account<'info, BuyerHistory>>,
+    /// Page of the genome's access log currently being written.
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + AccessLog::INIT_SPACE,
+        seeds = [ACCESS_LOG_SEED, genome.key().as_ref(), &genome.access_log_page.to_le_bytes()],
+        bump,
+    )]
+    pub access_log: Account<'info, AccessLog>,
+    #[account(
+        mut,
+        seeds = [SELLER_PROFILE_SEED, transaction.seller.as_ref()],
+        bump = seller_profile.bump,
+    )]
+    pub seller_profile: Account<'info, SellerProfile>,
+    /// The buyer's own profile, read for listings with a reputation gate.
+    #[account(
+        seeds = [SELLER_PROFILE_SEED, buyer.key().as_ref()],
+        bump = buyer_profile.bump,
+    )]
+    pub buyer_profile: Option<Account<'info, SellerProfile>>,
+    #[account(mut, seeds = [MARKET_STATS_SEED], bump = market_stats.bump)]
+    pub market_stats: Account<'info, MarketStats>,
+    #[account(
+        constraint = Some(whitelist.key()) == transaction.whitelist @ GenomeError::BuyerNotAllowed,
+    )]
+    pub whitelist: Option<Account<'info, Whitelist>>,
+    /// Buyer's holding of the listing's gating token, if it has one.
+    #[account(
+        constraint = gate_token_account.owner == buyer.key() @ GenomeError::InvalidTokenAccount,
+    )]
+    pub gate_token_account: Option<Account<'info, TokenAccount>>,
+    /// CHECK: Pyth price account, pinned at listing time; parsed and checked
+    /// for staleness in `oracle_token_amount`. Only USD-pegged listings.
+    #[account(
+        constraint = Some(price_feed.key()) == transaction.price_feed @ GenomeError::OracleMismatch,
+    )]
+    pub price_feed: Option<UncheckedAccount<'info>>,
+    /// CHECK: the buyer's `RefundRecord` with this seller. Always required
+    /// so the cooldown cannot be skipped; it is only read when it exists,
+    /// since buyers who were never refunded have none.
+    #[account(
+        seeds = [REFUND_RECORD_SEED, buyer.key().as_ref(), transaction.seller.as_ref()],
+        bump,
+    )]
+    pub refund_record: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    /// Settles the oracle price difference on USD-pegged SPL listings.
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == buyer.key() @ GenomeError::InvalidTokenAccount,
+        constraint = Some(buyer_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub buyer_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = seller_token_account.owner == transaction.seller @ GenomeError::InvalidTokenAccount,
+        constraint = Some(seller_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub seller_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = transaction.seller @ GenomeError::InvalidRecipient)]
+    pub seller: Option<SystemAccount<'info>>,
+    /// When supplied, the seller's proceeds accrue here instead of being
+    /// paid out, together with its vault for the listing's payment type.
+    #[account(
+        mut,
+        constraint = seller_earnings.seller == transaction.seller @ GenomeError::InvalidRecipient,
+        constraint = seller_earnings.payment_mint == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub seller_earnings: Option<Account<'info, Earnings>>,
+    #[account(
+        mut,
+        constraint = Some(earnings_vault.key()) == seller_earnings.as_ref().map(|e| e.vault) @ GenomeError::InvalidPaymentAccounts,
+    )]
+    pub earnings_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = Some(earnings_sol_vault.key()) == seller_earnings.as_ref().map(|e| e.vault) @ GenomeError::InvalidPaymentAccounts,
+    )]
+    pub earnings_sol_vault: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == config.treasury @ GenomeError::InvalidTokenAccount,
+        constraint = Some(treasury_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = config.treasury @ GenomeError::InvalidRecipient)]
+    pub treasury: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == genome.original_creator @ GenomeError::InvalidTokenAccount,
+        constraint = Some(creator_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub creator_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = genome.original_creator @ GenomeError::InvalidRecipient)]
+    pub creator: Option<SystemAccount<'info>>,
+    /// Credited with the referral cut; required when `referrer` is given.
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + ReferralProfile::INIT_SPACE,
+        seeds = [REFERRAL_SEED, referrer.unwrap_or_default().as_ref()],
+        bump,
+    )]
+    pub referral_profile: Option<Account<'info, ReferralProfile>>,
+    /// Receive the referral cut, checked against `referrer` when paid.
+    #[account(mut)]
+    pub referrer_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub referrer_wallet: Option<SystemAccount<'info>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConfirmReceipt<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub transaction: Account<'info, Transaction>,
+    #[account(mut, address = transaction.genome @ GenomeError::GenomeMismatch)]
+    pub genome: Account<'info, Genome>,
+    /// Required when the genome splits its proceeds; the recipients' wallets
+    /// or token accounts follow in `remaining_accounts`, in share order.
+    #[account(
+        constraint = Some(payout_split.key()) == genome.payout_split @ GenomeError::PayoutSplitMismatch,
+    )]
+    pub payout_split: Option<Account<'info, PayoutSplit>>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = seller_token_account.owner == transaction.seller @ GenomeError::InvalidTokenAccount,
+        constraint = Some(seller_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub seller_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = transaction.seller @ GenomeError::InvalidRecipient)]
+    pub seller: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == config.treasury @ GenomeError::InvalidTokenAccount,
+        constraint = Some(treasury_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = config.treasury @ GenomeError::InvalidRecipient)]
+    pub treasury: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == genome.original_creator @ GenomeError::InvalidTokenAccount,
+        constraint = Some(creator_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub creator_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = genome.original_creator @ GenomeError::InvalidRecipient)]
+    pub creator: Option<SystemAccount<'info>>,
+    /// Required when the sale was referred: the referrer's profile and the
+    /// accounts receiving their cut.
+    #[account(
+        mut,
+        seeds = [REFERRAL_SEED, transaction.referrer.unwrap_or_default().as_ref()],
+        bump = referral_profile.bump,
+    )]
+    pub referral_profile: Option<Account<'info, ReferralProfile>>,
+    #[account(mut)]
+    pub referrer_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub referrer_wallet: Option<SystemAccount<'info>>,
+    pub buyer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenDispute<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub transaction: Account<'info, Transaction>,
+    pub buyer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSlaBreach<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub transaction: Account<'info, Transaction>,
+    #[account(address = transaction.genome @ GenomeError::GenomeMismatch)]
+    pub genome: Account<'info, Genome>,
+    #[account(
+        seeds = [ACCESS_SEED, transaction.genome.as_ref(), buyer.key().as_ref()],
+        bump = access_grant.bump,
+        constraint = access_grant.transaction == transaction.key() @ GenomeError::AccessGrantMismatch,
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == buyer.key() @ GenomeError::InvalidTokenAccount,
+        constraint = Some(buyer_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub buyer_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = arbitrator @ GenomeError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub transaction: Account<'info, Transaction>,
+    #[account(mut, address = transaction.genome @ GenomeError::GenomeMismatch)]
+    pub genome: Account<'info, Genome>,
+    /// Required when the genome splits its proceeds; the recipients' wallets
+    /// or token accounts follow in `remaining_accounts`, in share order.
+    #[account(
+        constraint = Some(payout_split.key()) == genome.payout_split @ GenomeError::PayoutSplitMismatch,
+    )]
+    pub payout_split: Option<Account<'info, PayoutSplit>>,
+    #[account(
+        mut,
+        constraint = access_grant.transaction == transaction.key() @ GenomeError::AccessGrantMismatch,
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = Some(buyer_token_account.owner) == transaction.buyer @ GenomeError::InvalidTokenAccount,
+        constraint = Some(buyer_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub buyer_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, constraint = Some(buyer.key()) == transaction.buyer @ GenomeError::InvalidRecipient)]
+    pub buyer: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = seller_token_account.owner == transaction.seller @ GenomeError::InvalidTokenAccount,
+        constraint = Some(seller_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub seller_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = transaction.seller @ GenomeError::InvalidRecipient)]
+    pub seller: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == config.treasury @ GenomeError::InvalidTokenAccount,
+        constraint = Some(treasury_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = config.treasury @ GenomeError::InvalidRecipient)]
+    pub treasury: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == genome.original_creator @ GenomeError::InvalidTokenAccount,
+        constraint = Some(creator_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub creator_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = genome.original_creator @ GenomeError::InvalidRecipient)]
+    pub creator: Option<SystemAccount<'info>>,
+    /// Required when the sale was referred: the referrer's profile and the
+    /// accounts receiving their cut.
+    #[account(
+        mut,
+        seeds = [REFERRAL_SEED, transaction.referrer.unwrap_or_default().as_ref()],
+        bump = referral_profile.bump,
+    )]
+    pub referral_profile: Option<Account<'info, ReferralProfile>>,
+    #[account(mut)]
+    pub referrer_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub referrer_wallet: Option<SystemAccount<'info>>,
+    /// Stamped with the refund time when the ruling refunds the buyer.
+    #[account(
+        init_if_needed,
+        payer = arbitrator,
+        space = 8 + RefundRecord::INIT_SPACE,
+        seeds = [REFUND_RECORD_SEED, access_grant.buyer.as_ref(), transaction.seller.as_ref()],
+        bump,
+    )]
+    pub refund_record: Account<'info, RefundRecord>,
+    #[account(mut)]
+    pub arbitrator: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSettlement<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub transaction: Account<'info, Transaction>,
+    #[account(mut, address = transaction.genome @ GenomeError::GenomeMismatch)]
+    pub genome: Account<'info, Genome>,
+    /// Required when the genome splits its proceeds; the recipients' wallets
+    /// or token accounts follow in `remaining_accounts`, in share order.
+    #[account(
+        constraint = Some(payout_split.key()) == genome.payout_split @ GenomeError::PayoutSplitMismatch,
+    )]
+    pub payout_split: Option<Account<'info, PayoutSplit>>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = seller_token_account.owner == transaction.seller @ GenomeError::InvalidTokenAccount,
+        constraint = Some(seller_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub seller_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = transaction.seller @ GenomeError::InvalidRecipient)]
+    pub seller: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == config.treasury @ GenomeError::InvalidTokenAccount,
+        constraint = Some(treasury_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = config.treasury @ GenomeError::InvalidRecipient)]
+    pub treasury: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == genome.original_creator @ GenomeError::InvalidTokenAccount,
+        constraint = Some(creator_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub creator_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = genome.original_creator @ GenomeError::InvalidRecipient)]
+    pub creator: Option<SystemAccount<'info>>,
+    /// Required when the sale was referred: the referrer's profile and the
+    /// accounts receiving their cut.
+    #[account(
+        mut,
+        seeds = [REFERRAL_SEED, transaction.referrer.unwrap_or_default().as_ref()],
+        bump = referral_profile.bump,
+    )]
+    pub referral_profile: Option<Account<'info, ReferralProfile>>,
+    #[account(mut)]
+    pub referrer_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub referrer_wallet: Option<SystemAccount<'info>>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// One rating per buyer per transaction; the `init` on `rating` rejects a
+/// second submission for the same purchase. The buyer's access grant is the
+/// proof of purchase, which also covers each buyer of a non-exclusive listing.
+#[derive(Accounts)]
+pub struct SubmitRating<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    pub transaction: Account<'info, Transaction>,
+    #[account(
+        seeds = [ACCESS_SEED, transaction.genome.as_ref(), buyer.key().as_ref()],
+        bump = access_grant.bump,
+        constraint = access_grant.transaction == transaction.key() @ GenomeError::AccessGrantMismatch,
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + Rating::INIT_SPACE,
+        seeds = [RATING_SEED, transaction.key().as_ref(), buyer.key().as_ref()],
+        bump,
+    )]
+    pub rating: Account<'info, Rating>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + SellerProfile::INIT_SPACE,
+        seeds = [SELLER_PROFILE_SEED, transaction.seller.as_ref()],
+        bump,
+    )]
+    pub seller_profile: Account<'info, SellerProfile>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Tip<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = tipper,
+        space = 8 + SellerProfile::INIT_SPACE,
+        seeds = [SELLER_PROFILE_SEED, seller.key().as_ref()],
+        bump,
+    )]
+    pub seller_profile: Account<'info, SellerProfile>,
+    #[account(mut)]
+    pub seller: SystemAccount<'info>,
+    #[account(mut)]
+    pub tipper: Signer<'info>,
+    #[account(
+        mut,
+        constraint = tipper_token_account.owner == tipper.key() @ GenomeError::InvalidTokenAccount,
+    )]
+    pub tipper_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = seller_token_account.owner == seller.key() @ GenomeError::InvalidTokenAccount,
+    )]
+    pub seller_token_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Earnings accounts live at a PDA of the seller and payment mint, with
+/// the default key standing in for native SOL.
+#[derive(Accounts)]
+pub struct OpenEarnings<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + Earnings::INIT_SPACE,
+        seeds = [
+            EARNINGS_SEED,
+            seller.key().as_ref(),
+            mint.as_ref().map_or(Pubkey::default(), |m| m.key()).as_ref(),
+        ],
+        bump,
+    )]
+    pub earnings: Account<'info, Earnings>,
+    /// Token vault for an SPL balance; omitted along with `mint` for SOL.
+    #[account(
+        init,
+        payer = seller,
+        seeds = [ESCROW_SEED, earnings.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = earnings_vault,
+    )]
+    pub earnings_vault: Option<Account<'info, TokenAccount>>,
+    pub mint: Option<Account<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, earnings.key().as_ref()],
+        bump,
+    )]
+    pub earnings_sol_vault: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawEarnings<'info> {
+    #[account(
+        mut,
+        seeds = [
+            EARNINGS_SEED,
+            seller.key().as_ref(),
+            earnings.payment_mint.unwrap_or_default().as_ref(),
+        ],
+        bump = earnings.bump,
+        has_one = seller @ GenomeError::Unauthorized,
+    )]
+    pub earnings: Account<'info, Earnings>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, earnings.key().as_ref()],
+        bump = earnings.vault_bump,
+    )]
+    pub earnings_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, earnings.key().as_ref()],
+        bump = earnings.vault_bump,
+    )]
+    pub earnings_sol_vault: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = seller_token_account.owner == seller.key() @ GenomeError::InvalidTokenAccount,
+        constraint = Some(seller_token_account.mint) == earnings.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub seller_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetSellerStats<'info> {
+    pub seller_profile: Account<'info, SellerProfile>,
+}
+
+#[derive(Accounts)]
+pub struct GetWeightedReputation<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    pub seller_profile: Account<'info, SellerProfile>,
+}
+
+#[derive(Accounts)]
+pub struct GetPurchaseHistory<'info> {
+    pub buyer_history: Account<'info, BuyerHistory>,
+}
+
+#[derive(Accounts)]
+pub struct GetAccessLog<'info> {
+    pub access_log: Account<'info, AccessLog>,
+}
+
+#[derive(Accounts)]
+pub struct GetStatusHistory<'info> {
+    pub transaction: Account<'info, Transaction>,
+}
+
+#[derive(Accounts)]
+pub struct GetMarketStats<'info> {
+    #[account(seeds = [MARKET_STATS_SEED], bump = market_stats.bump)]
+    pub market_stats: Account<'info, MarketStats>,
+}
+
+#[derive(Accounts)]
+pub struct RenewAccess<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    pub transaction: Account<'info, Transaction>,
+    #[account(address = transaction.genome @ GenomeError::GenomeMismatch)]
+    pub genome: Account<'info, Genome>,
+    /// Required when the genome splits its proceeds; the recipients' wallets
+    /// or token accounts follow in `remaining_accounts`, in share order.
+    #[account(
+        constraint = Some(payout_split.key()) == genome.payout_split @ GenomeError::PayoutSplitMismatch,
+    )]
+    pub payout_split: Option<Account<'info, PayoutSplit>>,
+    #[account(
+        mut,
+        seeds = [ACCESS_SEED, transaction.genome.as_ref(), buyer.key().as_ref()],
+        bump = access_grant.bump,
+        constraint = access_grant.transaction == transaction.key() @ GenomeError::AccessGrantMismatch,
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == buyer.key() @ GenomeError::InvalidTokenAccount,
+        constraint = Some(buyer_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub buyer_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = seller_token_account.owner == transaction.seller @ GenomeError::InvalidTokenAccount,
+        constraint = Some(seller_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub seller_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = transaction.seller @ GenomeError::InvalidRecipient)]
+    pub seller: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == config.treasury @ GenomeError::InvalidTokenAccount,
+        constraint = Some(treasury_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = config.treasury @ GenomeError::InvalidRecipient)]
+    pub treasury: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == genome.original_creator @ GenomeError::InvalidTokenAccount,
+        constraint = Some(creator_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub creator_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = genome.original_creator @ GenomeError::InvalidRecipient)]
+    pub creator: Option<SystemAccount<'info>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetAutoRenew<'info> {
+    #[account(mut, has_one = buyer @ GenomeError::Unauthorized)]
+    pub access_grant: Account<'info, AccessGrant>,
+    pub buyer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessRenewal<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    pub transaction: Account<'info, Transaction>,
+    #[account(address = transaction.genome @ GenomeError::GenomeMismatch)]
+    pub genome: Account<'info, Genome>,
+    /// Required when the genome splits its proceeds; the recipients' token
+    /// accounts follow in `remaining_accounts`, in share order.
+    #[account(
+        constraint = Some(payout_split.key()) == genome.payout_split @ GenomeError::PayoutSplitMismatch,
+    )]
+    pub payout_split: Option<Account<'info, PayoutSplit>>,
+    #[account(
+        mut,
+        seeds = [ACCESS_SEED, transaction.genome.as_ref(), access_grant.buyer.as_ref()],
+        bump = access_grant.bump,
+        constraint = access_grant.transaction == transaction.key() @ GenomeError::AccessGrantMismatch,
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+    /// Also the delegate the buyer approved on `buyer_token_account`.
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub escrow_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == access_grant.buyer @ GenomeError::InvalidTokenAccount,
+        constraint = Some(buyer_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = seller_token_account.owner == transaction.seller @ GenomeError::InvalidTokenAccount,
+        constraint = Some(seller_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub seller_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == config.treasury @ GenomeError::InvalidTokenAccount,
+        constraint = Some(treasury_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == genome.original_creator @ GenomeError::InvalidTokenAccount,
+        constraint = Some(creator_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub creator_token_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DeliverKey<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(has_one = owner @ GenomeError::NotOwner)]
+    pub genome: Account<'info, Genome>,
+    #[account(
+        mut,
+        constraint = access_grant.genome == genome.key() @ GenomeError::GenomeMismatch,
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ConfirmDelivery<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, has_one = buyer @ GenomeError::Unauthorized)]
+    pub access_grant: Account<'info, AccessGrant>,
+    pub buyer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RequestConversion<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(address = access_grant.genome @ GenomeError::GenomeMismatch)]
+    pub genome: Account<'info, Genome>,
+    #[account(mut, has_one = buyer @ GenomeError::Unauthorized)]
+    pub access_grant: Account<'info, AccessGrant>,
+    /// Lamport escrow for the conversion fee.
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, access_grant.key().as_ref()],
+        bump,
+    )]
+    pub conversion_vault: SystemAccount<'info>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FulfillConversion<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(has_one = owner @ GenomeError::NotOwner)]
+    pub genome: Account<'info, Genome>,
+    #[account(
+        mut,
+        constraint = access_grant.genome == genome.key() @ GenomeError::GenomeMismatch,
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, access_grant.key().as_ref()],
+        bump = access_grant.conversion_bump,
+    )]
+    pub conversion_vault: SystemAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GrantAccessBatch<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, has_one = owner @ GenomeError::NotOwner)]
+    pub genome: Account<'info, Genome>,
+    /// Page of the genome's access log currently being written.
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + AccessLog::INIT_SPACE,
+        seeds = [ACCESS_LOG_SEED, genome.key().as_ref(), &genome.access_log_page.to_le_bytes()],
+        bump,
+    )]
+    pub access_log: Account<'info, AccessLog>,
+    /// The page after `access_log`, for batches that fill it.
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + AccessLog::INIT_SPACE,
+        seeds = [ACCESS_LOG_SEED, genome.key().as_ref(), &genome.access_log_page.saturating_add(1).to_le_bytes()],
+        bump,
+    )]
+    pub next_access_log: Option<Account<'info, AccessLog>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimFreeAccess<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub genome: Account<'info, Genome>,
+    #[account(
+        mut,
+        seeds = [GRANT_QUOTA_SEED, holder.key().as_ref()],
+        bump = grant_quota.bump,
+        has_one = holder @ GenomeError::Unauthorized,
+    )]
+    pub grant_quota: Account<'info, GrantQuota>,
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = 8 + AccessGrant::INIT_SPACE,
+        seeds = [ACCESS_SEED, genome.key().as_ref(), holder.key().as_ref()],
+        bump,
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+    /// Page of the genome's access log currently being written.
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = 8 + AccessLog::INIT_SPACE,
+        seeds = [ACCESS_LOG_SEED, genome.key().as_ref(), &genome.access_log_page.to_le_bytes()],
+        bump,
+    )]
+    pub access_log: Account<'info, AccessLog>,
+    #[account(mut)]
+    pub holder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CheckAccess<'info> {
+    pub access_grant: Account<'info, AccessGrant>,
+}
+
+#[derive(Accounts)]
+pub struct CheckPurchasable<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    pub transaction: Account<'info, Transaction>,
+    #[account(address = transaction.genome @ GenomeError::GenomeMismatch)]
+    pub genome: Account<'info, Genome>,
+    #[account(seeds = [BLACKLIST_SEED], bump = blacklist.bump)]
+    pub blacklist: Account<'info, Blacklist>,
+}
+
+/// Stable layout for `assert_valid_access` CPIs; new accounts may only be
+/// appended.
+#[derive(Accounts)]
+pub struct AssertValidAccess<'info> {
+    /// CHECK: only its key is used, to derive the grant address.
+    pub genome: UncheckedAccount<'info>,
+    /// CHECK: only its key is used, to derive the grant address.
+    pub buyer: UncheckedAccount<'info>,
+    #[account(
+        seeds = [ACCESS_SEED, genome.key().as_ref(), buyer.key().as_ref()],
+        bump = access_grant.bump,
+        constraint = access_grant.buyer == buyer.key() @ GenomeError::InvalidRecipient,
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+}
+
+#[derive(Accounts)]
+pub struct GetEncryptedMetadata<'info> {
+    pub genome: Account<'info, Genome>,
+    #[account(
+        constraint = access_grant.genome == genome.key() @ GenomeError::GenomeMismatch,
+        constraint = access_grant.buyer == buyer.key() @ GenomeError::InvalidRecipient,
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+    pub buyer: Signer<'info>,
+}
+
+/// Either `access_grant` or `subscription_pass` is supplied.
+#[derive(Accounts)]
+pub struct VerifyAccess<'info> {
+    /// CHECK: only its key is needed to match a grant, which outlives a
+    /// closed genome; loaded as a `Genome` for subscription passes.
+    pub genome: UncheckedAccount<'info>,
+    #[account(
+        constraint = access_grant.genome == genome.key() @ GenomeError::GenomeMismatch,
+    )]
+    pub access_grant: Option<Account<'info, AccessGrant>>,
+    #[account(
+        seeds = [SUBSCRIPTION_PASS_SEED, subscription_pass.holder.as_ref()],
+        bump = subscription_pass.bump,
+    )]
+    pub subscription_pass: Option<Account<'info, SubscriptionPass>>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureSubscriptionPool<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ GenomeError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + SubscriptionPool::INIT_SPACE,
+        seeds = [SUBSCRIPTION_POOL_SEED],
+        bump,
+    )]
+    pub pool: Account<'info, SubscriptionPool>,
+    #[account(mut, seeds = [SOL_VAULT_SEED, pool.key().as_ref()], bump)]
+    pub pool_vault: SystemAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetSubscriptionPool<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, has_one = owner @ GenomeError::NotOwner)]
+    pub genome: Account<'info, Genome>,
+    #[account(mut, seeds = [SUBSCRIPTION_POOL_SEED], bump = pool.bump)]
+    pub pool: Account<'info, SubscriptionPool>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, pool.key().as_ref()],
+        bump = pool.vault_bump,
+    )]
+    pub pool_vault: SystemAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PurchaseSubscriptionPass<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [SUBSCRIPTION_POOL_SEED], bump = pool.bump)]
+    pub pool: Account<'info, SubscriptionPool>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, pool.key().as_ref()],
+        bump = pool.vault_bump,
+    )]
+    pub pool_vault: SystemAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + SubscriptionPass::INIT_SPACE,
+        seeds = [SUBSCRIPTION_PASS_SEED, buyer.key().as_ref()],
+        bump,
+    )]
+    pub pass: Account<'info, SubscriptionPass>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPoolRevenue<'info> {
+    #[account(mut, has_one = owner @ GenomeError::NotOwner)]
+    pub genome: Account<'info, Genome>,
+    #[account(seeds = [SUBSCRIPTION_POOL_SEED], bump = pool.bump)]
+    pub pool: Account<'info, SubscriptionPool>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, pool.key().as_ref()],
+        bump = pool.vault_bump,
+    )]
+    pub pool_vault: SystemAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeAccess<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    pub genome: Account<'info, Genome>,
+    #[account(
+        mut,
+        constraint = access_grant.genome == genome.key() @ GenomeError::GenomeMismatch,
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelActiveLicense<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub transaction: Account<'info, Transaction>,
+    #[account(mut, address = transaction.genome @ GenomeError::GenomeMismatch)]
+    pub genome: Account<'info, Genome>,
+    /// Required when the genome splits its proceeds; the recipients' wallets
+    /// or token accounts follow in `remaining_accounts`, in share order.
+    #[account(
+        constraint = Some(payout_split.key()) == genome.payout_split @ GenomeError::PayoutSplitMismatch,
+    )]
+    pub payout_split: Option<Account<'info, PayoutSplit>>,
+    #[account(
+        mut,
+        constraint = access_grant.transaction == transaction.key() @ GenomeError::AccessGrantMismatch,
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == access_grant.buyer @ GenomeError::InvalidTokenAccount,
+        constraint = Some(buyer_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub buyer_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = access_grant.buyer @ GenomeError::InvalidRecipient)]
+    pub buyer: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    #[account(
+        mut,
+        constraint = seller_token_account.owner == transaction.seller @ GenomeError::InvalidTokenAccount,
+        constraint = Some(seller_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub seller_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == config.treasury @ GenomeError::InvalidTokenAccount,
+        constraint = Some(treasury_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = config.treasury @ GenomeError::InvalidRecipient)]
+    pub treasury: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == genome.original_creator @ GenomeError::InvalidTokenAccount,
+        constraint = Some(creator_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub creator_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = genome.original_creator @ GenomeError::InvalidRecipient)]
+    pub creator: Option<SystemAccount<'info>>,
+    /// Required when the sale was referred and is still unsettled: the
+    /// referrer's profile and the accounts receiving their cut.
+    #[account(
+        mut,
+        seeds = [REFERRAL_SEED, transaction.referrer.unwrap_or_default().as_ref()],
+        bump = referral_profile.bump,
+    )]
+    pub referral_profile: Option<Account<'info, ReferralProfile>>,
+    #[account(mut)]
+    pub referrer_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub referrer_wallet: Option<SystemAccount<'info>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Refunds pass through the transaction's escrow vault so SPL and SOL
+/// listings share one payment path.
+#[derive(Accounts)]
+pub struct RecallLicense<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    pub transaction: Account<'info, Transaction>,
+    #[account(
+        address = transaction.genome @ GenomeError::GenomeMismatch,
+        has_one = owner @ GenomeError::NotOwner,
+    )]
+    pub genome: Account<'info, Genome>,
+    #[account(
+        mut,
+        constraint = access_grant.transaction == transaction.key() @ GenomeError::AccessGrantMismatch,
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == owner.key() @ GenomeError::InvalidTokenAccount,
+        constraint = Some(owner_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub owner_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == access_grant.buyer @ GenomeError::InvalidTokenAccount,
+        constraint = Some(buyer_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub buyer_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = access_grant.buyer @ GenomeError::InvalidRecipient)]
+    pub buyer: Option<SystemAccount<'info>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTransaction<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub transaction: Account<'info, Transaction>,
+    #[account(mut, address = transaction.genome @ GenomeError::GenomeMismatch)]
+    pub genome: Account<'info, Genome>,
+    #[account(
+        mut,
+        address = transaction.category_index @ GenomeError::CategoryIndexMismatch,
+    )]
+    pub category_index: Account<'info, CategoryIndex>,
+    /// Required when the admin cancels a blacklisted seller's listing.
+    #[account(seeds = [BLACKLIST_SEED], bump = blacklist.bump)]
+    pub blacklist: Option<Account<'info, Blacklist>>,
+    #[account(
+        mut,
+        seeds = [SELLER_PROFILE_SEED, transaction.seller.as_ref()],
+        bump = seller_profile.bump,
+    )]
+    pub seller_profile: Account<'info, SellerProfile>,
+    #[account(mut, seeds = [MARKET_STATS_SEED], bump = market_stats.bump)]
+    pub market_stats: Account<'info, MarketStats>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    /// Receives the cancellation fee when one is configured.
+    #[account(mut, address = config.treasury @ GenomeError::InvalidRecipient)]
+    pub treasury: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = Some(depositor_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub depositor_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub depositor: Option<SystemAccount<'info>>,
+    /// Receive the seller's collateral back, when one was posted.
+    #[account(
+        mut,
+        constraint = seller_token_account.owner == transaction.seller @ GenomeError::InvalidTokenAccount,
+        constraint = Some(seller_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub seller_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = transaction.seller @ GenomeError::InvalidRecipient)]
+    pub seller: Option<SystemAccount<'info>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTransactionsBatch<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [SELLER_PROFILE_SEED, seller.key().as_ref()],
+        bump = seller_profile.bump,
+    )]
+    pub seller_profile: Account<'info, SellerProfile>,
+    #[account(mut, seeds = [MARKET_STATS_SEED], bump = market_stats.bump)]
+    pub market_stats: Account<'info, MarketStats>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    /// Receives the cancellation fee when one is configured.
+    #[account(mut, address = config.treasury @ GenomeError::InvalidRecipient)]
+    pub treasury: Option<SystemAccount<'info>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireTransaction<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub transaction: Account<'info, Transaction>,
+    #[account(mut, address = transaction.genome @ GenomeError::GenomeMismatch)]
+    pub genome: Account<'info, Genome>,
+    #[account(
+        mut,
+        address = transaction.category_index @ GenomeError::CategoryIndexMismatch,
+    )]
+    pub category_index: Account<'info, CategoryIndex>,
+    #[account(
+        mut,
+        seeds = [SELLER_PROFILE_SEED, transaction.seller.as_ref()],
+        bump = seller_profile.bump,
+    )]
+    pub seller_profile: Account<'info, SellerProfile>,
+    #[account(mut, seeds = [MARKET_STATS_SEED], bump = market_stats.bump)]
+    pub market_stats: Account<'info, MarketStats>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = Some(depositor_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub depositor_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub depositor: Option<SystemAccount<'info>>,
+    /// Receive the seller's collateral back, when one was posted.
+    #[account(
+        mut,
+        constraint = seller_token_account.owner == transaction.seller @ GenomeError::InvalidTokenAccount,
+        constraint = Some(seller_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub seller_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = transaction.seller @ GenomeError::InvalidRecipient)]
+    pub seller: Option<SystemAccount<'info>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseTransaction<'info> {
+    #[account(mut, close = seller, has_one = seller @ GenomeError::Unauthorized)]
+    pub transaction: Account<'info, Transaction>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AdminRecoverEscrow<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ GenomeError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+    pub transaction: Account<'info, Transaction>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = Some(recipient_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub recipient_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub recipient: Option<SystemAccount<'info>>,
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateTo<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ GenomeError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub genome: Account<'info, Genome>,
+    /// A listing of the genome to migrate along with it, together with its
+    /// vault for the listing's payment type.
+    #[account(
+        mut,
+        constraint = transaction.genome == genome.key() @ GenomeError::GenomeMismatch,
+    )]
+    pub transaction: Option<Account<'info, Transaction>>,
+    #[account(mut)]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    /// Receive the escrow in the new program; checked against its PDAs.
+    #[account(
+        mut,
+        constraint = Some(new_escrow_vault.mint) == transaction.as_ref().and_then(|t| t.payment_mint) @ GenomeError::MintMismatch,
+    )]
+    pub new_escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub new_sol_vault: Option<SystemAccount<'info>>,
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositEscrowYield<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ GenomeError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub transaction: Account<'info, Transaction>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub escrow_vault: Account<'info, TokenAccount>,
+    /// CHECK: only invoked, and pinned to the configured yield program.
+    #[account(address = config.yield_program @ GenomeError::YieldProgramMismatch)]
+    pub yield_program: UncheckedAccount<'info>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemEscrowYield<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub transaction: Account<'info, Transaction>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, transaction.key().as_ref()],
+        bump = transaction.escrow_bump,
+    )]
+    pub escrow_vault: Account<'info, TokenAccount>,
+    /// CHECK: only invoked, and pinned to the program the escrow was lent to.
+    #[account(address = transaction.yield_program @ GenomeError::YieldProgramMismatch)]
+    pub yield_program: UncheckedAccount<'info>,
+    /// Receives the yield; may be omitted when none accrued.
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == config.treasury @ GenomeError::InvalidTokenAccount,
+        constraint = Some(treasury_token_account.mint) == transaction.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddToWhitelist<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = 8 + Whitelist::INIT_SPACE,
+        seeds = [WHITELIST_SEED, seller.key().as_ref()],
+        bump,
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveFromWhitelist<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [WHITELIST_SEED, seller.key().as_ref()],
+        bump = whitelist.bump,
+        has_one = seller @ GenomeError::Unauthorized,
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+    pub seller: Signer<'info>,
+}
+
+/// One open offer per buyer per genome. The offer account is closed back to
+/// the buyer once it is accepted, rejected or reclaimed, so the same buyer
+/// can make a fresh offer later; the token vault is reused in that case.
+#[derive(Accounts)]
+pub struct MakeOffer<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    pub genome: Account<'info, Genome>,
+    /// Open listing of the genome whose offer floor applies; required while
+    /// the genome has open listings.
+    #[account(constraint = listing.genome == genome.key() @ GenomeError::GenomeMismatch)]
+    pub listing: Option<Account<'info, Transaction>>,
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + Offer::INIT_SPACE,
+        seeds = [OFFER_SEED, genome.key().as_ref(), buyer.key().as_ref()],
+        bump,
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        seeds = [ESCROW_SEED, offer.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = escrow_vault,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    pub mint: Option<Account<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, offer.key().as_ref()],
+        bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == buyer.key() @ GenomeError::InvalidTokenAccount,
+        constraint = Some(buyer_token_account.mint) == mint.as_ref().map(|m| m.key()) @ GenomeError::MintMismatch,
+    )]
+    pub buyer_token_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptOffer<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, has_one = owner @ GenomeError::NotOwner)]
+    pub genome: Account<'info, Genome>,
+    /// Open listing of the genome whose offer floor applies; required while
+    /// the genome has open listings, and cancelled by the acceptance.
+    #[account(
+        mut,
+        constraint = listing.genome == genome.key() @ GenomeError::GenomeMismatch,
+        constraint = listing.seller == genome.owner @ GenomeError::NotOwner,
+    )]
+    pub listing: Option<Account<'info, Transaction>>,
+    /// Required with `listing`, to take it off the books.
+    #[account(
+        mut,
+        constraint = Some(category_index.key()) == listing.as_ref().map(|l| l.category_index) @ GenomeError::CategoryIndexMismatch,
+    )]
+    pub category_index: Option<Account<'info, CategoryIndex>>,
+    #[account(
+        mut,
+        seeds = [SELLER_PROFILE_SEED, genome.owner.as_ref()],
+        bump = seller_profile.bump,
+    )]
+    pub seller_profile: Option<Account<'info, SellerProfile>>,
+    #[account(mut, seeds = [MARKET_STATS_SEED], bump = market_stats.bump)]
+    pub market_stats: Option<Account<'info, MarketStats>>,
+    /// The listing's escrow, and where its deposit and collateral are
+    /// refunded, when it holds either.
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, listing.as_ref().map(|l| l.key()).unwrap_or_default().as_ref()],
+        bump = listing.as_ref().map_or(0, |l| l.escrow_bump),
+    )]
+    pub listing_escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, listing.as_ref().map(|l| l.key()).unwrap_or_default().as_ref()],
+        bump = listing.as_ref().map_or(0, |l| l.escrow_bump),
+    )]
+    pub listing_sol_vault: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = Some(depositor_token_account.mint) == listing.as_ref().and_then(|l| l.payment_mint) @ GenomeError::MintMismatch,
+    )]
+    pub depositor_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub depositor: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = listing_owner_token_account.owner == genome.owner @ GenomeError::InvalidTokenAccount,
+        constraint = Some(listing_owner_token_account.mint) == listing.as_ref().and_then(|l| l.payment_mint) @ GenomeError::MintMismatch,
+    )]
+    pub listing_owner_token_account: Option<Account<'info, TokenAccount>>,
+    /// Required when the genome splits its proceeds; the recipients' wallets
+    /// or token accounts follow in `remaining_accounts`, in share order.
+    #[account(
+        constraint = Some(payout_split.key()) == genome.payout_split @ GenomeError::PayoutSplitMismatch,
+    )]
+    pub payout_split: Option<Account<'info, PayoutSplit>>,
+    #[account(
+        mut,
+        close = buyer,
+        seeds = [OFFER_SEED, genome.key().as_ref(), buyer.key().as_ref()],
+        bump = offer.bump,
+        has_one = buyer @ GenomeError::InvalidRecipient,
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(mut)]
+    pub buyer: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, offer.key().as_ref()],
+        bump = offer.escrow_bump,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, offer.key().as_ref()],
+        bump = offer.escrow_bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + AccessGrant::INIT_SPACE,
+        seeds = [ACCESS_SEED, genome.key().as_ref(), buyer.key().as_ref()],
+        bump,
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+    /// Page of the genome's access log currently being written.
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + AccessLog::INIT_SPACE,
+        seeds = [ACCESS_LOG_SEED, genome.key().as_ref(), &genome.access_log_page.to_le_bytes()],
+        bump,
+    )]
+    pub access_log: Account<'info, AccessLog>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == genome.owner @ GenomeError::InvalidTokenAccount,
+        constraint = Some(owner_token_account.mint) == offer.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub owner_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == config.treasury @ GenomeError::InvalidTokenAccount,
+        constraint = Some(treasury_token_account.mint) == offer.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = config.treasury @ GenomeError::InvalidRecipient)]
+    pub treasury: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == genome.original_creator @ GenomeError::InvalidTokenAccount,
+        constraint = Some(creator_token_account.mint) == offer.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub creator_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = genome.original_creator @ GenomeError::InvalidRecipient)]
+    pub creator: Option<SystemAccount<'info>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RejectOffer<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(has_one = owner @ GenomeError::NotOwner)]
+    pub genome: Account<'info, Genome>,
+    #[account(
+        mut,
+        close = buyer,
+        seeds = [OFFER_SEED, genome.key().as_ref(), buyer.key().as_ref()],
+        bump = offer.bump,
+        has_one = buyer @ GenomeError::InvalidRecipient,
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(mut)]
+    pub buyer: SystemAccount<'info>,
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == offer.buyer @ GenomeError::InvalidTokenAccount,
+        constraint = Some(buyer_token_account.mint) == offer.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub buyer_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, offer.key().as_ref()],
+        bump = offer.escrow_bump,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, offer.key().as_ref()],
+        bump = offer.escrow_bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimOffer<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        close = buyer,
+        seeds = [OFFER_SEED, offer.genome.as_ref(), buyer.key().as_ref()],
+        bump = offer.bump,
+        has_one = buyer @ GenomeError::Unauthorized,
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == buyer.key() @ GenomeError::InvalidTokenAccount,
+        constraint = Some(buyer_token_account.mint) == offer.payment_mint @ GenomeError::MintMismatch,
+    )]
+    pub buyer_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, offer.key().as_ref()],
+        bump = offer.escrow_bump,
+    )]
+    pub escrow_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, offer.key().as_ref()],
+        bump = offer.escrow_bump,
+    )]
+    pub sol_vault: Option<SystemAccount<'info>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(Default, InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+    pub fee_bps: u16,
+    pub paused: bool,
+    pub bump: u8,
+    pub arbitrator: Pubkey,
+    /// Seconds after execution during which the buyer may open a dispute.
+    /// Zero pays the seller out immediately on execution.
+    pub dispute_window: i64,
+    /// Lamports a seller pays the treasury for each listing they cancel.
+    pub cancellation_fee: u64,
+    /// Live genomes one owner may register; zero for no limit.
+    pub max_genomes_per_user: u32,
+    /// Bounds, in seconds, on how long a new listing may stay open.
+    pub min_duration: i64,
+    pub max_duration: i64,
+    /// Seconds a revoked grant keeps working so the buyer can wind down.
+    pub revocation_grace_period: i64,
+    /// SPL mints listings and offers may be paid in. Native SOL is always
+    /// accepted.
+    #[max_len(16)]
+    pub allowed_mints: Vec<Pubkey>,
+    /// Seconds a seller must wait between listings; zero for none.
+    pub listing_cooldown: i64,
+    /// Share of an exclusive listing's price, in basis points, the seller
+    /// posts as collateral at listing time; zero for none.
+    pub collateral_bps: u16,
+    /// Whether idle token escrows may be lent to `yield_program`, a lending
+    /// vault exposing `deposit(amount)` and `withdraw()`.
+    pub yield_enabled: bool,
+    pub yield_program: Pubkey,
+    /// Circuit breaker on the sale volume executed per Solana epoch,
+    /// summed across payment units; zero disables it.
+    pub epoch_volume_cap: u64,
+    /// Share of the marketplace fee, in basis points, paid to a sale's
+    /// referrer.
+    pub referral_bps: u16,
+    /// Seconds a new owner must hold a transferred genome before listing
+    /// it; zero allows instant resale.
+    pub resale_lock: i64,
+    /// `PROGRAM_VERSION` the config was created under, and the oldest
+    /// `client_version` the listing, purchase, offer and registration
+    /// instructions still accept.
+    pub version: u8,
+    pub min_client_version: u8,
+    /// Seconds a genome's heartbeat stays fresh; its listings cannot be
+    /// bought once it goes stale. Zero disables the check.
+    pub heartbeat_ttl: i64,
+    /// Treasury's cut, in basis points, for converting USD-pegged prices at
+    /// the oracle rate, covering its volatility risk.
+    pub conversion_fee_bps: u16,
+    /// Share of a listing's remaining collateral, in basis points, a buyer
+    /// may claim each time the genome's heartbeat lapses; zero disables SLA
+    /// claims.
+    pub sla_slash_bps: u16,
+    /// Seconds after a dispute refund before the buyer may buy from the same
+    /// seller again. Zero disables the cooldown.
+    pub refund_cooldown: i64,
+    /// Seconds for a rating's weight in a seller's weighted reputation to
+    /// halve. Zero keeps every rating at full weight.
+    pub reputation_half_life: i64,
+    /// Program accounts may be migrated to, until `upgrade_window_ends`.
+    pub upgrade_program: Option<Pubkey>,
+    pub upgrade_window_ends: i64,
+}
+
+impl Config {
+    pub fn assert_upgrade_window(&self, new_program: Pubkey, now: i64) -> Result<()> {
+        require!(
+            self.upgrade_program.is_some() && now < self.upgrade_window_ends,
+            GenomeError::UpgradeWindowClosed
+        );
+        require!(
+            self.upgrade_program == Some(new_program),
+            GenomeError::MigrationTargetMismatch
+        );
+        Ok(())
+    }
+
+    pub fn assert_not_paused(&self) -> Result<()> {
+        require!(!self.paused, GenomeError::ProgramPaused);
+        Ok(())
+    }
+
+    pub fn assert_client_version(&self, client_version: u8) -> Result<()> {
+        require!(
+            client_version >= self.min_client_version,
+            GenomeError::ClientTooOld
+        );
+        Ok(())
+    }
+
+    pub fn assert_mint_allowed(&self, payment_mint: Option<Pubkey>) -> Result<()> {
+        if let Some(mint) = payment_mint {
+            require!(
+                self.allowed_mints.contains(&mint),
+                GenomeError::MintNotAllowed
+            );
+        }
+        Ok(())
+    }
+
+    /// Allowing a mint that is already listed is a no-op.
+    pub fn allow_mint(&mut self, mint: Pubkey) -> Result<()> {
+        if self.allowed_mints.contains(&mint) {
+            return Ok(());
+        }
+        require!(
+            self.allowed_mints.len() < MAX_ALLOWED_MINTS,
+            GenomeError::AllowedMintsFull
+        );
+        self.allowed_mints.push(mint);
+        Ok(())
+    }
+
+    pub fn disallow_mint(&mut self, mint: Pubkey) {
+        self.allowed_mints.retain(|m| *m != mint);
+    }
+
+    pub fn assert_listing_duration(&self, duration: i64) -> Result<()> {
+        require!(
+            duration >= self.min_duration && duration <= self.max_duration,
+            GenomeError::ListingDurationOutOfBounds
+        );
+        Ok(())
+    }
+
+    pub fn rotate_keys(
+        &mut self,
+        admin: Option<Pubkey>,
+        arbitrator: Option<Pubkey>,
+        treasury: Option<Pubkey>,
+    ) -> Result<()> {
+        for key in [admin, arbitrator, treasury].into_iter().flatten() {
+            require_keys_neq!(key, Pubkey::default(), GenomeError::InvalidConfigKey);
+        }
+        if let Some(admin) = admin {
+            self.admin = admin;
+        }
+        if let Some(arbitrator) = arbitrator {
+            self.arbitrator = arbitrator;
+        }
+        if let Some(treasury) = treasury {
+            self.treasury = treasury;
+        }
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(Default, InitSpace)]
+pub struct Genome {
+    #[max_len(64)]
+    pub storage_id: String,
+    #[max_len(512)]
+    pub metadata: String,
+    pub owner: Pubkey,
+    pub created_at: i64,
+    pub deleted: bool,
+    pub updated_at: i64,
+    pub previous_owner: Option<Pubkey>,
+    pub bump: u8,
+    /// SHA-256 of the off-chain genome file.
+    pub content_hash: [u8; 32],
+    /// Sequencer who registered the genome; keeps earning royalties on
+    /// resales after ownership changes hands.
+    pub original_creator: Pubkey,
+    pub royalty_bps: u16,
+    /// Listings still in `Created`; the account can only be closed at zero.
+    pub active_listings: u32,
+    /// Sales whose proceeds are still held in escrow, awaiting the buyer's
+    /// receipt, the dispute window or a ruling. Also zero before closing.
+    pub unsettled_sales: u32,
+    pub category: GenomeCategory,
+    /// Short, indexer-friendly labels, e.g. "wgs" or "brca1".
+    #[max_len(8, 32)]
+    pub tags: Vec<String>,
+    /// Starts at 1 and increases by one along the revision chain.
+    pub version: u32,
+    pub previous_version: Option<Pubkey>,
+    pub superseded_by: Option<Pubkey>,
+    /// When set, listing and transferring require multisig approval.
+    pub multisig: Option<Pubkey>,
+    /// ISO 3166-1 alpha-2 code of the jurisdiction the data was collected in.
+    pub jurisdiction: Option<[u8; 2]>,
+    /// Version of the off-chain JSON schema `metadata` follows.
+    pub metadata_schema_version: u16,
+    /// May create listings for the owner; cleared when ownership changes.
+    pub delegate: Option<Pubkey>,
+    /// `PayoutSplit` sharing the seller's proceeds; also cleared when
+    /// ownership changes.
+    pub payout_split: Option<Pubkey>,
+    /// Size of the off-chain file, its `FORMAT_*` code and the
+    /// `REFERENCE_*` build it is aligned to.
+    pub file_size_bytes: u64,
+    pub format: u8,
+    pub reference_build: u8,
+    /// Freely accessible sample of the data, and its hash, so buyers can
+    /// try it before licensing. Empty when the seller offers none.
+    #[max_len(200)]
+    pub preview_uri: String,
+    pub preview_hash: [u8; 32],
+    /// `USE_*` code for what the consent terms allow the data to be used
+    /// for; `USE_ANY` places no restriction.
+    pub permitted_use: u8,
+    /// When the current owner took the genome over by transfer; zero for
+    /// its original registrant.
+    pub acquired_at: i64,
+    /// Set while the metadata is sealed; the genome cannot be listed before
+    /// this time.
+    pub embargo_until: Option<i64>,
+    /// SHA-256 of the sealed metadata, checked when it is revealed.
+    pub sealed_metadata_hash: [u8; 32],
+    /// Whether `GrantQuota` holders may claim free research licenses.
+    pub nonprofit_access: bool,
+    /// Whether subscription pass holders may access the genome, and the
+    /// pool's `revenue_per_genome` when its share was last paid out.
+    pub subscription_pool: bool,
+    pub pool_checkpoint: u128,
+    /// When the owner last attested the off-chain data is still hosted;
+    /// registration counts as the first heartbeat.
+    pub last_heartbeat: i64,
+    /// Sensitive metadata, e.g. patient identifiers, encrypted so only
+    /// grant holders are given the key to read it.
+    #[max_len(512)]
+    pub encrypted_metadata: Vec<u8>,
+    /// Page of the `AccessLog` the next grant is recorded on.
+    pub access_log_page: u32,
+    /// Key the owner trusts to reassign the genome if they lose theirs, and
+    /// the reassignment it has started, if any.
+    pub recovery_authority: Option<Pubkey>,
+    pub pending_recovery: Option<PendingRecovery>,
+    /// Whether the data subject still consents to new sales. Set at
+    /// registration; once withdrawn the genome can no longer be listed or
+    /// bought.
+    pub consent_active: bool,
+    /// Public database records the data derives from. Entries are only
+    /// ever appended.
+    #[max_len(8)]
+    pub provenance: Vec<Accession>,
+    /// Program the genome was handed over to by `migrate_to`; this program
+    /// rejects it from then on.
+    pub migrated_to: Option<Pubkey>,
+}
+
+impl Genome {
+    /// Builds a freshly registered genome owned, and created, by `owner`.
+    pub fn register(owner: Pubkey, entry: GenomeEntry, now: i64, bump: u8) -> Result<Self> {
+        validate_len(&entry.storage_id, MAX_ID_LEN)?;
+        validate_len(&entry.metadata, MAX_METADATA_LEN)?;
+        validate_len(&entry.preview_uri, MAX_PREVIEW_URI_LEN)?;
+        validate_tags(&entry.tags)?;
+        if let Some(jurisdiction) = &entry.jurisdiction {
+            validate_jurisdiction(jurisdiction)?;
+        }
+        require!(
+            u64::from(entry.royalty_bps) <= BPS_DENOMINATOR,
+            GenomeError::InvalidRoyaltyRate
+        );
+        require!(entry.format <= FORMAT_OTHER, GenomeError::InvalidFormat);
+        require!(
+            entry.reference_build <= REFERENCE_OTHER,
+            GenomeError::InvalidReferenceBuild
+        );
+        require!(
+            entry.permitted_use <= USE_COMMERCIAL,
+            GenomeError::InvalidPermittedUse
+        );
+
+        let mut genome = Self {
+            storage_id: entry.storage_id,
+            metadata: entry.metadata,
+            metadata_schema_version: entry.metadata_schema_version,
+            owner,
+            created_at: now,
+            updated_at: now,
+            bump,
+            content_hash: entry.content_hash,
+            original_creator: owner,
+            royalty_bps: entry.royalty_bps,
+            category: entry.category,
+            tags: entry.tags,
+            jurisdiction: entry.jurisdiction,
+            file_size_bytes: entry.file_size_bytes,
+            format: entry.format,
+            reference_build: entry.reference_build,
+            preview_uri: entry.preview_uri,
+            preview_hash: entry.preview_hash,
+            permitted_use: entry.permitted_use,
+            last_heartbeat: now,
+            consent_active: true,
+            version: 1,
+            ..Default::default()
+        };
+        genome.add_provenance(entry.provenance)?;
+        Ok(genome)
+    }
+
+    /// Appends `accessions` to the genome's provenance.
+    pub fn add_provenance(&mut self, accessions: Vec<Accession>) -> Result<()> {
+        require!(
+            self.provenance.len() + accessions.len() <= MAX_PROVENANCE,
+            GenomeError::TooManyAccessions
+        );
+        for accession in &accessions {
+            require!(
+                accession.source <= ACCESSION_OTHER && !accession.id.is_empty(),
+                GenomeError::InvalidAccession
+            );
+            validate_len(&accession.id, MAX_ACCESSION_ID_LEN)?;
+        }
+        self.provenance.extend(accessions);
+        Ok(())
+    }
+
+    /// Builds the next revision of this genome. Ownership, creator, royalty
+    /// and classification carry forward; only the data itself changes.
+    pub fn successor(
+        &self,
+        key: Pubkey,
+        storage_id: String,
+        metadata: String,
+        content_hash: [u8; 32],
+        now: i64,
+        bump: u8,
+    ) -> Result<Self> {
+        require!(self.superseded_by.is_none(), GenomeError::GenomeSuperseded);
+
+        let mut successor = Self::register(
+            self.owner,
+            GenomeEntry {
+                storage_id,
+                metadata,
+                metadata_schema_version: self.metadata_schema_version,
+                content_hash,
+                royalty_bps: self.royalty_bps,
+                category: self.category,
+                tags: self.tags.clone(),
+                jurisdiction: self.jurisdiction,
+                file_size_bytes: self.file_size_bytes,
+                format: self.format,
+                reference_build: self.reference_build,
+                preview_uri: self.preview_uri.clone(),
+                preview_hash: self.preview_hash,
+                permitted_use: self.permitted_use,
+                provenance: self.provenance.clone(),
+            },
+            now,
+            bump,
+        )?;
+        successor.original_creator = self.original_creator;
+        successor.acquired_at = self.acquired_at;
+        successor.consent_active = self.consent_active;
+        successor.version = self
+            .version
+            .checked_add(1)
+            .ok_or(GenomeError::MathOverflow)?;
+        successor.previous_version = Some(key);
+        Ok(successor)
+    }
+
+    /// Replaces the metadata, optionally moving it to a newer schema. The
+    /// schema version never goes backwards, so readers can rely on it.
+    pub fn set_metadata(&mut self, metadata: String, schema_version: Option<u16>) -> Result<()> {
+        if let Some(schema_version) = schema_version {
+            require!(
+                schema_version >= self.metadata_schema_version,
+                GenomeError::InvalidSchemaVersion
+            );
+            self.metadata_schema_version = schema_version;
+        }
+        self.metadata = metadata;
+        Ok(())
+    }
+
+    /// Applies the `Some` fields of `patch`, checking every one of them
+    /// before changing anything.
+    pub fn apply_patch(&mut self, patch: GenomePatch) -> Result<()> {
+        if let Some(metadata) = &patch.metadata {
+            // Sealed metadata only comes back through `unseal`.
+            require!(self.embargo_until.is_none(), GenomeError::GenomeEmbargoed);
+            validate_len(metadata, MAX_METADATA_LEN)?;
+        }
+        if let Some(schema_version) = patch.metadata_schema_version {
+            require!(
+                schema_version >= self.metadata_schema_version,
+                GenomeError::InvalidSchemaVersion
+            );
+        }
+        if let Some(tags) = &patch.tags {
+            validate_tags(tags)?;
+        }
+        if let Some(preview_uri) = &patch.preview_uri {
+            validate_len(preview_uri, MAX_PREVIEW_URI_LEN)?;
+        }
+        if let Some(format) = patch.format {
+            require!(format <= FORMAT_OTHER, GenomeError::InvalidFormat);
+        }
+        if let Some(reference_build) = patch.reference_build {
+            require!(
+                reference_build <= REFERENCE_OTHER,
+                GenomeError::InvalidReferenceBuild
+            );
+        }
+        if let Some(encrypted_metadata) = &patch.encrypted_metadata {
+            require!(
+                encrypted_metadata.len() <= MAX_ENCRYPTED_METADATA_LEN,
+                GenomeError::EncryptedMetadataTooLong
+            );
+        }
+
+        if let Some(metadata) = patch.metadata {
+            self.metadata = metadata;
+        }
+        if let Some(schema_version) = patch.metadata_schema_version {
+            self.metadata_schema_version = schema_version;
+        }
+        if let Some(category) = patch.category {
+            self.category = category;
+        }
+        if let Some(tags) = patch.tags {
+            self.tags = tags;
+        }
+        if let Some(preview_uri) = patch.preview_uri {
+            self.preview_uri = preview_uri;
+        }
+        if let Some(preview_hash) = patch.preview_hash {
+            self.preview_hash = preview_hash;
+        }
+        if let Some(file_size_bytes) = patch.file_size_bytes {
+            self.file_size_bytes = file_size_bytes;
+        }
+        if let Some(format) = patch.format {
+            self.format = format;
+        }
+        if let Some(reference_build) = patch.reference_build {
+            self.reference_build = reference_build;
+        }
+        if let Some(encrypted_metadata) = patch.encrypted_metadata {
+            self.encrypted_metadata = encrypted_metadata;
+        }
+        Ok(())
+    }
+
+    /// Checks a buyer's declared use, which must be research or commercial,
+    /// is one the genome's consent permits.
+    pub fn assert_use(&self, declared_use: u8) -> Result<()> {
+        require!(
+            declared_use == USE_RESEARCH || declared_use == USE_COMMERCIAL,
+            GenomeError::InvalidPermittedUse
+        );
+        require!(
+            self.permitted_use == USE_ANY || self.permitted_use == declared_use,
+            GenomeError::UseNotPermitted
+        );
+        Ok(())
+    }
+
+    pub fn listing_active(&self) -> bool {
+        self.active_listings > 0
+    }
+
+    /// Genomes are sold exclusively, so a new listing may only open once
+    /// every earlier one has been cancelled, sold out or expired.
+    pub fn open_listing(&mut self) -> Result<()> {
+        require!(!self.listing_active(), GenomeError::AlreadyListed);
+        self.active_listings = 1;
+        Ok(())
+    }
+
+    pub fn assert_owner(&self, authority: Pubkey) -> Result<()> {
+        require_keys_eq!(self.owner, authority, GenomeError::NotOwner);
+        Ok(())
+    }
+
+    /// Checks `authority` may perform `action`: the owner for single-owner
+    /// genomes, or a multisig member once the action has reached threshold.
+    /// A multisig approval is consumed by the call.
+    pub fn authorize(
+        &self,
+        authority: Pubkey,
+        multisig: Option<&mut MultisigOwner>,
+        action: MultisigAction,
+    ) -> Result<()> {
+        if self.multisig.is_none() {
+            return self.assert_owner(authority);
+        }
+        let multisig = multisig.ok_or(GenomeError::MultisigMismatch)?;
+        multisig.assert_member(authority)?;
+        multisig.consume(&action)
+    }
+
+    /// Like `authorize`, but the genome's delegate may also list it.
+    pub fn authorize_listing(
+        &self,
+        authority: Pubkey,
+        multisig: Option<&mut MultisigOwner>,
+        action: MultisigAction,
+    ) -> Result<()> {
+        if self.delegate == Some(authority) {
+            return Ok(());
+        }
+        self.authorize(authority, multisig, action)
+    }
+
+    pub fn soft_delete(&mut self) -> Result<()> {
+        require!(!self.deleted, GenomeError::GenomeDeleted);
+        self.deleted = true;
+        Ok(())
+    }
+
+    pub fn embargoed(&self, now: i64) -> bool {
+        self.embargo_until.map_or(false, |until| now < until)
+    }
+
+    pub fn assert_not_embargoed(&self, now: i64) -> Result<()> {
+        require!(!self.embargoed(now), GenomeError::GenomeEmbargoed);
+        Ok(())
+    }
+
+    pub fn assert_consent(&self) -> Result<()> {
+        require!(self.consent_active, GenomeError::ConsentWithdrawn);
+        Ok(())
+    }
+
+    /// Neither deleted nor migrated away, so this program may act on it.
+    pub fn assert_live(&self) -> Result<()> {
+        require!(!self.deleted, GenomeError::GenomeDeleted);
+        require!(self.migrated_to.is_none(), GenomeError::AccountMigrated);
+        Ok(())
+    }
+
+    /// Nothing is listed and no sale is still waiting to be paid out of
+    /// escrow, so the account may be closed.
+    pub fn assert_closable(&self) -> Result<()> {
+        require!(self.active_listings == 0, GenomeError::ActiveListings);
+        require!(self.unsettled_sales == 0, GenomeError::UnsettledSales);
+        Ok(())
+    }
+
+    pub fn record_unsettled_sale(&mut self) -> Result<()> {
+        self.unsettled_sales = self
+            .unsettled_sales
+            .checked_add(1)
+            .ok_or(GenomeError::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn record_settlement(&mut self) {
+        self.unsettled_sales = self.unsettled_sales.saturating_sub(1);
+    }
+
+    /// Marks the genome migrated. Repeats for the same program are allowed
+    /// so its listings can follow it one at a time.
+    pub fn migrate(&mut self, new_program: Pubkey) -> Result<()> {
+        require!(
+            self.migrated_to.map_or(true, |p| p == new_program),
+            GenomeError::AccountMigrated
+        );
+        self.migrated_to = Some(new_program);
+        Ok(())
+    }
+
+    /// Clears the metadata, keeping only its hash until `unseal`.
+    pub fn seal(&mut self, embargo_until: i64, metadata_hash: [u8; 32], now: i64) -> Result<()> {
+        require!(self.embargo_until.is_none(), GenomeError::GenomeEmbargoed);
+        require!(embargo_until > now, GenomeError::InvalidDuration);
+        self.embargo_until = Some(embargo_until);
+        self.sealed_metadata_hash = metadata_hash;
+        self.metadata.clear();
+        Ok(())
+    }
+
+    /// Restores sealed metadata and ends the embargo.
+    pub fn unseal(&mut self, metadata: String) -> Result<()> {
+        require!(self.embargo_until.is_some(), GenomeError::NotEmbargoed);
+        validate_len(&metadata, MAX_METADATA_LEN)?;
+        require!(
+            hash(metadata.as_bytes()).to_bytes() == self.sealed_metadata_hash,
+            GenomeError::SealedMetadataMismatch
+        );
+        self.metadata = metadata;
+        self.embargo_until = None;
+        self.sealed_metadata_hash = [0; 32];
+        Ok(())
+    }
+
+    /// Fails once more than `heartbeat_ttl` has passed since the last
+    /// heartbeat; a zero ttl never expires.
+    pub fn assert_heartbeat_fresh(&self, heartbeat_ttl: i64, now: i64) -> Result<()> {
+        if heartbeat_ttl == 0 {
+            return Ok(());
+        }
+        let stale_at = self
+            .last_heartbeat
+            .checked_add(heartbeat_ttl)
+            .ok_or(GenomeError::MathOverflow)?;
+        require!(now <= stale_at, GenomeError::HeartbeatStale);
+        Ok(())
+    }
+
+    /// Fails while a transferred genome is inside its `resale_lock`.
+    pub fn assert_resale_unlocked(&self, resale_lock: i64, now: i64) -> Result<()> {
+        if self.acquired_at == 0 {
+            return Ok(());
+        }
+        let unlocks_at = self
+            .acquired_at
+            .checked_add(resale_lock)
+            .ok_or(GenomeError::MathOverflow)?;
+        require!(now >= unlocks_at, GenomeError::ResaleLocked);
+        Ok(())
+    }
+
+    pub fn transfer_to(&mut self, new_owner: Pubkey) -> Result<()> {
+        require!(
+            new_owner != Pubkey::default() && new_owner != self.owner,
+            GenomeError::InvalidNewOwner
+        );
+        self.previous_owner = Some(self.owner);
+        self.owner = new_owner;
+        self.delegate = None;
+        self.payout_split = None;
+        self.recovery_authority = None;
+        self.pending_recovery = None;
+        Ok(())
+    }
+
+    /// Starts reassigning the genome to `new_owner` on behalf of an owner
+    /// who lost their key. It can be finalized after `RECOVERY_TIMELOCK`.
+    pub fn initiate_recovery(
+        &mut self,
+        authority: Pubkey,
+        new_owner: Pubkey,
+        now: i64,
+    ) -> Result<()> {
+        require!(
+            self.recovery_authority == Some(authority),
+            GenomeError::Unauthorized
+        );
+        require!(
+            self.pending_recovery.is_none(),
+            GenomeError::RecoveryPending
+        );
+        require!(
+            new_owner != Pubkey::default() && new_owner != self.owner,
+            GenomeError::InvalidNewOwner
+        );
+        self.pending_recovery = Some(PendingRecovery {
+            new_owner,
+            unlocks_at: now
+                .checked_add(RECOVERY_TIMELOCK)
+                .ok_or(GenomeError::MathOverflow)?,
+        });
+        Ok(())
+    }
+
+    /// Hands the genome to the pending recovery's new owner once its
+    /// timelock has passed, returning the previous owner.
+    pub fn finalize_recovery(&mut self, authority: Pubkey, now: i64) -> Result<Pubkey> {
+        require!(
+            self.recovery_authority == Some(authority),
+            GenomeError::Unauthorized
+        );
+        let recovery = self
+            .pending_recovery
+            .clone()
+            .ok_or(GenomeError::NoRecoveryPending)?;
+        require!(now >= recovery.unlocks_at, GenomeError::RecoveryTimelocked);
+        let previous_owner = self.owner;
+        self.transfer_to(recovery.new_owner)?;
+        Ok(previous_owner)
+    }
+
+    pub fn cancel_recovery(&mut self) -> Result<PendingRecovery> {
+        self.pending_recovery
+            .take()
+            .ok_or_else(|| error!(GenomeError::NoRecoveryPending))
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub struct PendingRecovery {
+    pub new_owner: Pubkey,
+    /// When `finalize_recovery` may first be called.
+    pub unlocks_at: i64,
+}
+
+#[account]
+#[derive(Default, InitSpace)]
+pub struct Transaction {
+    #[max_len(64)]
+    pub genome_id: String,
+    pub genome: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Option<Pubkey>,
+    pub price: u64,
+    /// Length of the license each buyer receives.
+    pub access_duration: i64,
+    pub status: TransactionStatus,
+    pub created_at: i64,
+    pub executed_at: Option<i64>,
+    /// SPL mint the listing is priced in; `None` means native SOL.
+    pub payment_mint: Option<Pubkey>,
+    pub escrow_bump: u8,
+    pub depositor: Option<Pubkey>,
+    /// Set for English auctions, where `price` tracks the highest bid and
+    /// `depositor` the highest bidder.
+    pub auction: Option<Auction>,
+    /// Whether the proceeds of an executed sale have left escrow.
+    pub settled: bool,
+    /// Private sale: only this buyer may purchase.
+    pub allowed_buyer: Option<Pubkey>,
+    /// Seller whitelist the buyer must appear on, if any.
+    pub whitelist: Option<Pubkey>,
+    /// Held for the duration of `execute_transaction`.
+    pub locked: bool,
+    /// Membership token buyers must hold at least `required_amount` of.
+    pub required_token_mint: Option<Pubkey>,
+    pub required_amount: u64,
+    /// When the listing stops accepting purchases; for auctions, when
+    /// bidding closes.
+    pub listing_expires_at: i64,
+    /// Licenses that may be sold; above one the listing is non-exclusive and
+    /// stays open until all are issued. `buyer` is only set for exclusive
+    /// sales.
+    pub max_licenses: u32,
+    pub licenses_issued: u32,
+    /// Licenses one buyer may take from a non-exclusive listing, each after
+    /// the last has ended; zero for no cap.
+    pub max_per_buyer: u32,
+    /// Hash of the license agreement text buyers must acknowledge.
+    pub terms_hash: [u8; 32],
+    /// ISO 3166-1 alpha-2 codes buyers may purchase from; empty allows any.
+    #[max_len(16)]
+    pub allowed_jurisdictions: Vec<[u8; 2]>,
+    /// Times this listing's genome has been relisted through
+    /// `relist_transaction`, and the first listing of that chain.
+    pub relist_count: u32,
+    pub original_listing: Option<Pubkey>,
+    /// Seed and bump of the listing's PDA.
+    pub nonce: u64,
+    pub bump: u8,
+    /// Referrer of the latest sale, owed a cut of the fee when it settles.
+    pub referrer: Option<Pubkey>,
+    /// USD price (`USD_DECIMALS` decimals) for listings pegged to USD and
+    /// the oracle feed used to convert it; `price` then holds the amount
+    /// buyers deposit, trued up at execution.
+    pub price_in_usd: Option<u64>,
+    pub price_feed: Option<Pubkey>,
+    pub payment_decimals: u8,
+    /// Every genome sold by a bundle listing, by address, starting with
+    /// `genome`; empty for single-genome listings.
+    #[max_len(8, 64)]
+    pub genome_ids: Vec<String>,
+    /// `CategoryIndex` page listing this transaction while it is open.
+    pub category_index: Pubkey,
+    /// Price per unit and units left for listings sold by quantity; both
+    /// zero for fixed-price listings.
+    pub unit_price: u64,
+    pub units_available: u64,
+    /// The most recent `MAX_STATUS_HISTORY` status changes, oldest first.
+    #[max_len(16)]
+    pub status_history: Vec<StatusChange>,
+    /// `ACCESS_*` level each license sold here confers.
+    pub access_level: u8,
+    /// Escrowed tokens lent out through `Config::yield_program`; escrow pays
+    /// nothing out until they are redeemed.
+    pub yield_principal: u64,
+    /// The yield program `yield_principal` was lent to, which redemption
+    /// goes back to even after the config is re-pointed.
+    pub yield_program: Pubkey,
+    /// Seller collateral held in escrow alongside any deposit, slashed to
+    /// the buyer if a dispute goes their way.
+    pub collateral_amount: u64,
+    /// Free-text context for this listing alone, e.g. "includes phenotype
+    /// annotations"; the genome's own metadata is left untouched.
+    #[max_len(256)]
+    pub listing_notes: String,
+    /// Lowest `SellerProfile::reputation` a buyer needs; zero admits
+    /// anyone.
+    pub min_buyer_reputation: u16,
+    /// Whether `crank_relist` may reopen the listing once it expires, and
+    /// how many times it has.
+    pub auto_relist: bool,
+    pub auto_relists: u32,
+    /// Lowest offer on the genome the seller will accept while this listing
+    /// is open; zero for no floor.
+    pub min_acceptable_price: u64,
+    /// Part of `price` charged for converting a USD-pegged price at the
+    /// latest sale, owed to the treasury rather than split with the seller.
+    pub conversion_fee: u64,
+    /// When collateral was last slashed for a heartbeat lapse.
+    pub last_sla_claim: i64,
+    /// Program the listing, and its escrow, were handed over to.
+    pub migrated_to: Option<Pubkey>,
+}
+
+impl Transaction {
+    /// Moves to `status`, recording the change. Once the history is full
+    /// the oldest entry is dropped, so the account never outgrows its space.
+    pub fn set_status(&mut self, status: TransactionStatus, now: i64) {
+        if self.status_history.len() >= MAX_STATUS_HISTORY {
+            self.status_history.remove(0);
+        }
+        self.status_history.push(StatusChange {
+            status: status.clone(),
+            timestamp: now,
+        });
+        self.status = status;
+    }
+
+    /// Retires the listing in favour of `new_program`. Its bookkeeping stays
+    /// readable for the new program to pick up.
+    pub fn migrate(&mut self, new_program: Pubkey, now: i64) -> Result<()> {
+        self.assert_live()?;
+        self.migrated_to = Some(new_program);
+        self.set_status(TransactionStatus::Migrated, now);
+        Ok(())
+    }
+
+    /// Rejects listings handed over to a new program by `migrate_to`, whose
+    /// escrow has already moved there.
+    pub fn assert_live(&self) -> Result<()> {
+        require!(
+            self.status != TransactionStatus::Migrated,
+            GenomeError::AccountMigrated
+        );
+        Ok(())
+    }
+
+    /// A license can be renewed on any live listing not under, or lost to,
+    /// a dispute.
+    pub fn assert_renewable(&self) -> Result<()> {
+        self.assert_live()?;
+        require!(
+            !matches!(
+                self.status,
+                TransactionStatus::Disputed | TransactionStatus::Refunded
+            ),
+            GenomeError::InvalidTransactionStatus
+        );
+        Ok(())
+    }
+
+    pub fn assert_seller(&self, authority: Pubkey) -> Result<()> {
+        require_keys_eq!(self.seller, authority, GenomeError::Unauthorized);
+        Ok(())
+    }
+
+    pub fn apply_terms(&mut self, terms: ListingTerms) {
+        self.price = terms.price;
+        self.access_duration = terms.access_duration;
+        self.listing_expires_at = terms.listing_expires_at;
+        self.max_licenses = terms.max_licenses;
+        self.max_per_buyer = terms.max_per_buyer;
+        self.terms_hash = terms.terms_hash;
+        self.allowed_jurisdictions = terms.allowed_jurisdictions;
+        self.auction = terms.min_bid_increment.map(|min_increment| Auction {
+            min_increment,
+            bid_count: 0,
+            reserve_price: terms.reserve_price,
+            reveal_ends_at: terms
+                .reveal_period
+                .map(|period| terms.listing_expires_at.saturating_add(period)),
+        });
+        self.allowed_buyer = terms.allowed_buyer;
+        if let Some(gate) = terms.token_gate {
+            self.required_token_mint = Some(gate.mint);
+            self.required_amount = gate.amount;
+        }
+        if let Some(peg) = terms.usd_peg {
+            self.price_in_usd = Some(peg.price_in_usd);
+            self.price_feed = Some(peg.price_feed);
+        }
+        if let Some(units) = terms.unit_pricing {
+            self.unit_price = units.unit_price;
+            self.units_available = units.units_available;
+        }
+        self.access_level = terms.access_level;
+        self.listing_notes = terms.listing_notes;
+        self.min_buyer_reputation = terms.min_buyer_reputation;
+        self.auto_relist = terms.auto_relist;
+        self.min_acceptable_price = terms.min_acceptable_price;
+    }
+
+    /// Takes `quantity` units off a unit-priced listing and returns what
+    /// they cost. Fixed-price listings ignore `quantity` and return `None`.
+    pub fn take_units(&mut self, quantity: u64) -> Result<Option<u64>> {
+        if self.unit_price == 0 {
+            return Ok(None);
+        }
+        require!(
+            quantity > 0 && quantity <= self.units_available,
+            GenomeError::InsufficientUnits
+        );
+        let amount = self
+            .unit_price
+            .checked_mul(quantity)
+            .ok_or(GenomeError::MathOverflow)?;
+        self.units_available -= quantity;
+        Ok(Some(amount))
+    }
+
+    /// Terms for relisting a cancelled or expired listing: everything but
+    /// the price and access duration carries forward, and the listing period
+    /// keeps its length, starting from `now`.
+    pub fn relisting_terms(
+        &self,
+        price: u64,
+        access_duration: i64,
+        now: i64,
+    ) -> Result<ListingTerms> {
+        require!(
+            matches!(
+                self.status,
+                TransactionStatus::Cancelled | TransactionStatus::Expired
+            ),
+            GenomeError::InvalidTransactionStatus
+        );
+        let listing_period = self.listing_expires_at.saturating_sub(self.created_at);
+        Ok(ListingTerms {
+            price,
+            access_duration,
+            listing_expires_at: now
+                .checked_add(listing_period)
+                .ok_or(GenomeError::MathOverflow)?,
+            min_bid_increment: self.auction.as_ref().map(|a| a.min_increment),
+            allowed_buyer: self.allowed_buyer,
+            token_gate: self.required_token_mint.map(|mint| TokenGate {
+                mint,
+                amount: self.required_amount,
+            }),
+            max_licenses: self.max_licenses,
+            max_per_buyer: self.max_per_buyer,
+            reserve_price: self.auction.as_ref().map_or(0, |a| a.reserve_price),
+            reveal_period: self
+                .auction
+                .as_ref()
+                .and_then(|a| a.reveal_ends_at)
+                .map(|end| end - self.listing_expires_at),
+            terms_hash: self.terms_hash,
+            allowed_jurisdictions: self.allowed_jurisdictions.clone(),
+            usd_peg: self
+                .price_in_usd
+                .zip(self.price_feed)
+                .map(|(price_in_usd, price_feed)| UsdPeg {
+                    price_in_usd,
+                    price_feed,
+                }),
+            unit_pricing: (self.unit_price > 0).then_some(UnitPricing {
+                unit_price: self.unit_price,
+                units_available: self.units_available,
+            }),
+            access_level: self.access_level,
+            listing_notes: self.listing_notes.clone(),
+            min_buyer_reputation: self.min_buyer_reputation,
+            auto_relist: self.auto_relist,
+            min_acceptable_price: self.min_acceptable_price,
+        })
+    }
+
+    /// Entry guard for execution. Account write locks already serialize
+    /// instructions on one transaction, so this is defence in depth against
+    /// re-entry and against a record that is half-executed.
+    pub fn begin_execution(&mut self) -> Result<()> {
+        require!(!self.locked, GenomeError::TransactionLocked);
+        require!(
+            self.status == TransactionStatus::Created
+                && self.licenses_issued < self.max_licenses
+                && self.buyer.is_none()
+                && self.executed_at.is_none(),
+            GenomeError::InvalidTransactionStatus
+        );
+        self.locked = true;
+        Ok(())
+    }
+
+    /// Records one license sale. Exclusive listings move straight to
+    /// `Executed`; non-exclusive ones free the escrow for the next buyer and
+    /// only close once the last license is issued.
+    pub fn complete_execution(
+        &mut self,
+        buyer: Pubkey,
+        executed_at: i64,
+        settled: bool,
+    ) -> Result<()> {
+        // Checked again here, next to the assignments, so neither field can
+        // be overwritten even if an entry path skips `begin_execution`.
+        require!(self.buyer.is_none(), GenomeError::BuyerAlreadySet);
+        require!(self.executed_at.is_none(), GenomeError::AlreadyExecuted);
+
+        self.licenses_issued = self
+            .licenses_issued
+            .checked_add(1)
+            .ok_or(GenomeError::MathOverflow)?;
+        if self.is_exclusive() {
+            self.buyer = Some(buyer);
+        } else {
+            self.depositor = None;
+        }
+        let units_sold_out = self.unit_price > 0 && self.units_available == 0;
+        if self.licenses_issued == self.max_licenses || units_sold_out {
+            self.executed_at = Some(executed_at);
+            self.set_status(TransactionStatus::Executed, executed_at);
+        }
+        self.settled = settled;
+        self.locked = false;
+        Ok(())
+    }
+
+    /// Collateral the seller must post for this listing. Only exclusive
+    /// sales can be disputed, so other listings post none.
+    pub fn required_collateral(&self, collateral_bps: u16) -> Result<u64> {
+        if !self.is_exclusive() {
+            return Ok(0);
+        }
+        apply_bps(self.price, collateral_bps)
+    }
+
+    /// Takes the collateral out of the books for paying out of escrow.
+    pub fn release_collateral(&mut self) -> u64 {
+        std::mem::take(&mut self.collateral_amount)
+    }
+
+    /// Pushes the listing's expiry back by `additional`, keeping the whole
+    /// listing period from creation within `max_duration`.
+    pub fn extend_listing(&mut self, additional: i64, max_duration: i64) -> Result<()> {
+        require!(additional > 0, GenomeError::InvalidDuration);
+        let expires_at = self
+            .listing_expires_at
+            .checked_add(additional)
+            .ok_or(GenomeError::MathOverflow)?;
+        require!(
+            expires_at.saturating_sub(self.created_at) <= max_duration,
+            GenomeError::ListingDurationOutOfBounds
+        );
+        self.listing_expires_at = expires_at;
+        Ok(())
+    }
+
+    /// Records who referred the sale to `buyer`; neither side of the sale
+    /// may refer it.
+    pub fn set_referrer(&mut self, referrer: Option<Pubkey>, buyer: Pubkey) -> Result<()> {
+        if let Some(referrer) = referrer {
+            require!(
+                referrer != buyer && referrer != self.seller,
+                GenomeError::SelfReferral
+            );
+        }
+        self.referrer = referrer;
+        Ok(())
+    }
+
+    pub fn assert_terms(&self, terms_hash: &[u8; 32]) -> Result<()> {
+        require!(self.terms_hash == *terms_hash, GenomeError::TermsMismatch);
+        Ok(())
+    }
+
+    /// Takes `sla_slash_bps` of the remaining collateral for the current
+    /// lapse in the genome's heartbeat, at most once per lapse. Returns the
+    /// amount taken and when the lapse began.
+    pub fn slash_for_breach(
+        &mut self,
+        last_heartbeat: i64,
+        heartbeat_ttl: i64,
+        sla_slash_bps: u16,
+        now: i64,
+    ) -> Result<(u64, i64)> {
+        require!(
+            heartbeat_ttl > 0 && sla_slash_bps > 0,
+            GenomeError::SlaDisabled
+        );
+        let breached_at = last_heartbeat
+            .checked_add(heartbeat_ttl)
+            .ok_or(GenomeError::MathOverflow)?;
+        require!(now > breached_at, GenomeError::NoSlaBreach);
+        require!(
+            self.last_sla_claim <= breached_at,
+            GenomeError::SlaAlreadyClaimed
+        );
+        let amount = apply_bps(self.collateral_amount, sla_slash_bps)?;
+        require!(amount > 0, GenomeError::NoCollateral);
+        self.collateral_amount -= amount;
+        self.last_sla_claim = now;
+        Ok((amount, breached_at))
+    }
+
+    /// Checks an offer of `amount` in `payment_mint` meets the listing's
+    /// offer floor, which is in the listing's own currency.
+    pub fn assert_offer_floor(&self, amount: u64, payment_mint: Option<Pubkey>) -> Result<()> {
+        if self.min_acceptable_price == 0 {
+            return Ok(());
+        }
+        require!(self.payment_mint == payment_mint, GenomeError::MintMismatch);
+        require!(
+            amount >= self.min_acceptable_price,
+            GenomeError::OfferBelowFloor
+        );
+        Ok(())
+    }
+
+    /// Checks the listing is over and escrow owes nobody anything: a
+    /// terminal status, any sale settled, and no deposit, collateral or
+    /// lent-out funds left behind.
+    pub fn assert_closable(&self) -> Result<()> {
+        require!(
+            matches!(
+                self.status,
+                TransactionStatus::Executed
+                    | TransactionStatus::Cancelled
+                    | TransactionStatus::Expired
+                    | TransactionStatus::Refunded
+            ),
+            GenomeError::InvalidTransactionStatus
+        );
+        require!(
+            !self.awaiting_settlement()
+                && self.depositor.is_none()
+                && self.collateral_amount == 0
+                && self.yield_principal == 0,
+            GenomeError::EscrowNotEmpty
+        );
+        Ok(())
+    }
+
+    /// An executed sale whose proceeds are still in escrow, open to dispute
+    /// until the window closes. `Locked` sales are also awaiting the buyer's
+    /// confirmation.
+    pub fn awaiting_settlement(&self) -> bool {
+        matches!(
+            self.status,
+            TransactionStatus::Executed | TransactionStatus::Locked
+        ) && !self.settled
+    }
+
+    pub fn assert_jurisdiction(&self, jurisdiction: &[u8; 2]) -> Result<()> {
+        validate_jurisdiction(jurisdiction)?;
+        require!(
+            self.allowed_jurisdictions.is_empty()
+                || self.allowed_jurisdictions.contains(jurisdiction),
+            GenomeError::JurisdictionNotAllowed
+        );
+        Ok(())
+    }
+
+    pub fn is_exclusive(&self) -> bool {
+        self.max_licenses <= 1
+    }
+
+    pub fn assert_buyer_allowed(&self, buyer: Pubkey, whitelist: Option<&Whitelist>) -> Result<()> {
+        if let Some(allowed_buyer) = self.allowed_buyer {
+            require_keys_eq!(allowed_buyer, buyer, GenomeError::BuyerNotAllowed);
+        }
+        if self.whitelist.is_some() {
+            let whitelist = whitelist.ok_or(GenomeError::BuyerNotAllowed)?;
+            require!(whitelist.contains(&buyer), GenomeError::BuyerNotAllowed);
+        }
+        Ok(())
+    }
+
+    /// Checks the buyer's reputation meets the listing's minimum; buyers
+    /// with no profile count as unrated.
+    pub fn assert_buyer_reputation(&self, profile: Option<&SellerProfile>) -> Result<()> {
+        if self.min_buyer_reputation == 0 {
+            return Ok(());
+        }
+        let reputation = profile.map_or(0, SellerProfile::reputation);
+        require!(
+            reputation >= self.min_buyer_reputation,
+            GenomeError::BuyerReputationTooLow
+        );
+        Ok(())
+    }
+
+    pub fn assert_token_gate(&self, holding: Option<&TokenAccount>) -> Result<()> {
+        let Some(required_mint) = self.required_token_mint else {
+            return Ok(());
+        };
+        let holding = holding.ok_or(GenomeError::TokenGateNotMet)?;
+        require_keys_eq!(holding.mint, required_mint, GenomeError::MintMismatch);
+        require!(
+            holding.amount >= self.required_amount,
+            GenomeError::TokenGateNotMet
+        );
+        Ok(())
+    }
+
+    /// The seller may release held proceeds to themselves once the dispute
+    /// window has passed without a dispute, without the buyer confirming.
+    pub fn assert_claimable(&self, dispute_window: i64, now: i64) -> Result<()> {
+        require!(
+            self.awaiting_settlement(),
+            GenomeError::InvalidTransactionStatus
+        );
+        require!(
+            now > self.dispute_deadline(dispute_window)?,
+            GenomeError::DisputeWindowOpen
+        );
+        Ok(())
+    }
+
+    pub fn dispute_deadline(&self, dispute_window: i64) -> Result<i64> {
+        self.executed_at
+            .ok_or(GenomeError::InvalidTransactionStatus)?
+            .checked_add(dispute_window)
+            .ok_or_else(|| error!(GenomeError::MathOverflow))
+    }
+
+    pub fn listing_open(&self, now: i64) -> bool {
+        now < self.listing_expires_at
+    }
+
+    /// Checks the listing can be bought at `now`: fixed-price listings until
+    /// they expire, auctions only once bidding has closed above the reserve.
+    pub fn assert_purchasable(&self, now: i64) -> Result<()> {
+        if self.auction.is_some() {
+            require!(self.bidding_closed(now), GenomeError::AuctionNotEnded);
+            require!(self.reserve_met(), GenomeError::ReserveNotMet);
+        } else {
+            require!(self.listing_open(now), GenomeError::ListingExpired);
+        }
+        Ok(())
+    }
+
+    /// Whether the listing has stopped taking purchases and bids, counting
+    /// the reveal period of sealed-bid auctions.
+    pub fn bidding_closed(&self, now: i64) -> bool {
+        !self.listing_open(now)
+            && self
+                .auction
+                .as_ref()
+                .and_then(|a| a.reveal_ends_at)
+                .map_or(true, |reveal_ends_at| now >= reveal_ends_at)
+    }
+
+    /// Sealed bids are revealed between the close of bidding and the end
+    /// of the reveal period.
+    pub fn assert_reveal_open(&self, now: i64) -> Result<()> {
+        let reveal_ends_at = self
+            .auction
+            .as_ref()
+            .and_then(|a| a.reveal_ends_at)
+            .ok_or(GenomeError::NotSealedAuction)?;
+        require!(
+            !self.listing_open(now) && now < reveal_ends_at,
+            GenomeError::RevealNotOpen
+        );
+        Ok(())
+    }
+
+    /// Whether `cancel_transactions_batch` may cancel this listing for
+    /// `seller`: it must be theirs, still open and hold no deposit or
+    /// collateral that would need paying out. Auction bids are withdrawn
+    /// separately.
+    pub fn batch_cancellable(&self, seller: Pubkey) -> bool {
+        self.seller == seller
+            && self.status == TransactionStatus::Created
+            && (self.auction.is_some() || self.depositor.is_none())
+            && self.collateral_amount == 0
+    }
+
+    /// Escrow may be swept by the admin only once the transaction is closed,
+    /// with nothing left for the seller or buyer to release, and has been
+    /// idle for `ESCROW_RECOVERY_DELAY`.
+    pub fn assert_recoverable(&self, now: i64) -> Result<()> {
+        let closed = match self.status {
+            TransactionStatus::Cancelled
+            | TransactionStatus::Expired
+            | TransactionStatus::Refunded => true,
+            TransactionStatus::Executed => self.settled,
+            _ => false,
+        };
+        require!(closed, GenomeError::InvalidTransactionStatus);
+        let last_activity = self
+            .executed_at
+            .unwrap_or(self.created_at)
+            .max(self.listing_expires_at);
+        require!(
+            now >= last_activity.saturating_add(ESCROW_RECOVERY_DELAY),
+            GenomeError::RecoveryTooEarly
+        );
+        Ok(())
+    }
+
+    /// A listing may be expired once its period has passed without a sale.
+    /// Auctions that drew a bid are left for the winner to execute.
+    pub fn assert_expirable(&self, now: i64) -> Result<()> {
+        require!(
+            self.status == TransactionStatus::Created,
+            GenomeError::InvalidTransactionStatus
+        );
+        require!(self.bidding_closed(now), GenomeError::ListingNotExpired);
+        require!(
+            self.auction.is_none() || self.depositor.is_none() || !self.reserve_met(),
+            GenomeError::AuctionHasBids
+        );
+        Ok(())
+    }
+
+    /// Restarts a lapsed, unfunded auto-relisting listing from `now`,
+    /// keeping the length of its listing period.
+    pub fn auto_relist(&mut self, now: i64) -> Result<()> {
+        require!(self.auto_relist, GenomeError::AutoRelistDisabled);
+        require!(self.auction.is_none(), GenomeError::AuctionListing);
+        require!(
+            self.status == TransactionStatus::Created,
+            GenomeError::InvalidTransactionStatus
+        );
+        require!(!self.listing_open(now), GenomeError::ListingNotExpired);
+        require!(self.depositor.is_none(), GenomeError::EscrowAlreadyFunded);
+        require!(
+            self.auto_relists < MAX_AUTO_RELISTS,
+            GenomeError::AutoRelistLimitReached
+        );
+        let period = self.listing_expires_at.saturating_sub(self.created_at);
+        self.listing_expires_at = now.checked_add(period).ok_or(GenomeError::MathOverflow)?;
+        self.created_at = now;
+        self.auto_relists += 1;
+        self.set_status(TransactionStatus::Created, now);
+        Ok(())
+    }
+
+    /// Whether an auction's top bid can win; always true for fixed-price
+    /// listings.
+    pub fn reserve_met(&self) -> bool {
+        match &self.auction {
+            Some(auction) => self.depositor.is_some() && self.price >= auction.reserve_price,
+            None => true,
+        }
+    }
+
+    /// Smallest acceptable bid: the starting price until someone bids, then
+    /// the current top bid plus the minimum increment.
+    pub fn minimum_bid(&self) -> Result<u64> {
+        let auction = self.auction.as_ref().ok_or(GenomeError::NotAnAuction)?;
+        if self.depositor.is_none() {
+            return Ok(self.price);
+        }
+        self.price
+            .checked_add(auction.min_increment.max(1))
+            .ok_or_else(|| error!(GenomeError::MathOverflow))
+    }
+}
+
+/// Time-limited license issued to a buyer for one genome, checked by
+/// off-chain services before releasing decryption keys.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct AccessGrant {
+    pub genome: Pubkey,
+    pub transaction: Pubkey,
+    pub buyer: Pubkey,
+    pub granted_at: i64,
+    pub expires_at: i64,
+    pub bump: u8,
+    pub revoked: bool,
+    pub key_status: KeyStatus,
+    /// Decryption key for the genome file, encrypted to the buyer.
+    #[max_len(512)]
+    pub encrypted_key: Vec<u8>,
+    /// License terms the buyer acknowledged, and when.
+    pub terms_hash: [u8; 32],
+    pub terms_accepted_at: i64,
+    /// Jurisdiction the buyer declared at purchase.
+    pub buyer_jurisdiction: [u8; 2],
+    /// Whether `process_renewal` may extend the grant from the buyer's
+    /// pre-approved token account.
+    pub auto_renew: bool,
+    /// Hash of the data the buyer acknowledged receiving, and when.
+    pub delivery_hash: [u8; 32],
+    pub delivered_at: i64,
+    /// When `revoke_access` was called and how long the grant keeps working
+    /// afterwards. Zero for grants revoked on the spot by refunds and
+    /// recalls.
+    pub revoked_at: i64,
+    pub revocation_grace: i64,
+    /// `USE_*` code the buyer declared at purchase.
+    pub declared_use: u8,
+    /// `ACCESS_*` level the license confers.
+    pub access_level: u8,
+    /// A paid request for the data in another `FORMAT_*`: the target, the
+    /// fee held in the grant's conversion vault and the vault's bump.
+    pub conversion_requested: bool,
+    pub conversion_format: u8,
+    pub conversion_fee: u64,
+    pub conversion_bump: u8,
+    /// Hash of the converted file, once the seller has delivered it.
+    pub conversion_hash: [u8; 32],
+}
+
+impl AccessGrant {
+    /// Opens a conversion request into `target_format`, which must differ
+    /// from the genome's `original_format`.
+    pub fn request_conversion(
+        &mut self,
+        original_format: u8,
+        target_format: u8,
+        fee: u64,
+    ) -> Result<()> {
+        require!(!self.conversion_requested, GenomeError::ConversionPending);
+        require!(target_format <= FORMAT_OTHER, GenomeError::InvalidFormat);
+        require!(target_format != original_format, GenomeError::SameFormat);
+        require!(fee > 0, GenomeError::InvalidPrice);
+        self.conversion_requested = true;
+        self.conversion_format = target_format;
+        self.conversion_fee = fee;
+        Ok(())
+    }
+
+    /// Records the converted file and returns the fee now owed the seller.
+    pub fn fulfill_conversion(&mut self, result_hash: [u8; 32]) -> Result<u64> {
+        require!(
+            self.conversion_requested,
+            GenomeError::NoConversionRequested
+        );
+        self.conversion_requested = false;
+        self.conversion_hash = result_hash;
+        Ok(std::mem::take(&mut self.conversion_fee))
+    }
+
+    pub fn deliver_key(&mut self, encrypted_key: Vec<u8>, now: i64) -> Result<()> {
+        require!(!encrypted_key.is_empty(), GenomeError::InvalidKey);
+        require!(
+            encrypted_key.len() <= MAX_ENCRYPTED_KEY_LEN,
+            GenomeError::InvalidKey
+        );
+        self.assert_active(now)?;
+        self.encrypted_key = encrypted_key;
+        self.key_status = KeyStatus::KeyDelivered;
+        Ok(())
+    }
+
+    /// Records the buyer's acknowledgement that the data behind the delivered
+    /// key was usable, for arbitrators settling later disputes.
+    pub fn confirm_delivery(&mut self, data_hash: [u8; 32], now: i64) -> Result<()> {
+        require!(
+            self.key_status == KeyStatus::KeyDelivered,
+            GenomeError::KeyNotDelivered
+        );
+        self.delivery_hash = data_hash;
+        self.delivered_at = now;
+        self.key_status = KeyStatus::Delivered;
+        Ok(())
+    }
+
+    /// Marks the grant revoked as of `now + grace`. Returns when access ends.
+    pub fn revoke(&mut self, now: i64, grace: i64) -> Result<i64> {
+        self.revoked = true;
+        self.revoked_at = now;
+        self.revocation_grace = grace;
+        now.checked_add(grace)
+            .ok_or_else(|| error!(GenomeError::MathOverflow))
+    }
+
+    /// Whether a revocation has taken effect, its grace period having run out.
+    pub fn revocation_effective(&self, now: i64) -> bool {
+        self.revoked && now >= self.revoked_at.saturating_add(self.revocation_grace)
+    }
+
+    pub fn assert_active(&self, now: i64) -> Result<()> {
+        require!(!self.revocation_effective(now), GenomeError::AccessRevoked);
+        require!(now <= self.expires_at, GenomeError::AccessExpired);
+        Ok(())
+    }
+
+    /// A buyer's grant for a genome lives at one address, so a new license
+    /// reuses it once the last one has expired or been revoked. A freshly
+    /// created account has no buyer yet.
+    pub fn assert_reissuable(&self, now: i64) -> Result<()> {
+        if self.buyer == Pubkey::default() {
+            return Ok(());
+        }
+        require!(
+            self.assert_active(now).is_err(),
+            GenomeError::AccessAlreadyGranted
+        );
+        require!(!self.conversion_requested, GenomeError::ConversionPending);
+        Ok(())
+    }
+
+    /// Clears the grant for a new license.
+    pub fn reissue(&mut self, now: i64) -> Result<()> {
+        self.assert_reissuable(now)?;
+        *self = Self::default();
+        Ok(())
+    }
+
+    pub fn status(&self, now: i64) -> AccessStatus {
+        let valid = self.assert_active(now).is_ok();
+        let ends_at = if self.revoked {
+            self.expires_at
+                .min(self.revoked_at.saturating_add(self.revocation_grace))
+        } else {
+            self.expires_at
+        };
+        AccessStatus {
+            valid,
+            remaining_seconds: if valid { (ends_at - now) as u64 } else { 0 },
+            expires_at: self.expires_at,
+            revoked: self.revoked,
+            key_delivered: matches!(
+                self.key_status,
+                KeyStatus::KeyDelivered | KeyStatus::Delivered
+            ),
+        }
+    }
+
+    /// Share of `price` covering the unused part of one `access_duration`
+    /// period, rounded down. Fails once the grant has lapsed or been revoked.
+    pub fn unused_refund(&self, price: u64, access_duration: i64, now: i64) -> Result<u64> {
+        self.assert_active(now)?;
+        require!(access_duration > 0, GenomeError::InvalidDuration);
+        let remaining = (self.expires_at - now).min(access_duration);
+        Ok((u128::from(price) * remaining as u128 / access_duration as u128) as u64)
+    }
+
+    /// Whether the renewal crank may extend the grant now: auto-renew is on,
+    /// it has not been revoked and it expires within `RENEWAL_WINDOW`.
+    pub fn renewal_due(&self, now: i64) -> bool {
+        self.auto_renew
+            && !self.revoked
+            && now <= self.expires_at
+            && self.expires_at - now <= RENEWAL_WINDOW
+    }
+
+    /// Extends the grant by `additional_duration`, counting from now if it
+    /// has already expired. Returns the new expiry.
+    pub fn renew(&mut self, now: i64, additional_duration: i64) -> Result<i64> {
+        require!(!self.revoked, GenomeError::AccessRevoked);
+        self.expires_at = self
+            .expires_at
+            .max(now)
+            .checked_add(additional_duration)
+            .ok_or(GenomeError::MathOverflow)?;
+        Ok(self.expires_at)
+    }
+}
+
+#[account]
+#[derive(Default, InitSpace)]
+pub struct Rating {
+    pub transaction: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub stars: u8,
+    #[max_len(280)]
+    pub comment: String,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+/// Sale proceeds held for a seller until they pull them with
+/// `withdraw_earnings`, one account per payment mint.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct Earnings {
+    pub seller: Pubkey,
+    /// `None` for native SOL.
+    pub payment_mint: Option<Pubkey>,
+    /// Token or SOL vault holding the balance, and its bump.
+    pub vault: Pubkey,
+    pub vault_bump: u8,
+    /// Accrued and not yet withdrawn.
+    pub balance: u64,
+    pub total_accrued: u64,
+    pub bump: u8,
+}
+
+impl Earnings {
+    pub fn accrue(&mut self, amount: u64) -> Result<()> {
+        self.balance = self
+            .balance
+            .checked_add(amount)
+            .ok_or(GenomeError::MathOverflow)?;
+        self.total_accrued = self
+            .total_accrued
+            .checked_add(amount)
+            .ok_or(GenomeError::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn withdraw(&mut self, amount: u64) -> Result<()> {
+        require!(amount > 0, GenomeError::InvalidAmount);
+        self.balance = self
+            .balance
+            .checked_sub(amount)
+            .ok_or(GenomeError::InsufficientEarnings)?;
+        Ok(())
+    }
+}
+
+/// Per-seller reputation aggregate; average rating is
+/// `rating_sum / rating_count`.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct SellerProfile {
+    pub seller: Pubkey,
+    pub rating_sum: u64,
+    pub rating_count: u64,
+    pub bump: u8,
+    /// Licenses sold and the total paid for them, in each listing's
+    /// payment units.
+    pub total_sales: u64,
+    pub total_volume: u64,
+    pub active_listings: u32,
+    /// Cumulative tips, likewise in each tip's payment units.
+    pub tips_received: u64,
+    /// When the seller last opened a listing; zero if never.
+    pub last_listed_at: i64,
+    /// Time-weighted rating aggregate: each rating adds
+    /// `stars * REPUTATION_WEIGHT_SCALE` and `REPUTATION_WEIGHT_SCALE`, and
+    /// both decay with the configured half-life. They were last brought up
+    /// to date at `weighted_at`.
+    pub weighted_rating_sum: u64,
+    pub weighted_rating_count: u64,
+    pub weighted_at: i64,
+}
+
+/// Running total of the referral cuts a referrer has been paid.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct ReferralProfile {
+    pub referrer: Pubkey,
+    pub referral_count: u64,
+    /// Summed in each sale's payment units, like `SellerProfile` volume.
+    pub referral_earnings: u64,
+    pub bump: u8,
+}
+
+impl ReferralProfile {
+    /// Claims a freshly created profile for `referrer`; a no-op afterwards.
+    pub fn open(&mut self, referrer: Pubkey, bump: u8) {
+        if self.referrer == Pubkey::default() {
+            self.referrer = referrer;
+            self.bump = bump;
+        }
+    }
+
+    pub fn record_referral(&mut self, amount: u64) -> Result<()> {
+        self.referral_count = self
+            .referral_count
+            .checked_add(1)
+            .ok_or(GenomeError::MathOverflow)?;
+        self.referral_earnings = self
+            .referral_earnings
+            .checked_add(amount)
+            .ok_or(GenomeError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+/// Free licenses the admin has granted a research nonprofit.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct GrantQuota {
+    pub holder: Pubkey,
+    pub remaining: u32,
+    /// How long each license claimed under the quota lasts.
+    pub access_duration: i64,
+    pub bump: u8,
+}
+
+impl GrantQuota {
+    pub fn consume(&mut self) -> Result<()> {
+        self.remaining = self
+            .remaining
+            .checked_sub(1)
+            .ok_or(GenomeError::GrantQuotaExhausted)?;
+        Ok(())
+    }
+}
+
+/// Global pool of pass revenue, shared equally among opted-in genomes
+/// through a running per-genome total.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct SubscriptionPool {
+    /// Lamports per pass, and how long each pass lasts.
+    pub pass_price: u64,
+    pub pass_duration: i64,
+    /// Genomes currently in the pool.
+    pub participants: u32,
+    /// Revenue owed to each participating genome since the pool opened,
+    /// scaled by `POOL_REVENUE_SCALE`.
+    pub revenue_per_genome: u128,
+    pub total_revenue: u64,
+    pub vault_bump: u8,
+    pub bump: u8,
+}
+
+impl SubscriptionPool {
+    pub fn join(&mut self, genome: &mut Genome) -> Result<()> {
+        require!(
+            !genome.subscription_pool,
+            GenomeError::AlreadyInSubscriptionPool
+        );
+        self.participants = self
+            .participants
+            .checked_add(1)
+            .ok_or(GenomeError::MathOverflow)?;
+        genome.subscription_pool = true;
+        genome.pool_checkpoint = self.revenue_per_genome;
+        Ok(())
+    }
+
+    /// Takes the genome out of the pool and returns its unpaid share.
+    pub fn leave(&mut self, genome: &mut Genome) -> Result<u64> {
+        require!(genome.subscription_pool, GenomeError::NotInSubscriptionPool);
+        let owed = self.settle(genome)?;
+        self.participants = self.participants.saturating_sub(1);
+        genome.subscription_pool = false;
+        Ok(owed)
+    }
+
+    /// The genome's share accrued since its checkpoint, which moves up to
+    /// the current total. Rounding dust stays in the vault.
+    pub fn settle(&self, genome: &mut Genome) -> Result<u64> {
+        let owed = (self.revenue_per_genome - genome.pool_checkpoint) / POOL_REVENUE_SCALE;
+        genome.pool_checkpoint = self.revenue_per_genome;
+        u64::try_from(owed).map_err(|_| error!(GenomeError::MathOverflow))
+    }
+
+    /// Splits a pass sale across the genomes in the pool right now.
+    pub fn record_sale(&mut self, amount: u64) -> Result<()> {
+        require!(self.participants > 0, GenomeError::EmptySubscriptionPool);
+        self.revenue_per_genome = u128::from(amount)
+            .checked_mul(POOL_REVENUE_SCALE)
+            .map(|scaled| scaled / u128::from(self.participants))
+            .and_then(|share| self.revenue_per_genome.checked_add(share))
+            .ok_or(GenomeError::MathOverflow)?;
+        self.total_revenue = self
+            .total_revenue
+            .checked_add(amount)
+            .ok_or(GenomeError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+/// A buyer's all-access pass to every genome in the subscription pool.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct SubscriptionPass {
+    pub holder: Pubkey,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl SubscriptionPass {
+    /// Adds `duration` from the current expiry, or from `now` if the pass
+    /// has lapsed.
+    pub fn extend(&mut self, now: i64, duration: i64) -> Result<()> {
+        self.expires_at = self
+            .expires_at
+            .max(now)
+            .checked_add(duration)
+            .ok_or(GenomeError::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn assert_covers(&self, genome: &Genome, now: i64) -> Result<()> {
+        require!(genome.subscription_pool, GenomeError::NotInSubscriptionPool);
+        require!(now <= self.expires_at, GenomeError::AccessExpired);
+        Ok(())
+    }
+}
+
+/// Snapshot of an `AccessGrant` returned by `get_access_status`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct AccessStatus {
+    pub valid: bool,
+    /// Zero once the grant has expired or been revoked.
+    pub remaining_seconds: u64,
+    pub expires_at: i64,
+    pub revoked: bool,
+    pub key_delivered: bool,
+}
+
+/// Result of `check_purchasable`: whether the listing can be bought and,
+/// if not, the `PURCHASE_*` code of the first gate that stops it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct PurchaseCheck {
+    pub purchasable: bool,
+    pub reason: u8,
+}
+
+impl PurchaseCheck {
+    pub fn evaluate(
+        config: &Config,
+        genome: &Genome,
+        transaction: &Transaction,
+        blacklist: &Blacklist,
+        now: i64,
+    ) -> Self {
+        let reason = if config.paused {
+            PURCHASE_PAUSED
+        } else if genome.deleted {
+            PURCHASE_GENOME_DELETED
+        } else if genome.migrated_to.is_some() {
+            PURCHASE_MIGRATED
+        } else if !genome.consent_active {
+            PURCHASE_CONSENT_WITHDRAWN
+        } else if genome.embargoed(now) {
+            PURCHASE_EMBARGOED
+        } else if blacklist.contains(&transaction.seller) {
+            PURCHASE_SELLER_BLACKLISTED
+        } else if transaction.status != TransactionStatus::Created {
+            PURCHASE_LISTING_CLOSED
+        } else if transaction.assert_purchasable(now).is_err() {
+            if transaction.auction.is_some() && !transaction.bidding_closed(now) {
+                PURCHASE_AUCTION_OPEN
+            } else {
+                PURCHASE_LISTING_EXPIRED
+            }
+        } else if genome
+            .assert_heartbeat_fresh(config.heartbeat_ttl, now)
+            .is_err()
+        {
+            PURCHASE_HEARTBEAT_STALE
+        } else {
+            PURCHASE_OK
+        };
+        Self {
+            purchasable: reason == PURCHASE_OK,
+            reason,
+        }
+    }
+}
+
+/// Snapshot of a `SellerProfile` returned by `get_seller_stats`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct SellerStats {
+    pub total_sales: u64,
+    pub total_volume: u64,
+    pub active_listings: u32,
+    pub rating_sum: u64,
+    pub rating_count: u64,
+    pub tips_received: u64,
+}
+
+/// A seller's time-decayed reputation, returned by `get_weighted_reputation`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct WeightedReputation {
+    /// Weighted average rating in hundredths of a star, zero while unrated.
+    pub reputation: u16,
+    /// How many fresh ratings the decayed ones add up to, scaled by
+    /// `REPUTATION_WEIGHT_SCALE`.
+    pub weight: u64,
+}
+
+impl SellerProfile {
+    /// Claims a freshly created profile for `seller`; a no-op afterwards.
+    pub fn open(&mut self, seller: Pubkey, bump: u8) {
+        if self.seller == Pubkey::default() {
+            self.seller = seller;
+            self.bump = bump;
+        }
+    }
+
+    /// Stamps a new listing at `now`, failing while the previous one is
+    /// still inside `cooldown`.
+    pub fn start_listing(&mut self, now: i64, cooldown: i64) -> Result<()> {
+        if self.last_listed_at != 0 {
+            let ready_at = self
+                .last_listed_at
+                .checked_add(cooldown)
+                .ok_or(GenomeError::MathOverflow)?;
+            require!(now >= ready_at, GenomeError::ListingCooldown);
+        }
+        self.last_listed_at = now;
+        Ok(())
+    }
+
+    pub fn record_listing(&mut self) -> Result<()> {
+        self.active_listings = self
+            .active_listings
+            .checked_add(1)
+            .ok_or(GenomeError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Saturates so a listing opened before stats were tracked cannot
+    /// underflow the counter when it closes.
+    pub fn record_closed_listing(&mut self) {
+        self.active_listings = self.active_listings.saturating_sub(1);
+    }
+
+    pub fn record_sale(&mut self, price: u64) -> Result<()> {
+        self.total_sales = self
+            .total_sales
+            .checked_add(1)
+            .ok_or(GenomeError::MathOverflow)?;
+        self.total_volume = self
+            .total_volume
+            .checked_add(price)
+            .ok_or(GenomeError::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn record_tip(&mut self, amount: u64) -> Result<()> {
+        self.tips_received = self
+            .tips_received
+            .checked_add(amount)
+            .ok_or(GenomeError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Average rating in hundredths of a star, zero while unrated.
+    pub fn reputation(&self) -> u16 {
+        if self.rating_count == 0 {
+            return 0;
+        }
+        (self.rating_sum.saturating_mul(100) / self.rating_count).min(u64::from(MAX_REPUTATION))
+            as u16
+    }
+
+    pub fn stats(&self) -> SellerStats {
+        SellerStats {
+            total_sales: self.total_sales,
+            total_volume: self.total_volume,
+            active_listings: self.active_listings,
+            rating_sum: self.rating_sum,
+            rating_count: self.rating_count,
+            tips_received: self.tips_received,
+        }
+    }
+
+    pub fn record_rating(&mut self, stars: u8) -> Result<()> {
+        require!((1..=5).contains(&stars), GenomeError::InvalidRating);
+        self.rating_sum = self
+            .rating_sum
+            .checked_add(u64::from(stars))
+            .ok_or(GenomeError::MathOverflow)?;
+        self.rating_count = self
+            .rating_count
+            .checked_add(1)
+            .ok_or(GenomeError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Folds a rating into the time-weighted aggregate, first decaying what
+    /// is already there to `now`.
+    pub fn record_weighted_rating(&mut self, stars: u8, half_life: i64, now: i64) -> Result<()> {
+        require!((1..=5).contains(&stars), GenomeError::InvalidRating);
+        let elapsed = now.saturating_sub(self.weighted_at);
+        self.weighted_rating_sum = decay(self.weighted_rating_sum, elapsed, half_life)
+            .checked_add(u64::from(stars) * REPUTATION_WEIGHT_SCALE)
+            .ok_or(GenomeError::MathOverflow)?;
+        self.weighted_rating_count = decay(self.weighted_rating_count, elapsed, half_life)
+            .checked_add(REPUTATION_WEIGHT_SCALE)
+            .ok_or(GenomeError::MathOverflow)?;
+        self.weighted_at = now;
+        Ok(())
+    }
+
+    pub fn weighted_reputation(&self, half_life: i64, now: i64) -> WeightedReputation {
+        let elapsed = now.saturating_sub(self.weighted_at);
+        let sum = decay(self.weighted_rating_sum, elapsed, half_life);
+        let weight = decay(self.weighted_rating_count, elapsed, half_life);
+        let reputation = if weight == 0 {
+            0
+        } else {
+            (sum.saturating_mul(100) / weight).min(u64::from(MAX_REPUTATION)) as u16
+        };
+        WeightedReputation { reputation, weight }
+    }
+}
+
+/// Buyers a seller has approved for whitelist-only listings.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct Whitelist {
+    pub seller: Pubkey,
+    #[max_len(32)]
+    pub buyers: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl Whitelist {
+    pub fn contains(&self, buyer: &Pubkey) -> bool {
+        self.buyers.contains(buyer)
+    }
+
+    /// Adding a buyer that is already listed is a no-op.
+    pub fn add(&mut self, buyer: Pubkey) -> Result<()> {
+        if self.contains(&buyer) {
+            return Ok(());
+        }
+        require!(
+            self.buyers.len() < MAX_WHITELIST_LEN,
+            GenomeError::WhitelistFull
+        );
+        self.buyers.push(buyer);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, buyer: Pubkey) {
+        self.buyers.retain(|b| *b != buyer);
+    }
+}
+
+/// Marketplace-wide counters. Volume sums each sale's price in its own
+/// payment units, so it is only a rough gauge across mints. The counters
+/// saturate: they are metrics and must never block a sale. Only the epoch
+/// volume, which backs the circuit breaker, may.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct MarketStats {
+    pub total_genomes: u64,
+    pub total_transactions: u64,
+    pub total_volume: u64,
+    pub active_listings: u64,
+    pub bump: u8,
+    /// Volume executed so far in `epoch`, checked against
+    /// `Config::epoch_volume_cap`.
+    pub epoch: u64,
+    pub epoch_volume: u64,
+}
+
+impl MarketStats {
+    pub fn record_genomes(&mut self, added: u64) {
+        self.total_genomes = self.total_genomes.saturating_add(added);
+    }
+
+    pub fn record_listing(&mut self) {
+        self.active_listings = self.active_listings.saturating_add(1);
+    }
+
+    pub fn record_closed_listing(&mut self) {
+        self.active_listings = self.active_listings.saturating_sub(1);
+    }
+
+    pub fn record_sale(&mut self, price: u64) {
+        self.total_transactions = self.total_transactions.saturating_add(1);
+        self.total_volume = self.total_volume.saturating_add(price);
+    }
+
+    /// Adds `price` to the current epoch's volume, starting afresh when the
+    /// epoch has rolled over, and fails once a non-zero `cap` is exceeded.
+    pub fn record_epoch_volume(&mut self, epoch: u64, price: u64, cap: u64) -> Result<()> {
+        if epoch != self.epoch {
+            self.epoch = epoch;
+            self.epoch_volume = 0;
+        }
+        let volume = self
+            .epoch_volume
+            .checked_add(price)
+            .ok_or(GenomeError::MathOverflow)?;
+        require!(
+            cap == 0 || volume <= cap,
+            GenomeError::EpochVolumeCapReached
+        );
+        self.epoch_volume = volume;
+        Ok(())
+    }
+}
+
+/// Sellers the admin has banned from listing.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct Blacklist {
+    #[max_len(64)]
+    pub sellers: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl Blacklist {
+    pub fn contains(&self, seller: &Pubkey) -> bool {
+        self.sellers.contains(seller)
+    }
+
+    pub fn assert_allowed(&self, seller: &Pubkey) -> Result<()> {
+        require!(!self.contains(seller), GenomeError::SellerBlacklisted);
+        Ok(())
+    }
+
+    /// Adding a seller that is already listed is a no-op.
+    pub fn add(&mut self, seller: Pubkey) -> Result<()> {
+        if self.contains(&seller) {
+            return Ok(());
+        }
+        require!(
+            self.sellers.len() < MAX_BLACKLIST_LEN,
+            GenomeError::BlacklistFull
+        );
+        self.sellers.push(seller);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, seller: Pubkey) {
+        self.sellers.retain(|s| *s != seller);
+    }
+}
+
+/// One page of the licenses a buyer has purchased, chained like `UserIndex`
+/// pages so wallets can list them by fetching pages in order.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct BuyerHistory {
+    pub buyer: Pubkey,
+    pub page: u32,
+    #[max_len(16)]
+    pub records: Vec<PurchaseRecord>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub struct PurchaseRecord {
+    pub genome: Pubkey,
+    pub transaction: Pubkey,
+    pub price: u64,
+    pub purchased_at: i64,
+    pub expires_at: i64,
+}
+
+impl BuyerHistory {
+    /// Initializes a freshly created page; a no-op for an existing one.
+    /// Opening a later page requires the previous page to be full.
+    pub fn open(
+        &mut self,
+        buyer: Pubkey,
+        page: u32,
+        bump: u8,
+        previous: Option<&BuyerHistory>,
+    ) -> Result<()> {
+        if self.buyer != Pubkey::default() {
+            return Ok(());
+        }
+        if page > 0 {
+            let previous = previous.ok_or(GenomeError::InvalidIndexPage)?;
+            require!(
+                previous.page + 1 == page && previous.is_full(),
+                GenomeError::InvalidIndexPage
+            );
+        }
+        self.buyer = buyer;
+        self.page = page;
+        self.bump = bump;
+        Ok(())
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.records.len() >= MAX_BUYER_HISTORY_LEN
+    }
+
+    /// Appends `record`, or refreshes the existing entry for the same
+    /// transaction so a repeated execution is never listed twice.
+    pub fn record(&mut self, record: PurchaseRecord) -> Result<()> {
+        if let Some(existing) = self
+            .records
+            .iter_mut()
+            .find(|r| r.transaction == record.transaction)
+        {
+            *existing = record;
+            return Ok(());
+        }
+        require!(!self.is_full(), GenomeError::BuyerHistoryFull);
+        self.records.push(record);
+        Ok(())
+    }
+}
+
+/// One page of the grants issued for a genome, appended by the program
+/// every time an `AccessGrant` is issued so owners have an audit trail no
+/// one else can edit. Pages are numbered from 0 and written in order; the
+/// genome's `access_log_page` moves on as each fills. Bundle licenses are
+/// logged on the listing's lead genome.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct AccessLog {
+    pub genome: Pubkey,
+    pub page: u32,
+    #[max_len(32)]
+    pub records: Vec<AccessRecord>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub struct AccessRecord {
+    pub buyer: Pubkey,
+    pub timestamp: i64,
+    pub access_level: u8,
+}
+
+impl AccessLog {
+    /// Initializes a freshly created page; a no-op for an existing one.
+    pub fn open(&mut self, genome: Pubkey, page: u32, bump: u8) {
+        if self.genome != Pubkey::default() {
+            return;
+        }
+        self.genome = genome;
+        self.page = page;
+        self.bump = bump;
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.records.len() >= MAX_ACCESS_LOG_LEN
+    }
+
+    pub fn record(&mut self, record: AccessRecord) -> Result<()> {
+        require!(!self.is_full(), GenomeError::AccessLogFull);
+        self.records.push(record);
+        Ok(())
+    }
+}
+
+/// When a buyer was last refunded by a dispute against a seller, one PDA per
+/// (buyer, seller) pair. Written by `resolve_dispute` and read back when the
+/// buyer next purchases from that seller.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct RefundRecord {
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub last_refund_at: i64,
+    pub bump: u8,
+}
+
+impl RefundRecord {
+    pub fn record_refund(&mut self, buyer: Pubkey, seller: Pubkey, now: i64, bump: u8) {
+        self.buyer = buyer;
+        self.seller = seller;
+        self.last_refund_at = now;
+        self.bump = bump;
+    }
+
+    pub fn assert_cooled_down(&self, cooldown: i64, now: i64) -> Result<()> {
+        require!(
+            cooldown == 0 || now >= self.last_refund_at.saturating_add(cooldown),
+            GenomeError::RefundCooldown
+        );
+        Ok(())
+    }
+}
+
+/// One page of the open listings in a category, for browsing. Pages are PDAs
+/// numbered from 0 and chained like `UserIndex` pages: a new page is opened
+/// only once the one before it is full.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct CategoryIndex {
+    pub category: GenomeCategory,
+    pub page: u32,
+    #[max_len(32)]
+    pub listings: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl CategoryIndex {
+    pub fn open(
+        &mut self,
+        category: GenomeCategory,
+        page: u32,
+        bump: u8,
+        previous: Option<&CategoryIndex>,
+    ) -> Result<()> {
+        if page > 0 {
+            let previous = previous.ok_or(GenomeError::InvalidIndexPage)?;
+            require!(
+                previous.page + 1 == page && previous.is_full(),
+                GenomeError::InvalidIndexPage
+            );
+        }
+        self.category = category;
+        self.page = page;
+        self.bump = bump;
+        Ok(())
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.listings.len() >= MAX_CATEGORY_INDEX_LEN
+    }
+
+    pub fn push(&mut self, listing: Pubkey) -> Result<()> {
+        require!(!self.is_full(), GenomeError::CategoryIndexFull);
+        self.listings.push(listing);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, listing: &Pubkey) -> Result<()> {
+        let position = self
+            .listings
+            .iter()
+            .position(|l| l == listing)
+            .ok_or(GenomeError::CategoryIndexMismatch)?;
+        self.listings.remove(position);
+        Ok(())
+    }
+}
+
+/// One page of the genomes registered by an owner. Pages are PDAs numbered
+/// from 0 and each new page is opened only once the one before it exists, so
+/// clients list a user's genomes by fetching pages in order until one is
+/// missing.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct UserIndex {
+    pub owner: Pubkey,
+    pub page: u32,
+    #[max_len(32)]
+    pub genomes: Vec<Pubkey>,
+    pub bump: u8,
+    /// Live genomes registered by `owner`; only maintained on page 0.
+    pub genome_count: u32,
+}
+
+impl UserIndex {
+    /// Initializes a freshly created page; a no-op for an existing one.
+    /// Opening a later page requires the previous page to be full.
+    pub fn open(
+        &mut self,
+        owner: Pubkey,
+        page: u32,
+        bump: u8,
+        previous: Option<&UserIndex>,
+    ) -> Result<()> {
+        if self.owner != Pubkey::default() {
+            return Ok(());
+        }
+        if page > 0 {
+            let previous = previous.ok_or(GenomeError::InvalidIndexPage)?;
+            require!(
+                previous.page + 1 == page && previous.is_full(),
+                GenomeError::InvalidIndexPage
+            );
+        }
+        self.owner = owner;
+        self.page = page;
+        self.bump = bump;
+        Ok(())
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.genomes.len() >= MAX_USER_INDEX_LEN
+    }
+
+    pub fn push(&mut self, genome: Pubkey) -> Result<()> {
+        require!(!self.is_full(), GenomeError::UserIndexFull);
+        self.genomes.push(genome);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, genome: &Pubkey) -> Result<()> {
+        let position = self
+            .genomes
+            .iter()
+            .position(|g| g == genome)
+            .ok_or(GenomeError::GenomeNotIndexed)?;
+        self.genomes.remove(position);
+        Ok(())
+    }
+
+    /// Counts `added` new genomes against a `max_genomes` quota, where zero
+    /// means unlimited.
+    pub fn record_genomes(&mut self, added: u32, max_genomes: u32) -> Result<()> {
+        let genome_count = self
+            .genome_count
+            .checked_add(added)
+            .ok_or(GenomeError::MathOverflow)?;
+        require!(
+            max_genomes == 0 || genome_count <= max_genomes,
+            GenomeError::GenomeLimitReached
+        );
+        self.genome_count = genome_count;
+        Ok(())
+    }
+
+    pub fn release_genome(&mut self) {
+        self.genome_count = self.genome_count.saturating_sub(1);
+    }
+}
+
+/// M-of-N owners governing an institutional genome. Holds at most one
+/// pending action; approving a different action replaces it.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct MultisigOwner {
+    pub genome: Pubkey,
+    #[max_len(10)]
+    pub owners: Vec<Pubkey>,
+    pub threshold: u8,
+    pub pending_action: Option<MultisigAction>,
+    #[max_len(10)]
+    pub approvals: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl MultisigOwner {
+    pub fn new(genome: Pubkey, owners: Vec<Pubkey>, threshold: u8, bump: u8) -> Result<Self> {
+        require!(
+            !owners.is_empty() && owners.len() <= MAX_MULTISIG_OWNERS,
+            GenomeError::InvalidMultisig
+        );
+        require!(
+            threshold > 0 && usize::from(threshold) <= owners.len(),
+            GenomeError::InvalidMultisig
+        );
+        for (i, owner) in owners.iter().enumerate() {
+            require!(!owners[..i].contains(owner), GenomeError::InvalidMultisig);
+        }
+
+        Ok(Self {
+            genome,
+            owners,
+            threshold,
+            bump,
+            ..Default::default()
+        })
+    }
+
+    pub fn assert_member(&self, authority: Pubkey) -> Result<()> {
+        require!(self.owners.contains(&authority), GenomeError::Unauthorized);
+        Ok(())
+    }
+
+    pub fn approve(&mut self, approver: Pubkey, action: MultisigAction) -> Result<()> {
+        self.assert_member(approver)?;
+        if self.pending_action.as_ref() != Some(&action) {
+            self.pending_action = Some(action);
+            self.approvals.clear();
+        }
+        if !self.approvals.contains(&approver) {
+            self.approvals.push(approver);
+        }
+        Ok(())
+    }
+
+    /// Clears the pending action if it matches `action` and has enough
+    /// approvals, so each approval round authorizes exactly one call.
+    pub fn consume(&mut self, action: &MultisigAction) -> Result<()> {
+        require!(
+            self.pending_action.as_ref() == Some(action)
+                && self.approvals.len() >= usize::from(self.threshold),
+            GenomeError::MultisigThresholdNotMet
+        );
+        self.pending_action = None;
+        self.approvals.clear();
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum MultisigAction {
+    CreateTransaction { price: u64, access_duration: i64 },
+    TransferOwnership { new_owner: Pubkey },
+    SetDelegate { delegate: Option<Pubkey> },
+    SetPayoutSplit { shares_hash: [u8; 32] },
+}
+
+/// Co-owners' shares of the seller proceeds of one genome.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct PayoutSplit {
+    pub genome: Pubkey,
+    #[max_len(8)]
+    pub shares: Vec<PayoutShare>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, InitSpace)]
+pub struct PayoutShare {
+    pub recipient: Pubkey,
+    pub share_bps: u16,
+}
+
+/// `bps` basis points of `amount`, rounded down. The product is taken in
+/// `u128` so prices near `u64::MAX` cannot wrap; a result that does not fit
+/// back into `u64` fails with `MathOverflow`.
+pub fn apply_bps(amount: u64, bps: u16) -> Result<u64> {
+    u128::from(amount)
+        .checked_mul(u128::from(bps))
+        .and_then(|scaled| scaled.checked_div(u128::from(BPS_DENOMINATOR)))
+        .and_then(|part| u64::try_from(part).ok())
+        .ok_or_else(|| error!(GenomeError::MathOverflow))
+}
+
+/// Divides `amount` by `shares`, rounding each share down; the dust goes to
+/// the first recipient so the parts always add up to `amount`.
+pub fn split_by_shares(shares: &[PayoutShare], amount: u64) -> Result<Vec<u64>> {
+    let mut parts = shares
+        .iter()
+        .map(|share| apply_bps(amount, share.share_bps))
+        .collect::<Result<Vec<u64>>>()?;
+    let allotted = parts
+        .iter()
+        .try_fold(0u64, |total, part| total.checked_add(*part))
+        .ok_or(GenomeError::MathOverflow)?;
+    if let Some(first) = parts.first_mut() {
+        *first = amount
+            .checked_sub(allotted)
+            .and_then(|dust| first.checked_add(dust))
+            .ok_or(GenomeError::MathOverflow)?;
+    }
+    Ok(parts)
+}
+
+/// Escrowed bid placed directly against a genome, outside any listing.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct Offer {
+    pub genome: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    /// Length of the access grant issued if the offer is accepted.
+    pub access_duration: i64,
+    /// SPL mint the offer is paid in; `None` means native SOL.
+    pub payment_mint: Option<Pubkey>,
+    pub escrow_bump: u8,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub bump: u8,
+    /// `USE_*` code the buyer declared, carried onto the grant.
+    pub declared_use: u8,
+    /// `ACCESS_*` level the buyer is offering for, likewise.
+    pub access_level: u8,
+}
+
+impl Offer {
+    pub fn is_expired(&self, now: i64) -> bool {
+        now > self.expires_at
+    }
+
+    pub fn assert_open(&self, now: i64) -> Result<()> {
+        require!(!self.is_expired(now), GenomeError::OfferExpired);
+        Ok(())
+    }
+
+    /// Checks the offer at `key` competes with the `accepted` offer on
+    /// `genome`, so accepting that one may refund it.
+    pub fn assert_rejectable(&self, key: Pubkey, genome: Pubkey, accepted: Pubkey) -> Result<()> {
+        require_keys_eq!(self.genome, genome, GenomeError::GenomeMismatch);
+        require_keys_neq!(key, accepted, GenomeError::InvalidBatchAccounts);
+        Ok(())
+    }
+}
+
+/// Registration input for one genome.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct GenomeEntry {
+    pub storage_id: String,
+    pub metadata: String,
+    pub metadata_schema_version: u16,
+    pub content_hash: [u8; 32],
+    pub royalty_bps: u16,
+    pub category: GenomeCategory,
+    pub tags: Vec<String>,
+    pub jurisdiction: Option<[u8; 2]>,
+    pub file_size_bytes: u64,
+    pub format: u8,
+    pub reference_build: u8,
+    pub preview_uri: String,
+    pub preview_hash: [u8; 32],
+    pub permitted_use: u8,
+    pub provenance: Vec<Accession>,
+}
+
+/// A record of the genome in a public database such as NCBI or EBI.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub struct Accession {
+    /// `ACCESSION_*` code of the database.
+    pub source: u8,
+    /// The database's identifier, e.g. `SRR1234567`.
+    #[max_len(32)]
+    pub id: String,
+}
+
+/// Descriptive genome fields to change in `update_genome`; `None` keeps
+/// the current value.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct GenomePatch {
+    pub metadata: Option<String>,
+    pub metadata_schema_version: Option<u16>,
+    pub category: Option<GenomeCategory>,
+    pub tags: Option<Vec<String>>,
+    pub preview_uri: Option<String>,
+    pub preview_hash: Option<[u8; 32]>,
+    pub file_size_bytes: Option<u64>,
+    pub format: Option<u8>,
+    pub reference_build: Option<u8>,
+    pub encrypted_metadata: Option<Vec<u8>>,
+}
+
+/// Seller-chosen parameters for a new listing.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct ListingTerms {
+    /// Fixed price, or the starting bid for auctions.
+    pub price: u64,
+    pub access_duration: i64,
+    pub listing_expires_at: i64,
+    /// Makes the listing an English auction with this minimum raise.
+    pub min_bid_increment: Option<u64>,
+    pub allowed_buyer: Option<Pubkey>,
+    pub token_gate: Option<TokenGate>,
+    /// 1 for an exclusive sale; auctions must be exclusive.
+    pub max_licenses: u32,
+    /// Zero for no per-buyer cap.
+    pub max_per_buyer: u32,
+    /// Auctions only; zero for no reserve.
+    pub reserve_price: u64,
+    /// Makes an auction sealed-bid: bids are committed until
+    /// `listing_expires_at`, then revealed for this many seconds.
+    pub reveal_period: Option<i64>,
+    /// Hash of the license agreement text.
+    pub terms_hash: [u8; 32],
+    /// Empty allows buyers from any jurisdiction.
+    pub allowed_jurisdictions: Vec<[u8; 2]>,
+    /// Fixed-price listings only; `price` is then the deposit buyers make.
+    pub usd_peg: Option<UsdPeg>,
+    /// Sells the listing by quantity; `price` is then the deposit buyers
+    /// make. Not for auctions or USD-pegged listings.
+    pub unit_pricing: Option<UnitPricing>,
+    /// `ACCESS_*` level the license confers.
+    pub access_level: u8,
+    /// Seller's notes shown with the listing.
+    pub listing_notes: String,
+    /// Zero for no reputation gate.
+    pub min_buyer_reputation: u16,
+    /// Fixed-price listings only.
+    pub auto_relist: bool,
+    /// Zero for no offer floor.
+    pub min_acceptable_price: u64,
+}
+
+impl ListingTerms {
+    pub fn validate(&self, now: i64) -> Result<()> {
+        require!(self.price > 0, GenomeError::InvalidPrice);
+        // Grants expire `access_duration` after purchase, so the sum has to
+        // fit even for a purchase made just before the listing expires.
+        require!(
+            self.access_duration > 0
+                && self
+                    .listing_expires_at
+                    .checked_add(self.access_duration)
+                    .is_some(),
+            GenomeError::InvalidDuration
+        );
+        require!(self.listing_expires_at > now, GenomeError::InvalidDuration);
+        require!(
+            self.max_licenses >= 1 && (self.max_licenses == 1 || self.min_bid_increment.is_none()),
+            GenomeError::InvalidLicenseCount
+        );
+        require!(
+            self.max_per_buyer <= self.max_licenses,
+            GenomeError::InvalidLicenseCount
+        );
+        require!(
+            self.allowed_jurisdictions.len() <= MAX_JURISDICTIONS,
+            GenomeError::TooManyJurisdictions
+        );
+        for jurisdiction in &self.allowed_jurisdictions {
+            validate_jurisdiction(jurisdiction)?;
+        }
+        if let Some(peg) = &self.usd_peg {
+            require!(
+                peg.price_in_usd > 0 && self.min_bid_increment.is_none(),
+                GenomeError::InvalidPrice
+            );
+        }
+        if let Some(reveal_period) = self.reveal_period {
+            require!(
+                reveal_period > 0 && self.min_bid_increment.is_some(),
+                GenomeError::InvalidDuration
+            );
+        }
+        if let Some(units) = &self.unit_pricing {
+            require!(
+                units.unit_price > 0
+                    && units.units_available > 0
+                    && self.min_bid_increment.is_none()
+                    && self.usd_peg.is_none(),
+                GenomeError::InvalidPrice
+            );
+        }
+        validate_access_level(self.access_level)?;
+        validate_len(&self.listing_notes, MAX_LISTING_NOTES_LEN)?;
+        require!(
+            self.min_buyer_reputation <= MAX_REPUTATION,
+            GenomeError::InvalidRating
+        );
+        require!(
+            !self.auto_relist || self.min_bid_increment.is_none(),
+            GenomeError::AuctionListing
+        );
+        Ok(())
+    }
+}
+
+/// Prices a listing in USD, converted at execution with a Pyth feed.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct UsdPeg {
+    pub price_in_usd: u64,
+    pub price_feed: Pubkey,
+}
+
+/// Sells a listing by quantity, e.g. by sample count.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct UnitPricing {
+    pub unit_price: u64,
+    pub units_available: u64,
+}
+
+/// Restricts a listing to holders of `amount` or more of `mint`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct TokenGate {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Default, InitSpace)]
+pub enum GenomeCategory {
+    #[default]
+    Human,
+    Plant,
+    Microbial,
+    Animal,
+    Other,
+}
+
+impl GenomeCategory {
+    /// Seed of the category's `CategoryIndex` pages.
+    pub fn seed(&self) -> [u8; 1] {
+        [*self as u8]
+    }
+}
+
+/// Licenses one buyer has taken from a listing that caps them per buyer.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct BuyerLicenses {
+    pub transaction: Pubkey,
+    pub buyer: Pubkey,
+    pub count: u32,
+    pub bump: u8,
+}
+
+impl BuyerLicenses {
+    pub fn record(&mut self, max_per_buyer: u32) -> Result<()> {
+        require!(self.count < max_per_buyer, GenomeError::BuyerLimitReached);
+        self.count += 1;
+        Ok(())
+    }
+}
+
+/// A bidder's escrowed total on one auction.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct Bid {
+    pub transaction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+    /// Sealed-bid auctions: `bid_commitment` of the bid until it is revealed.
+    pub commitment: Option<[u8; 32]>,
+}
+
+/// Hash a sealed bid commits to; `nonce` is a secret chosen by the bidder
+/// so the amount cannot be guessed from the commitment.
+pub fn bid_commitment(amount: u64, nonce: &[u8; 32]) -> [u8; 32] {
+    hashv(&[&amount.to_le_bytes(), nonce]).to_bytes()
+}
+
+impl Bid {
+    /// Raises the bid to `amount`, returning how much more must be escrowed.
+    pub fn raise_to(&mut self, amount: u64) -> Result<u64> {
+        let top_up = amount
+            .checked_sub(self.amount)
+            .ok_or(GenomeError::BidTooLow)?;
+        self.amount = amount;
+        Ok(top_up)
+    }
+
+    /// Opens a sealed bid, checking it against the stored commitment.
+    pub fn reveal(&mut self, amount: u64, nonce: &[u8; 32]) -> Result<()> {
+        let commitment = self.commitment.ok_or(GenomeError::NoBid)?;
+        require!(
+            bid_commitment(amount, nonce) == commitment,
+            GenomeError::CommitmentMismatch
+        );
+        self.commitment = None;
+        self.amount = amount;
+        Ok(())
+    }
+
+    /// Unrevealed sealed bids hold no funds and may always be closed.
+    pub fn assert_withdrawable(&self, transaction: &Transaction) -> Result<()> {
+        require!(
+            self.amount > 0 || self.commitment.is_some(),
+            GenomeError::NoBid
+        );
+        require!(
+            transaction.depositor != Some(self.bidder),
+            GenomeError::WinningBidder
+        );
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Default, InitSpace)]
+pub struct Auction {
+    pub min_increment: u64,
+    pub bid_count: u32,
+    /// Lowest top bid that wins the auction; zero for no reserve.
+    pub reserve_price: u64,
+    /// Sealed-bid auctions only: committed bids are revealed from
+    /// `listing_expires_at` until this time.
+    pub reveal_ends_at: Option<i64>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Default, InitSpace)]
+pub enum KeyStatus {
+    #[default]
+    AwaitingKey,
+    KeyDelivered,
+    /// The buyer confirmed receiving usable data.
+    Delivered,
+}
+
+/// One entry of a transaction's `status_history`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub struct StatusChange {
+    pub status: TransactionStatus,
+    pub timestamp: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Default, InitSpace)]
+pub enum TransactionStatus {
+    #[default]
+    Created,
+    Executed,
+    Cancelled,
+    Disputed,
+    Refunded,
+    Expired,
+    /// Paid and access granted, awaiting the buyer's `confirm_receipt`.
+    Locked,
+    /// Handed over to a new program version by `migrate_to`.
+    Migrated,
+}
+
+#[event]
+pub struct PauseToggled {
+    pub admin: Pubkey,
+    pub paused: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RevocationGracePeriodUpdated {
+    pub admin: Pubkey,
+    pub revocation_grace_period: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HeartbeatTtlUpdated {
+    pub admin: Pubkey,
+    pub heartbeat_ttl: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConversionFeeUpdated {
+    pub admin: Pubkey,
+    pub conversion_fee_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SlaSlashUpdated {
+    pub admin: Pubkey,
+    pub sla_slash_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RefundCooldownUpdated {
+    pub admin: Pubkey,
+    pub refund_cooldown: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReputationHalfLifeUpdated {
+    pub admin: Pubkey,
+    pub reputation_half_life: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UpgradeWindowOpened {
+    pub admin: Pubkey,
+    pub new_program: Pubkey,
+    pub ends_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SlaBreachClaimed {
+    pub transaction: Pubkey,
+    pub genome: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    /// When the lapse being compensated began.
+    pub breached_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HeartbeatRecorded {
+    pub genome: Pubkey,
+    pub owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MinClientVersionUpdated {
+    pub admin: Pubkey,
+    pub min_client_version: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ResaleLockUpdated {
+    pub admin: Pubkey,
+    pub resale_lock: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralRateUpdated {
+    pub admin: Pubkey,
+    pub referral_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConversionRequested {
+    pub access_grant: Pubkey,
+    pub genome: Pubkey,
+    pub buyer: Pubkey,
+    pub target_format: u8,
+    pub fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConversionFulfilled {
+    pub access_grant: Pubkey,
+    pub genome: Pubkey,
+    pub owner: Pubkey,
+    pub target_format: u8,
+    pub result_hash: [u8; 32],
+    pub fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EpochVolumeCapUpdated {
+    pub admin: Pubkey,
+    pub epoch_volume_cap: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct YieldRoutingUpdated {
+    pub admin: Pubkey,
+    pub yield_enabled: bool,
+    pub yield_program: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowYieldDeposited {
+    pub transaction: Pubkey,
+    pub yield_program: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowYieldRedeemed {
+    pub transaction: Pubkey,
+    pub principal: u64,
+    pub yield_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CollateralUpdated {
+    pub admin: Pubkey,
+    pub collateral_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ListingCooldownUpdated {
+    pub admin: Pubkey,
+    pub listing_cooldown: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ListingDurationBoundsUpdated {
+    pub admin: Pubkey,
+    pub min_duration: i64,
+    pub max_duration: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GenomeLimitUpdated {
+    pub admin: Pubkey,
+    pub max_genomes_per_user: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CancellationFeeUpdated {
+    pub admin: Pubkey,
+    pub cancellation_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub updated_by: Pubkey,
+    pub admin: Pubkey,
+    pub arbitrator: Pubkey,
+    pub treasury: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GenomeInitialized {
+    pub genome: Pubkey,
+    pub owner: Pubkey,
+    pub storage_id: String,
+    pub metadata: String,
+    pub metadata_schema_version: u16,
+    pub content_hash: [u8; 32],
+    pub royalty_bps: u16,
+    pub category: GenomeCategory,
+    pub tags: Vec<String>,
+    pub jurisdiction: Option<[u8; 2]>,
+    pub file_size_bytes: u64,
+    pub format: u8,
+    pub reference_build: u8,
+    pub preview_uri: String,
+    pub preview_hash: [u8; 32],
+    pub permitted_use: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GenomeVersioned {
+    pub genome: Pubkey,
+    pub previous_version: Pubkey,
+    pub owner: Pubkey,
+    pub version: u32,
+    pub content_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GenomeDeleted {
+    pub genome: Pubkey,
+    pub owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GenomeMigrated {
+    pub genome: Pubkey,
+    pub owner: Pubkey,
+    pub old_len: u32,
+    pub new_len: u32,
+    pub refunded: u64,
+    pub rent_paid: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GenomeClosed {
+    pub genome: Pubkey,
+    pub owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MetadataSealed {
+    pub genome: Pubkey,
+    pub owner: Pubkey,
+    pub embargo_until: i64,
+    pub metadata_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmbargoLifted {
+    pub genome: Pubkey,
+    pub authority: Pubkey,
+    pub metadata: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MetadataUpdated {
+    pub genome: Pubkey,
+    pub owner: Pubkey,
+    pub metadata: String,
+    pub metadata_schema_version: u16,
+    pub category: GenomeCategory,
+    pub tags: Vec<String>,
+    pub preview_uri: String,
+    pub preview_hash: [u8; 32],
+    pub file_size_bytes: u64,
+    pub format: u8,
+    pub reference_build: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PayoutSplitUpdated {
+    pub genome: Pubkey,
+    pub owner: Pubkey,
+    pub shares: Vec<PayoutShare>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DelegateUpdated {
+    pub genome: Pubkey,
+    pub owner: Pubkey,
+    pub delegate: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProvenanceAdded {
+    pub genome: Pubkey,
+    pub owner: Pubkey,
+    pub accessions: Vec<Accession>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConsentWithdrawn {
+    pub genome: Pubkey,
+    pub owner: Pubkey,
+    pub grants_revoked: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RecoveryAuthorityUpdated {
+    pub genome: Pubkey,
+    pub owner: Pubkey,
+    pub recovery_authority: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RecoveryInitiated {
+    pub genome: Pubkey,
+    pub owner: Pubkey,
+    pub recovery_authority: Pubkey,
+    pub new_owner: Pubkey,
+    pub unlocks_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RecoveryCancelled {
+    pub genome: Pubkey,
+    pub owner: Pubkey,
+    pub new_owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OwnershipTransferred {
+    pub genome: Pubkey,
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MultisigCreated {
+    pub multisig: Pubkey,
+    pub genome: Pubkey,
+    pub owners: Vec<Pubkey>,
+    pub threshold: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ActionApproved {
+    pub multisig: Pubkey,
+    pub genome: Pubkey,
+    pub approver: Pubkey,
+    pub action: MultisigAction,
+    pub approvals: u8,
+    pub threshold: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TransactionCreated {
+    pub transaction: Pubkey,
+    pub genome: Pubkey,
+    pub seller: Pubkey,
+    pub payment_mint: Option<Pubkey>,
+    pub price: u64,
+    pub access_duration: i64,
+    pub listing_expires_at: i64,
+    pub terms_hash: [u8; 32],
+    pub genome_ids: Vec<String>,
+    pub collateral_amount: u64,
+    pub listing_notes: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Relisted {
+    pub transaction: Pubkey,
+    pub previous_listing: Pubkey,
+    pub original_listing: Pubkey,
+    pub genome: Pubkey,
+    pub seller: Pubkey,
+    pub relist_count: u32,
+    pub old_price: u64,
+    pub new_price: u64,
+    pub old_access_duration: i64,
+    pub new_access_duration: i64,
+    pub listing_expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ListingNotesUpdated {
+    pub transaction: Pubkey,
+    pub seller: Pubkey,
+    pub listing_notes: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RelistedByKeeper {
+    pub transaction: Pubkey,
+    pub seller: Pubkey,
+    pub keeper: Pubkey,
+    pub auto_relists: u32,
+    pub listing_expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ListingExtended {
+    pub transaction: Pubkey,
+    pub seller: Pubkey,
+    pub old_expires_at: i64,
+    pub new_expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PriceUpdated {
+    pub transaction: Pubkey,
+    pub seller: Pubkey,
+    pub old_price: u64,
+    pub new_price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowDeposited {
+    pub transaction: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BidPlaced {
+    pub transaction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub previous_bidder: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BidCommitted {
+    pub transaction: Pubkey,
+    pub bidder: Pubkey,
+    pub commitment: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BidRevealed {
+    pub transaction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub previous_bidder: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BidWithdrawn {
+    pub transaction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TransactionExecuted {
+    pub transaction: Pubkey,
+    pub genome: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub fee: u64,
+    pub royalty: u64,
+    pub licenses_issued: u32,
+    pub buyer_jurisdiction: [u8; 2],
+    pub declared_use: u8,
+    pub access_level: u8,
+    pub referrer: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BundleAccessGranted {
+    pub transaction: Pubkey,
+    pub buyer: Pubkey,
+    pub genomes: Vec<Pubkey>,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisputeOpened {
+    pub transaction: Pubkey,
+    pub buyer: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisputeResolved {
+    pub transaction: Pubkey,
+    pub arbitrator: Pubkey,
+    pub refund_buyer: bool,
+    pub amount: u64,
+    /// Seller collateral paid to whichever side the ruling favoured.
+    pub collateral: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SettlementClaimed {
+    pub transaction: Pubkey,
+    pub seller: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RatingSubmitted {
+    pub transaction: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub stars: u8,
+    pub rating_sum: u64,
+    pub rating_count: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TransactionCancelled {
+    pub transaction: Pubkey,
+    pub genome: Pubkey,
+    pub seller: Pubkey,
+    pub refunded_to: Option<Pubkey>,
+    pub refund_amount: u64,
+    pub cancellation_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TransactionsCancelled {
+    pub seller: Pubkey,
+    pub cancelled: u32,
+    pub skipped: u32,
+    pub cancellation_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccessRevoked {
+    pub access_grant: Pubkey,
+    pub genome: Pubkey,
+    pub buyer: Pubkey,
+    pub revoked_by: Pubkey,
+    /// When the grant stops working, after any grace period.
+    pub effective_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AllowedMintUpdated {
+    pub admin: Pubkey,
+    pub mint: Pubkey,
+    pub allowed: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionPoolConfigured {
+    pub admin: Pubkey,
+    pub pass_price: u64,
+    pub pass_duration: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionPoolMembershipUpdated {
+    pub genome: Pubkey,
+    pub owner: Pubkey,
+    pub enabled: bool,
+    pub paid_out: u64,
+    pub participants: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionPassPurchased {
+    pub pass: Pubkey,
+    pub holder: Pubkey,
+    pub price: u64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolRevenueClaimed {
+    pub genome: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CohortAccessGranted {
+    pub genome: Pubkey,
+    pub owner: Pubkey,
+    pub buyers: Vec<Pubkey>,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GrantQuotaAssigned {
+    pub admin: Pubkey,
+    pub holder: Pubkey,
+    pub grants: u32,
+    pub access_duration: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NonprofitAccessUpdated {
+    pub genome: Pubkey,
+    pub owner: Pubkey,
+    pub enabled: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FreeAccessClaimed {
+    pub genome: Pubkey,
+    pub holder: Pubkey,
+    pub access_grant: Pubkey,
+    pub expires_at: i64,
+    pub remaining: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BlacklistUpdated {
+    pub admin: Pubkey,
+    pub seller: Pubkey,
+    pub blacklisted: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WhitelistUpdated {
+    pub whitelist: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub added: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OfferMade {
+    pub offer: Pubkey,
+    pub genome: Pubkey,
+    pub buyer: Pubkey,
+    pub payment_mint: Option<Pubkey>,
+    pub amount: u64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OfferAccepted {
+    pub offer: Pubkey,
+    pub genome: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub royalty: u64,
+    /// Competing offers refunded in the same call.
+    pub rejected: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OfferRejected {
+    pub offer: Pubkey,
+    pub genome: Pubkey,
+    pub buyer: Pubkey,
+    pub refund_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OfferExpired {
+    pub offer: Pubkey,
+    pub genome: Pubkey,
+    pub buyer: Pubkey,
+    pub refund_amount: u64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct KeyDelivered {
+    pub access_grant: Pubkey,
+    pub genome: Pubkey,
+    pub buyer: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DeliveryConfirmed {
+    pub access_grant: Pubkey,
+    pub genome: Pubkey,
+    pub buyer: Pubkey,
+    pub data_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccessRenewed {
+    pub access_grant: Pubkey,
+    pub transaction: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AutoRenewUpdated {
+    pub access_grant: Pubkey,
+    pub buyer: Pubkey,
+    pub auto_renew: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RenewalFailed {
+    pub access_grant: Pubkey,
+    pub transaction: Pubkey,
+    pub buyer: Pubkey,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EarningsWithdrawn {
+    pub earnings: Pubkey,
+    pub seller: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TipSent {
+    pub tipper: Pubkey,
+    pub seller: Pubkey,
+    /// `None` for SOL tips.
+    pub mint: Option<Pubkey>,
+    pub amount: u64,
+    pub tips_received: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LicenseRecalled {
+    pub access_grant: Pubkey,
+    pub transaction: Pubkey,
+    pub genome: Pubkey,
+    pub owner: Pubkey,
+    pub buyer: Pubkey,
+    pub refund_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReceiptConfirmed {
+    pub transaction: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LicenseCancelled {
+    pub access_grant: Pubkey,
+    pub transaction: Pubkey,
+    pub buyer: Pubkey,
+    pub refund_amount: u64,
+    pub timestamp: i64,
+}
+
+/// How the proceeds of a sale are divided. Every percentage is rounded
+/// down and the seller takes the dust, so the parts always add up to exactly
+/// the price: the program never pays out more than it holds, and leaves
+/// nothing behind in escrow.
+pub struct SaleSplit {
+    pub fee: u64,
+    pub royalty: u64,
+    pub seller_amount: u64,
+    /// Carved out of the fee for a referred sale.
+    pub referral: u64,
+    /// Charged on top of a USD-pegged price; paid to the treasury with
+    /// `fee`.
+    pub conversion_fee: u64,
+}
+
+impl SaleSplit {
+    pub fn new(price: u64, fee_bps: u16, royalty_bps: u16) -> Result<Self> {
+        require!(
+            u64::from(fee_bps) + u64::from(royalty_bps) <= BPS_DENOMINATOR,
+            GenomeError::InvalidFeeRate
+        );
+
+        let fee = apply_bps(price, fee_bps)?;
+        let royalty = apply_bps(price, royalty_bps)?;
+        let seller_amount = price
+            .checked_sub(fee)
+            .and_then(|rest| rest.checked_sub(royalty))
+            .ok_or(GenomeError::MathOverflow)?;
+        let split = Self {
+            fee,
+            royalty,
+            seller_amount,
+            referral: 0,
+            conversion_fee: 0,
+        };
+        split.assert_total(price)?;
+        Ok(split)
+    }
+
+    /// Everything the split pays out, referral and conversion fee included.
+    pub fn total(&self) -> Option<u64> {
+        self.fee
+            .checked_add(self.royalty)?
+            .checked_add(self.seller_amount)?
+            .checked_add(self.referral)?
+            .checked_add(self.conversion_fee)
+    }
+
+    fn assert_total(&self, price: u64) -> Result<()> {
+        require!(self.total() == Some(price), GenomeError::SplitMismatch);
+        Ok(())
+    }
+
+    /// Moves `referral_bps` of the fee to the referrer, if there is one.
+    /// The referral also rounds down, leaving its dust in the fee.
+    pub fn with_referral(mut self, referrer: Option<Pubkey>, referral_bps: u16) -> Result<Self> {
+        if referrer.is_some() {
+            let price = self.total().ok_or(GenomeError::MathOverflow)?;
+            self.referral = apply_bps(self.fee, referral_bps)?;
+            self.fee = self
+                .fee
+                .checked_sub(self.referral)
+                .ok_or(GenomeError::MathOverflow)?;
+            self.assert_total(price)?;
+        }
+        Ok(self)
+    }
+
+    /// A USD-pegged sale's conversion fee is set aside before the price is
+    /// split.
+    pub fn for_sale(config: &Config, genome: &Genome, transaction: &Transaction) -> Result<Self> {
+        Self::for_proceeds(config, genome, transaction, transaction.price)
+    }
+
+    /// Splits the part of a sale's price the seller keeps after a partial
+    /// refund. The conversion fee is taken out of it first.
+    pub fn for_proceeds(
+        config: &Config,
+        genome: &Genome,
+        transaction: &Transaction,
+        proceeds: u64,
+    ) -> Result<Self> {
+        let conversion_fee = transaction.conversion_fee.min(proceeds);
+        let mut split = Self::for_genome(
+            config,
+            genome,
+            transaction.seller,
+            proceeds - conversion_fee,
+        )?;
+        split.conversion_fee = conversion_fee;
+        Ok(split)
+    }
+
+    /// Royalties only apply on resales, not when the creator is selling.
+    pub fn for_genome(
+        config: &Config,
+        genome: &Genome,
+        seller: Pubkey,
+        price: u64,
+    ) -> Result<Self> {
+        let royalty_bps = if genome.original_creator == seller {
+            0
+        } else {
+            genome.royalty_bps
+        };
+        Self::new(price, config.fee_bps, royalty_bps)
+    }
+}
+
+#[event]
+pub struct MigratedToProgram {
+    pub genome: Pubkey,
+    pub transaction: Option<Pubkey>,
+    pub admin: Pubkey,
+    pub new_program: Pubkey,
+    pub escrow_moved: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowRecovered {
+    pub transaction: Pubkey,
+    pub admin: Pubkey,
+    pub to: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TransactionExpired {
+    pub transaction: Pubkey,
+    pub genome: Pubkey,
+    pub seller: Pubkey,
+    pub refunded_to: Option<Pubkey>,
+    pub refund_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TransactionClosed {
+    pub transaction: Pubkey,
+    pub genome: Pubkey,
+    pub seller: Pubkey,
+    pub status: TransactionStatus,
+    pub timestamp: i64,
+}
+
+/// Destinations for the proceeds of a sale. Token accounts are used for SPL
+/// listings and wallets for native SOL.
+pub struct SalePayees<'a, 'info> {
+    pub seller_token_account: Option<&'a Account<'info, TokenAccount>>,
+    pub seller: Option<AccountInfo<'info>>,
+    pub treasury_token_account: Option<&'a Account<'info, TokenAccount>>,
+    pub treasury: Option<AccountInfo<'info>>,
+    pub creator_token_account: Option<&'a Account<'info, TokenAccount>>,
+    pub creator: Option<AccountInfo<'info>>,
+    /// Replaces `seller` when the genome splits its proceeds.
+    pub seller_split: Option<SellerSplit<'a, 'info>>,
+    /// Required when the sale was referred.
+    pub referral: Option<ReferralPayee<'a, 'info>>,
+}
+
+/// Where a referred sale's referral cut goes, and the profile credited
+/// with it.
+pub struct ReferralPayee<'a, 'info> {
+    pub referrer: Option<Pubkey>,
+    pub profile: Option<&'a mut ReferralProfile>,
+    pub token_account: Option<&'a Account<'info, TokenAccount>>,
+    pub wallet: Option<AccountInfo<'info>>,
+}
+
+/// Recipients of a genome's payout split and, in the same order, their
+/// token accounts (SPL) or wallets (native SOL).
+pub struct SellerSplit<'a, 'info> {
+    pub shares: &'a [PayoutShare],
+    pub accounts: &'a [AccountInfo<'info>],
+}
+
+/// The seller's share recipients for a sale of `genome`, if it has a payout
+/// split; the split account must then be supplied.
+fn seller_split<'a, 'info>(
+    genome: &Genome,
+    payout_split: Option<&'a Account<'info, PayoutSplit>>,
+    accounts: &'a [AccountInfo<'info>],
+) -> Result<Option<SellerSplit<'a, 'info>>> {
+    if genome.payout_split.is_none() {
+        return Ok(None);
+    }
+    let payout_split = payout_split.ok_or(GenomeError::PayoutSplitMismatch)?;
+    Ok(Some(SellerSplit {
+        shares: &payout_split.shares,
+        accounts,
+    }))
+}
+
+fn validate_payout_shares(shares: &[PayoutShare]) -> Result<()> {
+    require!(
+        shares.len() <= MAX_PAYOUT_RECIPIENTS,
+        GenomeError::TooManyPayoutRecipients
+    );
+    let mut total = 0u64;
+    for (i, share) in shares.iter().enumerate() {
+        require!(
+            share.share_bps > 0 && !shares[..i].iter().any(|s| s.recipient == share.recipient),
+            GenomeError::InvalidPayoutSplit
+        );
+        total += u64::from(share.share_bps);
+    }
+    require!(total == BPS_DENOMINATOR, GenomeError::InvalidPayoutSplit);
+    Ok(())
+}
+
+/// Current cluster time. Reading the clock sysvar only fails when it is
+/// unavailable to the program, which is reported as such rather than as an
+/// opaque system error.
+fn unix_timestamp() -> Result<i64> {
+    Clock::get()
+        .map(|clock| clock.unix_timestamp)
+        .map_err(|_| error!(GenomeError::ClockUnavailable))
+}
+
+fn current_epoch() -> Result<u64> {
+    Clock::get()
+        .map(|clock| clock.epoch)
+        .map_err(|_| error!(GenomeError::ClockUnavailable))
+}
+
+/// Address of the listing `seller` opens for `genome` under `nonce`.
+pub fn transaction_address(genome: &Pubkey, seller: &Pubkey, nonce: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            TRANSACTION_SEED,
+            genome.as_ref(),
+            seller.as_ref(),
+            &nonce.to_le_bytes(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+/// Rejects strings longer than the `max_len` reserved for them in account
+/// space, so oversized input fails up front instead of during serialization.
+fn validate_len(value: &str, max_len: usize) -> Result<()> {
+    require!(value.len() <= max_len, GenomeError::StringTooLong);
+    Ok(())
+}
+
+fn validate_tags(tags: &[String]) -> Result<()> {
+    require!(tags.len() <= MAX_TAGS, GenomeError::TooManyTags);
+    for tag in tags {
+        validate_len(tag, MAX_TAG_LEN)?;
+    }
+    Ok(())
+}
+
+/// A bundle lists between two and `MAX_BUNDLE_SIZE` distinct genome ids.
+fn validate_bundle(genome_ids: &[String]) -> Result<()> {
+    require!(
+        genome_ids.len() >= 2 && genome_ids.len() <= MAX_BUNDLE_SIZE,
+        GenomeError::InvalidBundle
+    );
+    for (i, genome_id) in genome_ids.iter().enumerate() {
+        validate_len(genome_id, MAX_ID_LEN)?;
+        require!(
+            !genome_ids[..i].contains(genome_id),
+            GenomeError::InvalidBundle
+        );
+    }
+    Ok(())
+}
+
+/// Jurisdictions are ISO 3166-1 alpha-2 codes such as `*b"DE"`.
+fn validate_jurisdiction(code: &[u8; 2]) -> Result<()> {
+    require!(
+        code.iter().all(u8::is_ascii_uppercase),
+        GenomeError::InvalidJurisdiction
+    );
+    Ok(())
+}
+
+/// Pays a sale out of escrow: the treasury fee, the creator's royalty, the
+/// seller's proceeds (over their payout split, if any), the referral cut and
+/// `collateral` back to the seller. Every sale settles through here, so a
+/// payee added to `SalePayees` is paid on all of them.
+fn settle_sale<'info>(
+    escrow: &Escrow<'_, 'info>,
+    payees: SalePayees<'_, 'info>,
+    split: &SaleSplit,
+    collateral: u64,
+) -> Result<()> {
+    escrow.pay_sale(&payees, split)?;
+    if let Some(referral) = payees.referral {
+        pay_referral(escrow, split, referral)?;
+    }
+    escrow.pay(payees.seller_token_account, payees.seller, collateral)
+}
+
+/// Pays a settled sale's referral cut out of escrow and credits it to the
+/// referrer's profile.
+fn pay_referral<'info>(
+    escrow: &Escrow<'_, 'info>,
+    split: &SaleSplit,
+    referral: ReferralPayee<'_, 'info>,
+) -> Result<()> {
+    let Some(referrer) = referral.referrer else {
+        return Ok(());
+    };
+    if split.referral == 0 {
+        return Ok(());
+    }
+    escrow.pay_to(
+        referrer,
+        referral.token_account,
+        referral.wallet,
+        split.referral,
+    )?;
+    referral
+        .profile
+        .ok_or(GenomeError::ReferralProfileMissing)?
+        .record_referral(split.referral)
+}
+
+/// Calls `method` on the yield program with an Anchor-style discriminator,
+/// signing as the escrow vault, which is always the first account.
+fn invoke_yield_program<'info>(
+    yield_program: &AccountInfo<'info>,
+    method: &str,
+    amount: Option<u64>,
+    escrow_vault: &Account<'info, TokenAccount>,
+    protocol_accounts: &[AccountInfo<'info>],
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    let mut data = hash(format!("global:{method}").as_bytes()).to_bytes()[..8].to_vec();
+    if let Some(amount) = amount {
+        data.extend_from_slice(&amount.to_le_bytes());
+    }
+
+    let mut accounts = vec![AccountMeta::new(escrow_vault.key(), true)];
+    let mut infos = vec![escrow_vault.to_account_info()];
+    for account in protocol_accounts {
+        accounts.push(if account.is_writable {
+            AccountMeta::new(account.key(), account.is_signer)
+        } else {
+            AccountMeta::new_readonly(account.key(), account.is_signer)
+        });
+        infos.push(account.clone());
+    }
+    infos.push(yield_program.clone());
+
+    invoke_signed(
+        &Instruction {
+            program_id: yield_program.key(),
+            accounts,
+            data,
+        },
+        &infos,
+        &[signer_seeds],
+    )?;
+    Ok(())
+}
+
+/// Splits a redemption into principal and yield, refusing any redemption
+/// that falls short of the principal.
+pub fn yield_earned(principal: u64, redeemed: u64) -> Result<u64> {
+    redeemed
+        .checked_sub(principal)
+        .ok_or_else(|| error!(GenomeError::YieldShortfall))
+}
+
+fn validate_access_level(access_level: u8) -> Result<()> {
+    require!(
+        access_level <= ACCESS_REDISTRIBUTE,
+        GenomeError::InvalidAccessLevel
+    );
+    Ok(())
+}
+
+/// Reads a fresh price from a Pyth feed and converts `price_in_usd` into
+/// the payment token's base units.
+fn oracle_token_amount(
+    price_feed: &AccountInfo,
+    price_in_usd: u64,
+    decimals: u8,
+    now: i64,
+) -> Result<u64> {
+    let feed = pyth_sdk_solana::load_price_feed_from_account_info(price_feed)
+        .map_err(|_| error!(GenomeError::InvalidOracle))?;
+    let price = feed
+        .get_price_no_older_than(now, MAX_PRICE_AGE_SECS)
+        .ok_or(GenomeError::StaleOraclePrice)?;
+    usd_to_token_amount(price_in_usd, price.price, price.expo, decimals)
+}
+
+/// What the buyer pays for `converted` tokens of a USD-pegged price, and
+/// the conversion fee included in it. The fee rounds down.
+fn with_conversion_fee(converted: u64, conversion_fee_bps: u16) -> Result<(u64, u64)> {
+    let fee = apply_bps(converted, conversion_fee_bps)?;
+    let total = converted
+        .checked_add(fee)
+        .ok_or(GenomeError::MathOverflow)?;
+    Ok((total, fee))
+}
+
+/// `value` after `elapsed` seconds of exponential decay with the given
+/// half-life: each whole half-life halves it exactly, and what is left of
+/// the last one is interpolated linearly. A zero half-life disables decay.
+fn decay(value: u64, elapsed: i64, half_life: i64) -> u64 {
+    if half_life <= 0 || elapsed <= 0 {
+        return value;
+    }
+    let halvings = elapsed / half_life;
+    if halvings >= i64::from(u64::BITS) {
+        return 0;
+    }
+    let value = u128::from(value >> halvings);
+    let rest = (elapsed % half_life) as u128;
+    (value - value * rest / (2 * half_life as u128)) as u64
+}
+
+/// `price * 10^expo` is the USD value of one whole token. Rounds down.
+fn usd_to_token_amount(price_in_usd: u64, price: i64, expo: i32, decimals: u8) -> Result<u64> {
+    require!(price > 0, GenomeError::InvalidOracle);
+    let scale = |exponent: u32| {
+        10u128
+            .checked_pow(exponent)
+            .ok_or_else(|| error!(GenomeError::MathOverflow))
+    };
+
+    let mut numerator = u128::from(price_in_usd)
+        .checked_mul(scale(u32::from(decimals))?)
+        .ok_or(GenomeError::MathOverflow)?;
+    let mut denominator = (price as u128)
+        .checked_mul(scale(USD_DECIMALS)?)
+        .ok_or(GenomeError::MathOverflow)?;
+    if expo < 0 {
+        numerator = numerator
+            .checked_mul(scale(expo.unsigned_abs())?)
+            .ok_or(GenomeError::MathOverflow)?;
+    } else {
+        denominator = denominator
+            .checked_mul(scale(expo.unsigned_abs())?)
+            .ok_or(GenomeError::MathOverflow)?;
+    }
+    u64::try_from(numerator / denominator).map_err(|_| error!(GenomeError::MathOverflow))
+}
+
+/// Checks the further genomes of a bundle, passed as `remaining_accounts`
+/// in listing order, are live and controlled like the lead genome.
+fn assert_bundle_genomes(
+    lead: &Genome,
+    genome_ids: &[String],
+    accounts: &[AccountInfo],
+) -> Result<()> {
+    require!(
+        accounts.len() == genome_ids.len(),
+        GenomeError::InvalidBatchAccounts
+    );
+    for (genome_id, info) in genome_ids.iter().zip(accounts) {
+        let genome = Account::<Genome>::try_from(info)?;
+        require!(
+            *genome_id == genome.key().to_string(),
+            GenomeError::GenomeMismatch
+        );
+        genome.assert_live()?;
+        genome.assert_consent()?;
+        require_keys_eq!(genome.owner, lead.owner, GenomeError::BundleOwnerMismatch);
+        require!(
+            genome.multisig == lead.multisig,
+            GenomeError::MultisigMismatch
+        );
+        require!(
+            genome.permitted_use == lead.permitted_use,
+            GenomeError::InvalidBundle
+        );
+    }
+    Ok(())
+}
+
+/// Issues the buyer of a bundle a copy of the lead genome's grant for each
+/// further genome. `accounts` holds a (genome, access grant PDA) pair per
+/// genome after the first, in listing order. Returns the genomes granted.
+fn issue_bundle_grants<'info>(
+    transaction: &Transaction,
+    lead: &AccessGrant,
+    buyer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    accounts: &[AccountInfo<'info>],
+    program_id: &Pubkey,
+) -> Result<Vec<Pubkey>> {
+    let bundled = &transaction.genome_ids[1..];
+
+    let mut genomes = Vec::with_capacity(bundled.len());
+    for (genome_id, pair) in bundled.iter().zip(accounts.chunks(2)) {
+        let genome = Account::<Genome>::try_from(&pair[0])?;
+        let genome_key = genome.key();
+        require!(
+            *genome_id == genome_key.to_string(),
+            GenomeError::GenomeMismatch
+        );
+        genome.assert_live()?;
+        require_keys_eq!(
+            genome.owner,
+            transaction.seller,
+            GenomeError::BundleOwnerMismatch
+        );
+
+        let mut grant = lead.clone();
+        grant.genome = genome_key;
+        create_grant_account(grant, &pair[1], buyer, system_program, program_id)?;
+        genomes.push(genome_key);
+    }
+    Ok(genomes)
+}
+
+/// Revokes the grants `issue_bundle_grants` gave `buyer`, which `accounts`
+/// holds in listing order.
+fn revoke_bundle_grants<'info>(
+    transaction: &Account<'info, Transaction>,
+    buyer: Pubkey,
+    accounts: &[AccountInfo<'info>],
+    program_id: &Pubkey,
+) -> Result<()> {
+    let bundled = transaction.genome_ids.get(1..).unwrap_or_default();
+    require!(
+        accounts.len() >= bundled.len(),
+        GenomeError::InvalidBatchAccounts
+    );
+
+    for (genome_id, account) in bundled.iter().zip(accounts) {
+        require!(account.is_writable, GenomeError::InvalidBatchAccounts);
+        let mut grant = Account::<AccessGrant>::try_from(account)?;
+        require!(
+            *genome_id == grant.genome.to_string(),
+            GenomeError::GenomeMismatch
+        );
+        require_keys_eq!(
+            grant.transaction,
+            transaction.key(),
+            GenomeError::AccessGrantMismatch
+        );
+        require_keys_eq!(grant.buyer, buyer, GenomeError::AccessGrantMismatch);
+        grant.revoked = true;
+        grant.exit(program_id)?;
+    }
+    Ok(())
+}
+
+/// Creates `grant` at its PDA, which `account` must be, paid for by
+/// `payer`. A grant already there is overwritten if it may be re-issued.
+fn create_grant_account<'info>(
+    mut grant: AccessGrant,
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    program_id: &Pubkey,
+) -> Result<()> {
+    let space = 8 + AccessGrant::INIT_SPACE;
+    let (address, bump) = Pubkey::find_program_address(
+        &[ACCESS_SEED, grant.genome.as_ref(), grant.buyer.as_ref()],
+        program_id,
+    );
+    require_keys_eq!(account.key(), address, GenomeError::InvalidBatchAccounts);
+    if account.owner == program_id {
+        Account::<AccessGrant>::try_from(account)?.assert_reissuable(grant.granted_at)?;
+    } else {
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+                &[&[
+                    ACCESS_SEED,
+                    grant.genome.as_ref(),
+                    grant.buyer.as_ref(),
+                    &[bump],
+                ]],
+            ),
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            program_id,
+        )?;
+    }
+
+    grant.bump = bump;
+    grant.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])
+}
+
+/// Appends `records` to a genome's access log. `log` is the page the genome
+/// is writing to and `next` the one after it, needed only by batches that
+/// fill `log` part-way; the genome is left pointing at the first page with
+/// room.
+fn log_access(
+    genome: &mut Genome,
+    log: &mut AccessLog,
+    mut next: Option<&mut AccessLog>,
+    records: Vec<AccessRecord>,
+) -> Result<()> {
+    for record in records {
+        match next.as_deref_mut() {
+            Some(next) if log.is_full() => next.record(record)?,
+            _ => log.record(record)?,
+        }
+    }
+    let open_page = match next {
+        Some(next) if log.is_full() => next,
+        _ => log,
+    };
+    genome.access_log_page = if open_page.is_full() {
+        open_page.page + 1
+    } else {
+        open_page.page
+    };
+    Ok(())
+}
+
+/// Grants for a cohort of pre-approved `buyers` of `genome`, each lasting
+/// `duration` from `now`.
+fn cohort_grants(
+    genome: Pubkey,
+    buyers: &[Pubkey],
+    now: i64,
+    duration: i64,
+) -> Result<Vec<AccessGrant>> {
+    require!(
+        !buyers.is_empty() && buyers.len() <= MAX_BATCH_SIZE,
+        GenomeError::InvalidBatchSize
+    );
+    require!(duration > 0, GenomeError::InvalidDuration);
+    let expires_at = now.checked_add(duration).ok_or(GenomeError::MathOverflow)?;
+    for (i, buyer) in buyers.iter().enumerate() {
+        require!(!buyers[..i].contains(buyer), GenomeError::DuplicateBuyer);
+    }
+    Ok(buyers
+        .iter()
+        .map(|&buyer| AccessGrant {
+            genome,
+            transaction: genome,
+            buyer,
+            granted_at: now,
+            expires_at,
+            declared_use: USE_RESEARCH,
+            access_level: ACCESS_READ_ONLY,
+            ..Default::default()
+        })
+        .collect())
+}
+
+/// Page 0 of an owner's index, which carries their genome count: `page`
+/// itself, or `head` when `page` is a later page. Passing `head` alongside
+/// page 0 is rejected, as both copies would be written back.
+fn index_head<'a>(
+    page: &'a mut UserIndex,
+    head: Option<&'a mut UserIndex>,
+) -> Result<&'a mut UserIndex> {
+    if page.page == 0 {
+        require!(head.is_none(), GenomeError::InvalidIndexPage);
+        return Ok(page);
+    }
+    head.ok_or_else(|| error!(GenomeError::InvalidIndexPage))
+}
+
+/// Exact size of a genome account, for resizing one `current_len` bytes
+/// long whose data serializes to `serialized_len` bytes.
+fn migration_target(current_len: usize, serialized_len: usize) -> Result<usize> {
+    let target = 8 + Genome::INIT_SPACE;
+    require!(serialized_len <= target, GenomeError::AccountTooSmall);
+    require!(current_len != target, GenomeError::NothingToMigrate);
+    Ok(target)
+}
+
+/// Transfers the configured cancellation fee for `listings` cancelled
+/// listings from `payer` to the treasury and returns the amount charged.
+fn charge_cancellation_fee<'info>(
+    config: &Config,
+    payer: &Signer<'info>,
+    treasury: Option<&SystemAccount<'info>>,
+    system_program: &Program<'info, System>,
+    listings: u64,
+) -> Result<u64> {
+    let fee = config
+        .cancellation_fee
+        .checked_mul(listings)
+        .ok_or(GenomeError::MathOverflow)?;
+    if fee == 0 {
+        return Ok(0);
+    }
+    let treasury = treasury.ok_or(GenomeError::InvalidRecipient)?;
+    require!(payer.lamports() >= fee, GenomeError::InsufficientFunds);
+
+    system_program::transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            system_program::Transfer {
+                from: payer.to_account_info(),
+                to: treasury.to_account_info(),
+            },
+        ),
+        fee,
+    )?;
+    Ok(fee)
+}
+
+/// Checks that exactly one kind of vault was supplied at listing time and
+/// returns the payment mint it implies (`None` for native SOL).
+fn resolve_payment_mint(
+    mint: Option<Pubkey>,
+    has_token_vault: bool,
+    has_sol_vault: bool,
+) -> Result<Option<Pubkey>> {
+    match (mint, has_token_vault, has_sol_vault) {
+        (Some(mint), true, false) => Ok(Some(mint)),
+        (None, false, true) => Ok(None),
+        _ => err!(GenomeError::InvalidPaymentAccounts),
+    }
+}
+
+/// The vault backing a transaction's or offer's escrow, whichever payment
+/// type it uses.
+pub struct Escrow<'a, 'info> {
+    /// Account the vault PDAs are derived from.
+    owner_key: Pubkey,
+    payment_mint: Option<Pubkey>,
+    bump: u8,
+    /// Whether part of the balance is lent out and must be redeemed first.
+    lent_out: bool,
+    token_program: &'a Program<'info, Token>,
+    system_program: &'a Program<'info, System>,
+    escrow_vault: Option<&'a Account<'info, TokenAccount>>,
+    sol_vault: Option<&'a SystemAccount<'info>>,
+}
+
+impl<'a, 'info> Escrow<'a, 'info> {
+    pub fn new(
+        transaction: &Account<'info, Transaction>,
+        token_program: &'a Program<'info, Token>,
+        system_program: &'a Program<'info, System>,
+        escrow_vault: Option<&'a Account<'info, TokenAccount>>,
+        sol_vault: Option<&'a SystemAccount<'info>>,
+    ) -> Self {
+        Self {
+            owner_key: transaction.key(),
+            payment_mint: transaction.payment_mint,
+            bump: transaction.escrow_bump,
+            lent_out: transaction.yield_principal > 0,
+            token_program,
+            system_program,
+            escrow_vault,
+            sol_vault,
+        }
+    }
+
+    pub fn for_offer(
+        offer: &Account<'info, Offer>,
+        token_program: &'a Program<'info, Token>,
+        system_program: &'a Program<'info, System>,
+        escrow_vault: Option<&'a Account<'info, TokenAccount>>,
+        sol_vault: Option<&'a SystemAccount<'info>>,
+    ) -> Self {
+        Self {
+            owner_key: offer.key(),
+            payment_mint: offer.payment_mint,
+            bump: offer.escrow_bump,
+            lent_out: false,
+            token_program,
+            system_program,
+            escrow_vault,
+            sol_vault,
+        }
+    }
+
+    /// The lamport vault holding a grant's conversion fee.
+    pub fn for_conversion(
+        access_grant: &Account<'info, AccessGrant>,
+        token_program: &'a Program<'info, Token>,
+        system_program: &'a Program<'info, System>,
+        conversion_vault: &'a SystemAccount<'info>,
+    ) -> Self {
+        Self {
+            owner_key: access_grant.key(),
+            payment_mint: None,
+            bump: access_grant.conversion_bump,
+            lent_out: false,
+            token_program,
+            system_program,
+            escrow_vault: None,
+            sol_vault: Some(conversion_vault),
+        }
+    }
+
+    pub fn for_pool(
+        pool: &Account<'info, SubscriptionPool>,
+        token_program: &'a Program<'info, Token>,
+        system_program: &'a Program<'info, System>,
+        pool_vault: &'a SystemAccount<'info>,
+    ) -> Self {
+        Self {
+            owner_key: pool.key(),
+            payment_mint: None,
+            bump: pool.vault_bump,
+            lent_out: false,
+            token_program,
+            system_program,
+            escrow_vault: None,
+            sol_vault: Some(pool_vault),
+        }
+    }
+
+    pub fn for_earnings(
+        earnings: &Account<'info, Earnings>,
+        token_program: &'a Program<'info, Token>,
+        system_program: &'a Program<'info, System>,
+        escrow_vault: Option<&'a Account<'info, TokenAccount>>,
+        sol_vault: Option<&'a SystemAccount<'info>>,
+    ) -> Self {
+        Self {
+            owner_key: earnings.key(),
+            payment_mint: earnings.payment_mint,
+            bump: earnings.vault_bump,
+            lent_out: false,
+            token_program,
+            system_program,
+            escrow_vault,
+            sol_vault,
+        }
+    }
+
+    /// Funds currently held in escrow.
+    pub fn balance(&self) -> Result<u64> {
+        match self.payment_mint {
+            Some(_) => Ok(self
+                .escrow_vault
+                .ok_or(GenomeError::InvalidPaymentAccounts)?
+                .amount),
+            None => Ok(self
+                .sol_vault
+                .ok_or(GenomeError::InvalidPaymentAccounts)?
+                .lamports()),
+        }
+    }
+
+    /// Closes the vault matching the escrow's payment type, sending its rent
+    /// to `destination`. Refuses while it holds more than rent, e.g. bids
+    /// not yet withdrawn.
+    pub fn close(&self, destination: AccountInfo<'info>) -> Result<()> {
+        require!(!self.lent_out, GenomeError::EscrowInYield);
+
+        let bump = [self.bump];
+        match self.payment_mint {
+            Some(_) => {
+                let vault = self
+                    .escrow_vault
+                    .ok_or(GenomeError::InvalidPaymentAccounts)?;
+                require!(vault.amount == 0, GenomeError::EscrowNotEmpty);
+                let seeds: &[&[u8]] = &[ESCROW_SEED, self.owner_key.as_ref(), &bump];
+
+                token::close_account(CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    CloseAccount {
+                        account: vault.to_account_info(),
+                        destination,
+                        authority: vault.to_account_info(),
+                    },
+                    &[seeds],
+                ))
+            }
+            None => {
+                let vault = self.sol_vault.ok_or(GenomeError::InvalidPaymentAccounts)?;
+                let lamports = vault.lamports();
+                require!(
+                    lamports <= Rent::get()?.minimum_balance(0),
+                    GenomeError::EscrowNotEmpty
+                );
+                if lamports == 0 {
+                    return Ok(());
+                }
+                let seeds: &[&[u8]] = &[SOL_VAULT_SEED, self.owner_key.as_ref(), &bump];
+
+                system_program::transfer(
+                    CpiContext::new_with_signer(
+                        self.system_program.to_account_info(),
+                        system_program::Transfer {
+                            from: vault.to_account_info(),
+                            to: destination,
+                        },
+                        &[seeds],
+                    ),
+                    lamports,
+                )
+            }
+        }
+    }
+
+    /// Tops a SOL vault up to rent-exemption so partial payouts never leave
+    /// it in a rent-paying state. Token vaults pay their own rent at `init`.
+    pub fn fund_rent(&self, payer: &Signer<'info>) -> Result<()> {
+        let Some(sol_vault) = self.sol_vault else {
+            return Ok(());
+        };
+        let top_up = Rent::get()?
+            .minimum_balance(0)
+            .saturating_sub(sol_vault.lamports());
+        if top_up == 0 {
+            return Ok(());
+        }
+
+        system_program::transfer(
+            CpiContext::new(
+                self.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: sol_vault.to_account_info(),
+                },
+            ),
+            top_up,
+        )
+    }
+
+    /// Moves `amount` from `payer` into escrow, from their token account for
+    /// SPL listings or their wallet for native SOL.
+    pub fn collect(
+        &self,
+        payer: &Signer<'info>,
+        payer_token_account: Option<&Account<'info, TokenAccount>>,
+        amount: u64,
+    ) -> Result<()> {
+        match self.payment_mint {
+            Some(_) => {
+                let vault = self
+                    .escrow_vault
+                    .ok_or(GenomeError::InvalidPaymentAccounts)?;
+                let source = payer_token_account.ok_or(GenomeError::InvalidPaymentAccounts)?;
+                require!(source.amount >= amount, GenomeError::InsufficientFunds);
+
+                token::transfer(
+                    CpiContext::new(
+                        self.token_program.to_account_info(),
+                        Transfer {
+                            from: source.to_account_info(),
+                            to: vault.to_account_info(),
+                            authority: payer.to_account_info(),
+                        },
+                    ),
+                    amount,
+                )
+            }
+            None => {
+                let vault = self.sol_vault.ok_or(GenomeError::InvalidPaymentAccounts)?;
+                require!(payer.lamports() >= amount, GenomeError::InsufficientFunds);
+
+                system_program::transfer(
+                    CpiContext::new(
+                        self.system_program.to_account_info(),
+                        system_program::Transfer {
+                            from: payer.to_account_info(),
+                            to: vault.to_account_info(),
+                        },
+                    ),
+                    amount,
+                )
+            }
+        }
+    }
+
+    /// Pulls `amount` into the token vault from an account that has approved
+    /// the vault as its delegate. Returns `false`, moving nothing, when the
+    /// approval or the balance falls short.
+    pub fn collect_delegated(
+        &self,
+        source: &Account<'info, TokenAccount>,
+        amount: u64,
+    ) -> Result<bool> {
+        let vault = self
+            .escrow_vault
+            .ok_or(GenomeError::InvalidPaymentAccounts)?;
+        if source.delegate != COption::Some(vault.key())
+            || source.delegated_amount < amount
+            || source.amount < amount
+        {
+            return Ok(false);
+        }
+
+        let bump = [self.bump];
+        let seeds: &[&[u8]] = &[ESCROW_SEED, self.owner_key.as_ref(), &bump];
+        token::transfer(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                Transfer {
+                    from: source.to_account_info(),
+                    to: vault.to_account_info(),
+                    authority: vault.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )?;
+        Ok(true)
+    }
+
+    pub fn pay_sale(&self, payees: &SalePayees<'_, 'info>, split: &SaleSplit) -> Result<()> {
+        self.pay(
+            payees.treasury_token_account,
+            payees.treasury.clone(),
+            split
+                .fee
+                .checked_add(split.conversion_fee)
+                .ok_or(GenomeError::MathOverflow)?,
+        )?;
+        self.pay(
+            payees.creator_token_account,
+            payees.creator.clone(),
+            split.royalty,
+        )?;
+        match &payees.seller_split {
+            Some(seller_split) => self.pay_split(seller_split, split.seller_amount),
+            None => self.pay(
+                payees.seller_token_account,
+                payees.seller.clone(),
+                split.seller_amount,
+            ),
+        }
+    }
+
+    /// Pays `amount` out among a payout split's recipients by share.
+    pub fn pay_split(&self, seller_split: &SellerSplit<'_, 'info>, amount: u64) -> Result<()> {
+        require!(
+            seller_split.accounts.len() == seller_split.shares.len(),
+            GenomeError::InvalidPaymentAccounts
+        );
+        let parts = split_by_shares(seller_split.shares, amount)?;
+        for ((share, account), part) in seller_split
+            .shares
+            .iter()
+            .zip(seller_split.accounts)
+            .zip(parts)
+        {
+            match self.payment_mint {
+                Some(_) => {
+                    let token_account = Account::<TokenAccount>::try_from(account)?;
+                    self.pay_to(share.recipient, Some(&token_account), None, part)?;
+                }
+                None => self.pay_to(share.recipient, None, Some(account.clone()), part)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Like `pay`, but first checks the destination belongs to `recipient`.
+    pub fn pay_to(
+        &self,
+        recipient: Pubkey,
+        token_destination: Option<&Account<'info, TokenAccount>>,
+        wallet_destination: Option<AccountInfo<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        if let Some(token_account) = token_destination {
+            require_keys_eq!(
+                token_account.owner,
+                recipient,
+                GenomeError::InvalidTokenAccount
+            );
+        }
+        if let Some(wallet) = &wallet_destination {
+            require_keys_eq!(wallet.key(), recipient, GenomeError::InvalidRecipient);
+        }
+        self.pay(token_destination, wallet_destination, amount)
+    }
+
+    /// Pays `amount` out of escrow to the token account (SPL) or wallet
+    /// (native SOL) matching the escrow's payment type.
+    pub fn pay(
+        &self,
+        token_destination: Option<&Account<'info, TokenAccount>>,
+        wallet_destination: Option<AccountInfo<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        require!(!self.lent_out, GenomeError::EscrowInYield);
+
+        let bump = [self.bump];
+        match self.payment_mint {
+            Some(_) => {
+                let vault = self
+                    .escrow_vault
+                    .ok_or(GenomeError::InvalidPaymentAccounts)?;
+                let destination = token_destination.ok_or(GenomeError::InvalidPaymentAccounts)?;
+                let seeds: &[&[u8]] = &[ESCROW_SEED, self.owner_key.as_ref(), &bump];
+
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        self.token_program.to_account_info(),
+                        Transfer {
+                            from: vault.to_account_info(),
+                            to: destination.to_account_info(),
+                            authority: vault.to_account_info(),
+                        },
+                        &[seeds],
+                    ),
+                    amount,
+                )
+            }
+            None => {
+                let vault = self.sol_vault.ok_or(GenomeError::InvalidPaymentAccounts)?;
+                let destination = wallet_destination.ok_or(GenomeError::InvalidPaymentAccounts)?;
+                let seeds: &[&[u8]] = &[SOL_VAULT_SEED, self.owner_key.as_ref(), &bump];
+
+                system_program::transfer(
+                    CpiContext::new_with_signer(
+                        self.system_program.to_account_info(),
+                        system_program::Transfer {
+                            from: vault.to_account_info(),
+                            to: destination,
+                        },
+                        &[seeds],
+                    ),
+                    amount,
+                )
+            }
+        }
+    }
+}
+
+#[error_code]
 pub enum GenomeError {
     #[msg("Invalid transaction status")]
     InvalidTransactionStatus,
+    #[msg("Insufficient funds")]
+    InsufficientFunds,
+    #[msg("Token account mint does not match")]
+    MintMismatch,
+    #[msg("Invalid token account")]
+    InvalidTokenAccount,
+    #[msg("Escrow has already been funded")]
+    EscrowAlreadyFunded,
+    #[msg("Escrow has not been funded by this buyer")]
+    EscrowNotFunded,
+    #[msg("Signer is not authorized for this action")]
+    Unauthorized,
+    #[msg("Signer does not own this genome")]
+    NotOwner,
+    #[msg("Genome has been deleted")]
+    GenomeDeleted,
+    #[msg("Genome id does not match the genome account")]
+    GenomeMismatch,
+    #[msg("String exceeds maximum length")]
+    StringTooLong,
+    #[msg("New owner must be a different, non-default key")]
+    InvalidNewOwner,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Access grant has expired")]
+    AccessExpired,
+    #[msg("Access grant has been revoked")]
+    AccessRevoked,
+    #[msg("Buyer already holds an active license for this genome")]
+    AccessAlreadyGranted,
+    #[msg("Fee rate cannot exceed 100%")]
+    InvalidFeeRate,
+    #[msg("Program is paused")]
+    ProgramPaused,
+    #[msg("Content hash does not match")]
+    HashMismatch,
+    #[msg("Payment accounts do not match the listing's payment type")]
+    InvalidPaymentAccounts,
+    #[msg("Recipient does not match the expected account")]
+    InvalidRecipient,
+    #[msg("Transaction is not an auction")]
+    NotAnAuction,
+    #[msg("Auction listings are funded by bids, not deposits")]
+    AuctionListing,
+    #[msg("Auction bidding has ended")]
+    AuctionEnded,
+    #[msg("Auction has not ended yet")]
+    AuctionNotEnded,
+    #[msg("Bid is below the minimum required")]
+    BidTooLow,
+    #[msg("Royalty rate cannot exceed 100%")]
+    InvalidRoyaltyRate,
+    #[msg("Genome still has active listings")]
+    ActiveListings,
+    #[msg("Genome has sales whose proceeds are still in escrow")]
+    UnsettledSales,
+    #[msg("Invalid duration")]
+    InvalidDuration,
+    #[msg("Dispute window has closed")]
+    DisputeWindowClosed,
+    #[msg("Dispute window is still open")]
+    DisputeWindowOpen,
+    #[msg("Access grant does not belong to this transaction")]
+    AccessGrantMismatch,
+    #[msg("Rating must be between 1 and 5 stars")]
+    InvalidRating,
+    #[msg("Price must be greater than zero")]
+    InvalidPrice,
+    #[msg("Offer has expired")]
+    OfferExpired,
+    #[msg("Offer has not expired yet")]
+    OfferNotExpired,
+    #[msg("Batch must contain between 1 and 10 entries")]
+    InvalidBatchSize,
+    #[msg("Batch accounts do not match the entries")]
+    InvalidBatchAccounts,
+    #[msg("Buyer is not permitted to purchase this listing")]
+    BuyerNotAllowed,
+    #[msg("Whitelist is full")]
+    WhitelistFull,
+    #[msg("Too many tags")]
+    TooManyTags,
+    #[msg("Transaction is locked by an execution in progress")]
+    TransactionLocked,
+    #[msg("Listing period has not ended")]
+    ListingNotExpired,
+    #[msg("Auction has bids and must be executed")]
+    AuctionHasBids,
+    #[msg("Genome has already been superseded by a newer version")]
+    GenomeSuperseded,
+    #[msg("Multisig owners or threshold are invalid")]
+    InvalidMultisig,
+    #[msg("Multisig account does not match the genome")]
+    MultisigMismatch,
+    #[msg("Action has not been approved by enough multisig owners")]
+    MultisigThresholdNotMet,
+    #[msg("Buyer does not hold enough of the required token")]
+    TokenGateNotMet,
+    #[msg("Listing has expired")]
+    ListingExpired,
+    #[msg("License count must be at least 1, and exactly 1 for auctions")]
+    InvalidLicenseCount,
+    #[msg("Encrypted key must be between 1 and 512 bytes")]
+    InvalidKey,
+    #[msg("No bid to withdraw")]
+    NoBid,
+    #[msg("The top bid cannot be withdrawn")]
+    WinningBidder,
+    #[msg("Config keys cannot be the default pubkey")]
+    InvalidConfigKey,
+    #[msg("Top bid is below the auction's reserve price")]
+    ReserveNotMet,
+    #[msg("Acknowledged terms do not match the listing's license terms")]
+    TermsMismatch,
+    #[msg("User index page is full")]
+    UserIndexFull,
+    #[msg("A new user index page must follow a full previous page")]
+    InvalidIndexPage,
+    #[msg("Genome is not listed on this user index page")]
+    GenomeNotIndexed,
+    #[msg("Seller is blacklisted")]
+    SellerBlacklisted,
+    #[msg("Blacklist is full")]
+    BlacklistFull,
+    #[msg("Jurisdiction must be an ISO 3166-1 alpha-2 code")]
+    InvalidJurisdiction,
+    #[msg("Too many allowed jurisdictions")]
+    TooManyJurisdictions,
+    #[msg("Buyer's jurisdiction is not permitted for this listing")]
+    JurisdictionNotAllowed,
+    #[msg("Price feed does not match the listing's oracle")]
+    OracleMismatch,
+    #[msg("Oracle price account is invalid")]
+    InvalidOracle,
+    #[msg("Oracle price is stale")]
+    StaleOraclePrice,
+    #[msg("Metadata schema version cannot go backwards")]
+    InvalidSchemaVersion,
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Transaction already has a buyer")]
+    BuyerAlreadySet,
+    #[msg("Transaction has already been executed")]
+    AlreadyExecuted,
+    #[msg("Bundle must list between 2 and 8 distinct genomes")]
+    InvalidBundle,
+    #[msg("Every genome in a bundle must belong to the seller")]
+    BundleOwnerMismatch,
+    #[msg("Sealed-bid auctions take bids through commit_bid")]
+    SealedAuction,
+    #[msg("Listing is not a sealed-bid auction")]
+    NotSealedAuction,
+    #[msg("Bid reveal period is not open")]
+    RevealNotOpen,
+    #[msg("Revealed bid does not match its commitment")]
+    CommitmentMismatch,
+    #[msg("Owner has reached the maximum number of genomes")]
+    GenomeLimitReached,
+    #[msg("Payout shares must be positive, distinct and total 10000 basis points")]
+    InvalidPayoutSplit,
+    #[msg("Too many payout split recipients")]
+    TooManyPayoutRecipients,
+    #[msg("Payout split account does not match the genome")]
+    PayoutSplitMismatch,
+    #[msg("Category index page is full")]
+    CategoryIndexFull,
+    #[msg("Listing is not on this category index page")]
+    CategoryIndexMismatch,
+    #[msg("Account cannot shrink below its serialized data")]
+    AccountTooSmall,
+    #[msg("Account is already at its exact size")]
+    NothingToMigrate,
+    #[msg("Escrow cannot be recovered yet")]
+    RecoveryTooEarly,
+    #[msg("Unknown genome file format")]
+    InvalidFormat,
+    #[msg("Unknown reference genome build")]
+    InvalidReferenceBuild,
+    #[msg("Listing duration is outside the marketplace bounds")]
+    ListingDurationOutOfBounds,
+    #[msg("Buyer already holds the most licenses allowed from this listing")]
+    BuyerLimitReached,
+    #[msg("Listing caps licenses per buyer; buyer licenses account required")]
+    BuyerLicensesMissing,
+    #[msg("Clock sysvar is unavailable")]
+    ClockUnavailable,
+    #[msg("Access grant is not due for renewal")]
+    RenewalNotDue,
+    #[msg("Decryption key has not been delivered")]
+    KeyNotDelivered,
+    #[msg("Genome already has an active listing")]
+    AlreadyListed,
+    #[msg("Payment mint is not on the allowlist")]
+    MintNotAllowed,
+    #[msg("Mint allowlist is full")]
+    AllowedMintsFull,
+    #[msg("Purchase history page is full")]
+    BuyerHistoryFull,
+    #[msg("Invalid permitted or declared use")]
+    InvalidPermittedUse,
+    #[msg("Declared use is not permitted by the genome's consent terms")]
+    UseNotPermitted,
+    #[msg("Not enough units remain on the listing")]
+    InsufficientUnits,
+    #[msg("Seller must wait out the listing cooldown")]
+    ListingCooldown,
+    #[msg("Unknown access level")]
+    InvalidAccessLevel,
+    #[msg("Escrow yield routing is disabled")]
+    YieldDisabled,
+    #[msg("Yield program does not match the config")]
+    YieldProgramMismatch,
+    #[msg("Escrow funds are lent out and must be redeemed first")]
+    EscrowInYield,
+    #[msg("Escrow has nothing lent out")]
+    NothingLentOut,
+    #[msg("Yield program returned less than the principal")]
+    YieldShortfall,
+    #[msg("Sale volume cap for this epoch reached")]
+    EpochVolumeCapReached,
+    #[msg("Conversion target matches the original format")]
+    SameFormat,
+    #[msg("A conversion is already pending on this grant")]
+    ConversionPending,
+    #[msg("No conversion has been requested")]
+    NoConversionRequested,
+    #[msg("Buyers and sellers cannot refer their own sales")]
+    SelfReferral,
+    #[msg("Referral profile account is required")]
+    ReferralProfileMissing,
+    #[msg("Genome was acquired too recently to be resold")]
+    ResaleLocked,
+    #[msg("Genome metadata is sealed under embargo")]
+    GenomeEmbargoed,
+    #[msg("Genome is not under embargo")]
+    NotEmbargoed,
+    #[msg("Metadata does not match the sealed hash")]
+    SealedMetadataMismatch,
+    #[msg("Genome owner has not opted into nonprofit access")]
+    NonprofitAccessDisabled,
+    #[msg("Free access quota is exhausted")]
+    GrantQuotaExhausted,
+    #[msg("Withdrawal exceeds accrued earnings")]
+    InsufficientEarnings,
+    #[msg("An access grant or subscription pass is required")]
+    NoAccessProvided,
+    #[msg("Genome is not in the subscription pool")]
+    NotInSubscriptionPool,
+    #[msg("Subscription pool accounts are required")]
+    SubscriptionPoolMissing,
+    #[msg("Cancelling the listing requires its index, profile and stats accounts")]
+    ListingAccountsMissing,
+    #[msg("Genome is already in the subscription pool")]
+    AlreadyInSubscriptionPool,
+    #[msg("No genomes are in the subscription pool")]
+    EmptySubscriptionPool,
+    #[msg("Subscription passes are not on sale")]
+    SubscriptionsDisabled,
+    #[msg("Client version is no longer supported; upgrade the client")]
+    ClientTooOld,
+    #[msg("Minimum client version cannot exceed the program version")]
+    InvalidClientVersion,
+    #[msg("Genome data has not been attested recently")]
+    HeartbeatStale,
+    #[msg("Encrypted metadata exceeds maximum length")]
+    EncryptedMetadataTooLong,
+    #[msg("Buyer is listed more than once")]
+    DuplicateBuyer,
+    #[msg("Buyer reputation is below the listing's minimum")]
+    BuyerReputationTooLow,
+    #[msg("Listing is not set to relist automatically")]
+    AutoRelistDisabled,
+    #[msg("Listing has been relisted the maximum number of times")]
+    AutoRelistLimitReached,
+    #[msg("Escrow still holds funds")]
+    EscrowNotEmpty,
+    #[msg("Access log page is full")]
+    AccessLogFull,
+    #[msg("Sale split does not add up to the price")]
+    SplitMismatch,
+    #[msg("Offer is below the listing's minimum acceptable price")]
+    OfferBelowFloor,
+    #[msg("An open listing of the genome must be provided")]
+    ListingRequired,
+    #[msg("A recovery is already in progress")]
+    RecoveryPending,
+    #[msg("No recovery is in progress")]
+    NoRecoveryPending,
+    #[msg("Recovery timelock has not elapsed")]
+    RecoveryTimelocked,
+    #[msg("Consent for this genome has been withdrawn")]
+    ConsentWithdrawn,
+    #[msg("SLA claims are disabled")]
+    SlaDisabled,
+    #[msg("Genome heartbeat has not lapsed")]
+    NoSlaBreach,
+    #[msg("This heartbeat lapse has already been claimed")]
+    SlaAlreadyClaimed,
+    #[msg("Listing holds no collateral to slash")]
+    NoCollateral,
+    #[msg("Too many provenance accessions")]
+    TooManyAccessions,
+    #[msg("Invalid accession source or id")]
+    InvalidAccession,
+    #[msg("Buyer was refunded by this seller too recently")]
+    RefundCooldown,
+    #[msg("No upgrade window is open")]
+    UpgradeWindowClosed,
+    #[msg("Upgrade window is open for a different program")]
+    MigrationTargetMismatch,
+    #[msg("Account has been migrated to a new program")]
+    AccountMigrated,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_error(result: Result<()>, expected: GenomeError) {
+        assert_eq!(
+            result.unwrap_err(),
+            anchor_lang::error::Error::from(expected)
+        );
+    }
+
+    #[test]
+    fn yield_redemption_must_cover_principal() {
+        assert_eq!(yield_earned(1_000, 1_025).unwrap(), 25);
+        assert_eq!(yield_earned(1_000, 1_000).unwrap(), 0);
+        assert_error(
+            yield_earned(1_000, 999).map(|_| ()),
+            GenomeError::YieldShortfall,
+        );
+    }
+
+    #[test]
+    fn cancel_rejects_non_seller() {
+        let transaction = Transaction {
+            seller: Pubkey::new_unique(),
+            ..Default::default()
+        };
+
+        assert!(transaction.assert_seller(transaction.seller).is_ok());
+        assert_error(
+            transaction.assert_seller(Pubkey::new_unique()),
+            GenomeError::Unauthorized,
+        );
+    }
+
+    #[test]
+    fn locked_sale_awaits_confirmation_until_settled() {
+        let mut transaction = Transaction {
+            status: TransactionStatus::Locked,
+            ..Default::default()
+        };
+
+        assert!(transaction.awaiting_settlement());
+        transaction.status = TransactionStatus::Executed;
+        assert!(transaction.awaiting_settlement());
+        transaction.settled = true;
+        assert!(!transaction.awaiting_settlement());
+        transaction.status = TransactionStatus::Created;
+        transaction.settled = false;
+        assert!(!transaction.awaiting_settlement());
+    }
+
+    #[test]
+    fn settlement_claimable_only_after_undisputed_window() {
+        let mut transaction = Transaction {
+            status: TransactionStatus::Executed,
+            executed_at: Some(1_000),
+            ..Default::default()
+        };
+
+        assert_error(
+            transaction.assert_claimable(600, 1_600),
+            GenomeError::DisputeWindowOpen,
+        );
+        assert!(transaction.assert_claimable(600, 1_601).is_ok());
+
+        transaction.status = TransactionStatus::Disputed;
+        assert_error(
+            transaction.assert_claimable(600, 1_601),
+            GenomeError::InvalidTransactionStatus,
+        );
+    }
+
+    #[test]
+    fn execution_requires_matching_terms_hash() {
+        let transaction = Transaction {
+            terms_hash: hash(b"license v1").to_bytes(),
+            ..Default::default()
+        };
+
+        assert!(transaction
+            .assert_terms(&hash(b"license v1").to_bytes())
+            .is_ok());
+        assert_error(
+            transaction.assert_terms(&hash(b"license v2").to_bytes()),
+            GenomeError::TermsMismatch,
+        );
+    }
+
+    #[test]
+    fn execution_rejects_disallowed_jurisdictions() {
+        let mut transaction = Transaction::default();
+        assert!(transaction.assert_jurisdiction(b"US").is_ok());
+
+        transaction.allowed_jurisdictions = vec![*b"DE", *b"FR"];
+        assert!(transaction.assert_jurisdiction(b"DE").is_ok());
+        assert_error(
+            transaction.assert_jurisdiction(b"US"),
+            GenomeError::JurisdictionNotAllowed,
+        );
+        assert_error(
+            transaction.assert_jurisdiction(b"de"),
+            GenomeError::InvalidJurisdiction,
+        );
+    }
+
+    #[test]
+    fn executed_transaction_cannot_be_executed_again() {
+        let mut transaction = Transaction {
+            max_licenses: 1,
+            ..Default::default()
+        };
+
+        transaction.begin_execution().unwrap();
+        assert_error(
+            transaction.begin_execution(),
+            GenomeError::TransactionLocked,
+        );
+
+        transaction
+            .complete_execution(Pubkey::new_unique(), 100, true)
+            .unwrap();
+        assert!(!transaction.locked);
+        assert_error(
+            transaction.begin_execution(),
+            GenomeError::InvalidTransactionStatus,
+        );
+
+        // A record with a buyer already set is rejected even if its status
+        // was somehow left at `Created`.
+        transaction.status = TransactionStatus::Created;
+        assert_error(
+            transaction.begin_execution(),
+            GenomeError::InvalidTransactionStatus,
+        );
+    }
+
+    #[test]
+    fn batch_cancel_skips_foreign_closed_and_funded_listings() {
+        let seller = Pubkey::new_unique();
+        let open = Transaction {
+            seller,
+            ..Default::default()
+        };
+
+        assert!(open.batch_cancellable(seller));
+        assert!(!open.batch_cancellable(Pubkey::new_unique()));
+        assert!(!Transaction {
+            status: TransactionStatus::Cancelled,
+            ..open.clone()
+        }
+        .batch_cancellable(seller));
+        assert!(!Transaction {
+            depositor: Some(Pubkey::new_unique()),
+            ..open.clone()
+        }
+        .batch_cancellable(seller));
+        assert!(Transaction {
+            depositor: Some(Pubkey::new_unique()),
+            auction: Some(Auction::default()),
+            ..open
+        }
+        .batch_cancellable(seller));
+    }
+
+    #[test]
+    fn relisting_carries_restrictions_forward() {
+        let buyer = Pubkey::new_unique();
+        let mut previous = Transaction {
+            price: 100,
+            access_duration: 60,
+            created_at: 1_000,
+            listing_expires_at: 1_500,
+            max_licenses: 1,
+            allowed_buyer: Some(buyer),
+            terms_hash: [7; 32],
+            auction: Some(Auction {
+                min_increment: 5,
+                bid_count: 3,
+                reserve_price: 80,
+                reveal_ends_at: None,
+            }),
+            ..Default::default()
+        };
+
+        assert_error(
+            previous.relisting_terms(200, 120, 2_000).map(|_| ()),
+            GenomeError::InvalidTransactionStatus,
+        );
+
+        previous.status = TransactionStatus::Expired;
+        let terms = previous.relisting_terms(200, 120, 2_000).unwrap();
+        assert_eq!(terms.price, 200);
+        assert_eq!(terms.access_duration, 120);
+        assert_eq!(terms.listing_expires_at, 2_500);
+        assert_eq!(terms.allowed_buyer, Some(buyer));
+        assert_eq!(terms.terms_hash, [7; 32]);
+        assert_eq!(terms.min_bid_increment, Some(5));
+        assert_eq!(terms.reserve_price, 80);
+
+        let mut relisted = Transaction::default();
+        relisted.apply_terms(terms);
+        assert_eq!(relisted.auction.unwrap().bid_count, 0);
+    }
+
+    #[test]
+    fn completing_an_already_bought_transaction_is_rejected() {
+        let first_buyer = Pubkey::new_unique();
+        let mut transaction = Transaction {
+            max_licenses: 1,
+            ..Default::default()
+        };
+        transaction
+            .complete_execution(first_buyer, 100, true)
+            .unwrap();
+
+        // Even with the status forced back, the buyer and execution time
+        // cannot be overwritten.
+        transaction.status = TransactionStatus::Created;
+        assert_error(
+            transaction.complete_execution(Pubkey::new_unique(), 200, true),
+            GenomeError::BuyerAlreadySet,
+        );
+
+        transaction.buyer = None;
+        assert_error(
+            transaction.complete_execution(Pubkey::new_unique(), 200, true),
+            GenomeError::AlreadyExecuted,
+        );
+        assert_eq!(transaction.executed_at, Some(100));
+        assert_eq!(transaction.licenses_issued, 1);
+    }
+
+    #[test]
+    fn only_unsold_listings_past_their_period_expire() {
+        let mut transaction = Transaction {
+            created_at: 1_000,
+            listing_expires_at: 1_100,
+            ..Default::default()
+        };
+
+        assert_error(
+            transaction.assert_expirable(1_099),
+            GenomeError::ListingNotExpired,
+        );
+        assert!(transaction.assert_expirable(1_100).is_ok());
+
+        transaction.status = TransactionStatus::Executed;
+        assert_error(
+            transaction.assert_expirable(1_100),
+            GenomeError::InvalidTransactionStatus,
+        );
+        transaction.status = TransactionStatus::Cancelled;
+        assert_error(
+            transaction.assert_expirable(1_100),
+            GenomeError::InvalidTransactionStatus,
+        );
+    }
+
+    #[test]
+    fn non_exclusive_listing_sells_until_license_cap() {
+        let mut transaction = Transaction {
+            max_licenses: 2,
+            depositor: Some(Pubkey::new_unique()),
+            ..Default::default()
+        };
+
+        transaction.begin_execution().unwrap();
+        transaction
+            .complete_execution(Pubkey::new_unique(), 100, true)
+            .unwrap();
+        assert_eq!(transaction.licenses_issued, 1);
+        assert!(transaction.status == TransactionStatus::Created);
+        assert_eq!(transaction.depositor, None);
+        assert_eq!(transaction.buyer, None);
+
+        transaction.begin_execution().unwrap();
+        transaction
+            .complete_execution(Pubkey::new_unique(), 200, true)
+            .unwrap();
+        assert!(transaction.status == TransactionStatus::Executed);
+        assert_eq!(transaction.executed_at, Some(200));
+        assert_error(
+            transaction.begin_execution(),
+            GenomeError::InvalidTransactionStatus,
+        );
+    }
+
+    #[test]
+    fn delete_rejects_non_owner_and_double_delete() {
+        let mut genome = Genome {
+            owner: Pubkey::new_unique(),
+            ..Default::default()
+        };
+
+        assert_error(
+            genome.assert_owner(Pubkey::new_unique()),
+            GenomeError::NotOwner,
+        );
+        assert!(genome.soft_delete().is_ok());
+        assert!(genome.deleted);
+        assert_error(genome.soft_delete(), GenomeError::GenomeDeleted);
+    }
+
+    #[test]
+    fn transfer_ownership_revokes_previous_owner() {
+        let old_owner = Pubkey::new_unique();
+        let new_owner = Pubkey::new_unique();
+        let mut genome = Genome {
+            owner: old_owner,
+            ..Default::default()
+        };
+
+        assert_error(genome.transfer_to(old_owner), GenomeError::InvalidNewOwner);
+        assert_error(
+            genome.transfer_to(Pubkey::default()),
+            GenomeError::InvalidNewOwner,
+        );
+
+        genome.transfer_to(new_owner).unwrap();
+        assert_eq!(genome.previous_owner, Some(old_owner));
+        assert!(genome.assert_owner(new_owner).is_ok());
+        assert_error(genome.assert_owner(old_owner), GenomeError::NotOwner);
+    }
+
+    #[test]
+    fn status_history_keeps_the_latest_changes() {
+        let mut transaction = Transaction::default();
+
+        transaction.set_status(TransactionStatus::Created, 1);
+        transaction.set_status(TransactionStatus::Executed, 2);
+        assert!(transaction.status == TransactionStatus::Executed);
+        assert_eq!(transaction.status_history.len(), 2);
+        assert!(transaction.status_history[0].status == TransactionStatus::Created);
+
+        for now in 3..=20 {
+            transaction.set_status(TransactionStatus::Disputed, now);
+        }
+        assert_eq!(transaction.status_history.len(), MAX_STATUS_HISTORY);
+        assert_eq!(transaction.status_history[0].timestamp, 5);
+        assert_eq!(
+            transaction.status_history[MAX_STATUS_HISTORY - 1].timestamp,
+            20
+        );
+    }
+
+    #[test]
+    fn declared_use_must_match_consent() {
+        let research_only = Genome {
+            permitted_use: USE_RESEARCH,
+            ..Default::default()
+        };
+        assert!(research_only.assert_use(USE_RESEARCH).is_ok());
+        assert_error(
+            research_only.assert_use(USE_COMMERCIAL),
+            GenomeError::UseNotPermitted,
+        );
+        assert_error(
+            research_only.assert_use(USE_ANY),
+            GenomeError::InvalidPermittedUse,
+        );
+
+        let unrestricted = Genome::default();
+        assert!(unrestricted.assert_use(USE_COMMERCIAL).is_ok());
+    }
+
+    #[test]
+    fn unit_sales_charge_by_quantity_until_sold_out() {
+        let mut transaction = Transaction::default();
+        assert_eq!(transaction.take_units(5).unwrap(), None);
+
+        transaction.unit_price = 30;
+        transaction.units_available = 10;
+        assert_eq!(transaction.take_units(4).unwrap(), Some(120));
+        assert_eq!(transaction.units_available, 6);
+        assert_error(
+            transaction.take_units(7).map(|_| ()),
+            GenomeError::InsufficientUnits,
+        );
+        assert_error(
+            transaction.take_units(0).map(|_| ()),
+            GenomeError::InsufficientUnits,
+        );
+
+        transaction.unit_price = u64::MAX;
+        assert_error(
+            transaction.take_units(2).map(|_| ()),
+            GenomeError::MathOverflow,
+        );
+        assert_eq!(transaction.units_available, 6);
+    }
+
+    #[test]
+    fn second_concurrent_listing_is_rejected() {
+        let mut genome = Genome::default();
+
+        genome.open_listing().unwrap();
+        assert!(genome.listing_active());
+        assert_error(genome.open_listing(), GenomeError::AlreadyListed);
+
+        genome.active_listings = genome.active_listings.saturating_sub(1);
+        assert!(!genome.listing_active());
+        assert!(genome.open_listing().is_ok());
+    }
+
+    #[test]
+    fn delegate_can_list_but_not_transfer() {
+        let owner = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+        let mut genome = Genome {
+            owner,
+            delegate: Some(delegate),
+            ..Default::default()
+        };
+        let listing = || MultisigAction::CreateTransaction {
+            price: 100,
+            access_duration: 60,
+        };
+
+        assert!(genome.authorize_listing(delegate, None, listing()).is_ok());
+        assert!(genome.authorize_listing(owner, None, listing()).is_ok());
+        assert_error(
+            genome.authorize_listing(Pubkey::new_unique(), None, listing()),
+            GenomeError::NotOwner,
+        );
+        assert_error(
+            genome.authorize(
+                delegate,
+                None,
+                MultisigAction::TransferOwnership {
+                    new_owner: delegate,
+                },
+            ),
+            GenomeError::NotOwner,
+        );
+        assert_error(genome.assert_owner(delegate), GenomeError::NotOwner);
+
+        genome.transfer_to(Pubkey::new_unique()).unwrap();
+        assert_eq!(genome.delegate, None);
+    }
+
+    #[test]
+    fn strings_are_bounded_at_their_max_length() {
+        assert!(validate_len(&"s".repeat(MAX_ID_LEN), MAX_ID_LEN).is_ok());
+        assert_error(
+            validate_len(&"s".repeat(MAX_ID_LEN + 1), MAX_ID_LEN),
+            GenomeError::StringTooLong,
+        );
+        assert!(validate_len(&"m".repeat(MAX_METADATA_LEN), MAX_METADATA_LEN).is_ok());
+        assert_error(
+            validate_len(&"m".repeat(MAX_METADATA_LEN + 1), MAX_METADATA_LEN),
+            GenomeError::StringTooLong,
+        );
+    }
+
+    #[test]
+    fn tags_are_bounded_in_count_and_length() {
+        let tags = vec!["t".repeat(MAX_TAG_LEN); MAX_TAGS];
+        assert!(validate_tags(&tags).is_ok());
+
+        let too_many = vec!["t".to_string(); MAX_TAGS + 1];
+        assert_error(validate_tags(&too_many), GenomeError::TooManyTags);
+
+        let too_long = vec!["t".repeat(MAX_TAG_LEN + 1)];
+        assert_error(validate_tags(&too_long), GenomeError::StringTooLong);
+    }
+
+    #[test]
+    fn register_rejects_invalid_entries() {
+        let owner = Pubkey::new_unique();
+        let entry = GenomeEntry {
+            storage_id: "s".repeat(MAX_ID_LEN),
+            metadata: "m".repeat(MAX_METADATA_LEN),
+            royalty_bps: 500,
+            ..Default::default()
+        };
+
+        let genome = Genome::register(owner, entry.clone(), 42, 255).unwrap();
+        assert_eq!(genome.original_creator, owner);
+        assert_eq!(genome.updated_at, 42);
+
+        let long_id = GenomeEntry {
+            storage_id: "s".repeat(MAX_ID_LEN + 1),
+            ..entry.clone()
+        };
+        assert_eq!(
+            Genome::register(owner, long_id, 42, 255).unwrap_err(),
+            anchor_lang::error::Error::from(GenomeError::StringTooLong)
+        );
+        let bad_royalty = GenomeEntry {
+            royalty_bps: 10_001,
+            ..entry.clone()
+        };
+        assert_eq!(
+            Genome::register(owner, bad_royalty, 42, 255).unwrap_err(),
+            anchor_lang::error::Error::from(GenomeError::InvalidRoyaltyRate)
+        );
+        let bad_format = GenomeEntry {
+            format: FORMAT_OTHER + 1,
+            ..entry.clone()
+        };
+        assert_eq!(
+            Genome::register(owner, bad_format, 42, 255).unwrap_err(),
+            anchor_lang::error::Error::from(GenomeError::InvalidFormat)
+        );
+        let bad_build = GenomeEntry {
+            reference_build: REFERENCE_OTHER + 1,
+            ..entry.clone()
+        };
+        assert_eq!(
+            Genome::register(owner, bad_build, 42, 255).unwrap_err(),
+            anchor_lang::error::Error::from(GenomeError::InvalidReferenceBuild)
+        );
+        let long_preview = GenomeEntry {
+            preview_uri: "p".repeat(MAX_PREVIEW_URI_LEN + 1),
+            ..entry
+        };
+        assert_eq!(
+            Genome::register(owner, long_preview, 42, 255).unwrap_err(),
+            anchor_lang::error::Error::from(GenomeError::StringTooLong)
+        );
+    }
+
+    #[test]
+    fn metadata_schema_version_only_moves_forward() {
+        let mut genome = Genome {
+            metadata_schema_version: 2,
+            ..Default::default()
+        };
+
+        genome.set_metadata("v2".to_string(), None).unwrap();
+        assert_eq!(genome.metadata_schema_version, 2);
+        genome.set_metadata("v3".to_string(), Some(3)).unwrap();
+        assert_eq!(genome.metadata_schema_version, 3);
+        assert_error(
+            genome.set_metadata("v1".to_string(), Some(1)),
+            GenomeError::InvalidSchemaVersion,
+        );
+        assert_eq!(genome.metadata, "v3");
+    }
+
+    #[test]
+    fn genome_patch_applies_all_fields_or_none() {
+        let mut genome = Genome {
+            metadata: "v1".to_string(),
+            ..Default::default()
+        };
+
+        assert_error(
+            genome.apply_patch(GenomePatch {
+                metadata: Some("v2".to_string()),
+                format: Some(FORMAT_OTHER + 1),
+                ..Default::default()
+            }),
+            GenomeError::InvalidFormat,
+        );
+        assert_eq!(genome.metadata, "v1");
+
+        genome
+            .apply_patch(GenomePatch {
+                tags: Some(vec!["wgs".to_string()]),
+                format: Some(FORMAT_CRAM),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(genome.metadata, "v1");
+        assert_eq!(genome.tags, vec!["wgs".to_string()]);
+        assert_eq!(genome.format, FORMAT_CRAM);
+    }
+
+    #[test]
+    fn new_version_links_back_and_keeps_lineage() {
+        let creator = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let previous_key = Pubkey::new_unique();
+        let mut previous = Genome::register(creator, GenomeEntry::default(), 0, 255).unwrap();
+        previous.owner = owner;
+        previous.royalty_bps = 250;
+
+        let successor = previous
+            .successor(
+                previous_key,
+                "v2".to_string(),
+                String::new(),
+                [1; 32],
+                10,
+                254,
+            )
+            .unwrap();
+        assert_eq!(successor.version, 2);
+        assert_eq!(successor.previous_version, Some(previous_key));
+        assert_eq!(successor.owner, owner);
+        assert_eq!(successor.original_creator, creator);
+        assert_eq!(successor.royalty_bps, 250);
+
+        previous.superseded_by = Some(Pubkey::new_unique());
+        assert_eq!(
+            previous
+                .successor(
+                    previous_key,
+                    "v2b".to_string(),
+                    String::new(),
+                    [2; 32],
+                    10,
+                    254
+                )
+                .unwrap_err(),
+            anchor_lang::error::Error::from(GenomeError::GenomeSuperseded)
+        );
+    }
+
+    #[test]
+    fn multisig_two_of_three_approval() {
+        let owners: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let mut multisig =
+            MultisigOwner::new(Pubkey::new_unique(), owners.clone(), 2, 255).unwrap();
+        let genome = Genome {
+            owner: Pubkey::new_unique(),
+            multisig: Some(Pubkey::new_unique()),
+            ..Default::default()
+        };
+        let action = MultisigAction::TransferOwnership {
+            new_owner: Pubkey::new_unique(),
+        };
+
+        multisig.approve(owners[0], action.clone()).unwrap();
+        multisig.approve(owners[0], action.clone()).unwrap();
+        assert_error(
+            genome.authorize(owners[0], Some(&mut multisig), action.clone()),
+            GenomeError::MultisigThresholdNotMet,
+        );
+        assert_error(
+            multisig.approve(Pubkey::new_unique(), action.clone()),
+            GenomeError::Unauthorized,
+        );
+
+        multisig.approve(owners[1], action.clone()).unwrap();
+        assert!(genome
+            .authorize(owners[2], Some(&mut multisig), action.clone())
+            .is_ok());
+        // Approvals are spent by the authorized call.
+        assert_error(
+            genome.authorize(owners[2], Some(&mut multisig), action),
+            GenomeError::MultisigThresholdNotMet,
+        );
+        assert_error(
+            genome.authorize(
+                genome.owner,
+                None,
+                MultisigAction::CreateTransaction {
+                    price: 1,
+                    access_duration: 1,
+                },
+            ),
+            GenomeError::MultisigMismatch,
+        );
+
+        assert!(MultisigOwner::new(Pubkey::new_unique(), owners.clone(), 4, 255).is_err());
+        assert!(MultisigOwner::new(Pubkey::new_unique(), vec![owners[0]; 2], 1, 255).is_err());
+    }
+
+    #[test]
+    fn init_space_fits_max_length_accounts() {
+        let genome = Genome {
+            storage_id: "s".repeat(MAX_ID_LEN),
+            metadata: "m".repeat(MAX_METADATA_LEN),
+            previous_owner: Some(Pubkey::new_unique()),
+            tags: vec!["t".repeat(MAX_TAG_LEN); MAX_TAGS],
+            previous_version: Some(Pubkey::new_unique()),
+            superseded_by: Some(Pubkey::new_unique()),
+            ..Default::default()
+        };
+        let transaction = Transaction {
+            genome_id: "g".repeat(MAX_ID_LEN),
+            buyer: Some(Pubkey::new_unique()),
+            executed_at: Some(0),
+            depositor: Some(Pubkey::new_unique()),
+            ..Default::default()
+        };
+
+        assert!(genome.try_to_vec().unwrap().len() <= Genome::INIT_SPACE);
+        assert!(transaction.try_to_vec().unwrap().len() <= Transaction::INIT_SPACE);
+    }
+
+    #[test]
+    fn payment_mint_follows_supplied_vault() {
+        let mint = Pubkey::new_unique();
+
+        assert_eq!(
+            resolve_payment_mint(Some(mint), true, false).unwrap(),
+            Some(mint)
+        );
+        assert_eq!(resolve_payment_mint(None, false, true).unwrap(), None);
+        assert_eq!(
+            resolve_payment_mint(Some(mint), true, true).unwrap_err(),
+            anchor_lang::error::Error::from(GenomeError::InvalidPaymentAccounts)
+        );
+        assert_eq!(
+            resolve_payment_mint(None, true, false).unwrap_err(),
+            anchor_lang::error::Error::from(GenomeError::InvalidPaymentAccounts)
+        );
+    }
+
+    #[test]
+    fn only_allowlisted_mints_are_accepted() {
+        let vetted = Pubkey::new_unique();
+        let unknown = Pubkey::new_unique();
+        let mut config = Config::default();
+
+        config.allow_mint(vetted).unwrap();
+        config.allow_mint(vetted).unwrap();
+        assert_eq!(config.allowed_mints.len(), 1);
+
+        assert!(config.assert_mint_allowed(Some(vetted)).is_ok());
+        assert!(config.assert_mint_allowed(None).is_ok());
+        assert_error(
+            config.assert_mint_allowed(Some(unknown)),
+            GenomeError::MintNotAllowed,
+        );
+
+        config.disallow_mint(vetted);
+        assert_error(
+            config.assert_mint_allowed(Some(vetted)),
+            GenomeError::MintNotAllowed,
+        );
+    }
+
+    #[test]
+    fn listing_terms_reject_zero_price() {
+        let terms = ListingTerms {
+            price: 1,
+            access_duration: 60,
+            listing_expires_at: 100,
+            max_licenses: 1,
+            ..Default::default()
+        };
+
+        assert!(terms.validate(0).is_ok());
+        assert_error(
+            ListingTerms { price: 0, ..terms }.validate(0),
+            GenomeError::InvalidPrice,
+        );
+    }
+
+    #[test]
+    fn auction_below_reserve_settles_unsold() {
+        let mut transaction = Transaction {
+            price: 90,
+            listing_expires_at: 100,
+            depositor: Some(Pubkey::new_unique()),
+            auction: Some(Auction {
+                reserve_price: 100,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert!(!transaction.reserve_met());
+        assert!(transaction.assert_expirable(100).is_ok());
+
+        transaction.price = 100;
+        assert!(transaction.reserve_met());
+        assert_error(
+            transaction.assert_expirable(100),
+            GenomeError::AuctionHasBids,
+        );
+
+        transaction.depositor = None;
+        assert!(!transaction.reserve_met());
+    }
+
+    #[test]
+    fn auction_bids_must_beat_top_bid_by_increment() {
+        let mut transaction = Transaction {
+            price: 100,
+            auction: Some(Auction {
+                min_increment: 10,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(transaction.minimum_bid().unwrap(), 100);
+        transaction.depositor = Some(Pubkey::new_unique());
+        assert_eq!(transaction.minimum_bid().unwrap(), 110);
+
+        transaction.auction = None;
+        assert_eq!(
+            transaction.minimum_bid().unwrap_err(),
+            anchor_lang::error::Error::from(GenomeError::NotAnAuction)
+        );
+    }
+
+    #[test]
+    fn only_outbid_bidders_can_withdraw() {
+        let winner = Pubkey::new_unique();
+        let loser = Pubkey::new_unique();
+        let transaction = Transaction {
+            depositor: Some(winner),
+            ..Default::default()
+        };
+
+        let mut losing_bid = Bid {
+            bidder: loser,
+            ..Default::default()
+        };
+        assert_error(
+            losing_bid.assert_withdrawable(&transaction),
+            GenomeError::NoBid,
+        );
+        assert_eq!(losing_bid.raise_to(100).unwrap(), 100);
+        assert_eq!(losing_bid.raise_to(150).unwrap(), 50);
+        assert!(losing_bid.assert_withdrawable(&transaction).is_ok());
+
+        let winning_bid = Bid {
+            bidder: winner,
+            amount: 200,
+            ..Default::default()
+        };
+        assert_error(
+            winning_bid.assert_withdrawable(&transaction),
+            GenomeError::WinningBidder,
+        );
+    }
+
+    #[test]
+    fn migration_needs_open_window_and_retires_accounts() {
+        let new_program = Pubkey::new_unique();
+        let mut config = Config::default();
+        assert_error(
+            config.assert_upgrade_window(new_program, 0),
+            GenomeError::UpgradeWindowClosed,
+        );
+        config.upgrade_program = Some(new_program);
+        config.upgrade_window_ends = 100;
+        config.assert_upgrade_window(new_program, 99).unwrap();
+        assert_error(
+            config.assert_upgrade_window(new_program, 100),
+            GenomeError::UpgradeWindowClosed,
+        );
+        assert_error(
+            config.assert_upgrade_window(Pubkey::new_unique(), 99),
+            GenomeError::MigrationTargetMismatch,
+        );
+
+        let mut genome = Genome::default();
+        genome.assert_live().unwrap();
+        genome.migrate(new_program).unwrap();
+        genome.migrate(new_program).unwrap();
+        assert_error(genome.assert_live(), GenomeError::AccountMigrated);
+        assert_error(
+            genome.migrate(Pubkey::new_unique()),
+            GenomeError::AccountMigrated,
+        );
+
+        let mut transaction = Transaction::default();
+        transaction.assert_renewable().unwrap();
+        transaction.migrate(new_program, 50).unwrap();
+        assert!(transaction.status == TransactionStatus::Migrated);
+        assert_eq!(transaction.migrated_to, Some(new_program));
+        assert_error(
+            transaction.migrate(new_program, 51),
+            GenomeError::AccountMigrated,
+        );
+        assert_error(transaction.assert_live(), GenomeError::AccountMigrated);
+        assert_error(transaction.assert_renewable(), GenomeError::AccountMigrated);
+    }
+
+    #[test]
+    fn weighted_reputation_favours_recent_ratings() {
+        let mut profile = SellerProfile::default();
+        profile.record_rating(1).unwrap();
+        profile.record_weighted_rating(1, 100, 0).unwrap();
+        profile.record_rating(5).unwrap();
+        profile.record_weighted_rating(5, 100, 100).unwrap();
+
+        assert_eq!(profile.reputation(), 300);
+        let weighted = profile.weighted_reputation(100, 100);
+        assert_eq!(weighted.reputation, 366);
+        assert_eq!(weighted.weight, 1_500);
+        // Decay thins the weight out without moving the average.
+        assert_eq!(profile.weighted_reputation(100, 150).weight, 1_125);
+        assert_eq!(profile.weighted_reputation(100, 300).weight, 375);
+        assert_eq!(profile.weighted_reputation(100, 300).reputation, 366);
+        assert_eq!(profile.weighted_reputation(100, i64::MAX).weight, 0);
+
+        let mut undecayed = SellerProfile::default();
+        undecayed.record_weighted_rating(1, 0, 0).unwrap();
+        undecayed.record_weighted_rating(5, 0, 100).unwrap();
+        assert_eq!(undecayed.weighted_reputation(0, 1_000).reputation, 300);
+        assert_error(
+            undecayed.record_weighted_rating(6, 0, 0),
+            GenomeError::InvalidRating,
+        );
+    }
+
+    #[test]
+    fn refund_cooldown_blocks_early_repurchase() {
+        let mut record = RefundRecord::default();
+        record.record_refund(Pubkey::new_unique(), Pubkey::new_unique(), 1_000, 255);
+
+        assert_error(
+            record.assert_cooled_down(600, 1_599),
+            GenomeError::RefundCooldown,
+        );
+        record.assert_cooled_down(600, 1_600).unwrap();
+        record.assert_cooled_down(0, 1_000).unwrap();
+
+        record.record_refund(record.buyer, record.seller, 2_000, 255);
+        assert_error(
+            record.assert_cooled_down(600, 2_001),
+            GenomeError::RefundCooldown,
+        );
+        RefundRecord::default().assert_cooled_down(600, 0).unwrap();
+    }
+
+    #[test]
+    fn provenance_is_bounded_and_validated() {
+        let accession = |source, id: &str| Accession {
+            source,
+            id: id.to_string(),
+        };
+        let mut genome = Genome::register(
+            Pubkey::new_unique(),
+            GenomeEntry {
+                provenance: vec![accession(ACCESSION_NCBI, "SRR1234567")],
+                ..Default::default()
+            },
+            0,
+            1,
+        )
+        .unwrap();
+
+        assert_error(
+            genome.add_provenance(vec![accession(ACCESSION_OTHER + 1, "x")]),
+            GenomeError::InvalidAccession,
+        );
+        assert_error(
+            genome.add_provenance(vec![accession(ACCESSION_EBI, "")]),
+            GenomeError::InvalidAccession,
+        );
+        assert_error(
+            genome.add_provenance(vec![accession(
+                ACCESSION_EBI,
+                &"E".repeat(MAX_ACCESSION_ID_LEN + 1),
+            )]),
+            GenomeError::StringTooLong,
+        );
+        genome
+            .add_provenance(vec![accession(ACCESSION_EBI, "ERR1"); MAX_PROVENANCE - 1])
+            .unwrap();
+        assert_error(
+            genome.add_provenance(vec![accession(ACCESSION_DDBJ, "DRR1")]),
+            GenomeError::TooManyAccessions,
+        );
+        assert_eq!(genome.provenance[0].id, "SRR1234567");
+    }
+
+    #[test]
+    fn sla_breaches_slash_collateral_once_per_lapse() {
+        let mut transaction = Transaction {
+            collateral_amount: 1_000,
+            ..Default::default()
+        };
+        assert_error(
+            transaction
+                .slash_for_breach(100, 600, 1_000, 700)
+                .map(|_| ()),
+            GenomeError::NoSlaBreach,
+        );
+        assert_eq!(
+            transaction.slash_for_breach(100, 600, 1_000, 701).unwrap(),
+            (100, 700)
+        );
+        assert_eq!(transaction.collateral_amount, 900);
+        assert_error(
+            transaction
+                .slash_for_breach(100, 600, 1_000, 900)
+                .map(|_| ()),
+            GenomeError::SlaAlreadyClaimed,
+        );
+
+        // A fresh heartbeat followed by a new lapse can be claimed again.
+        assert_eq!(
+            transaction
+                .slash_for_breach(800, 600, 1_000, 1_401)
+                .unwrap(),
+            (90, 1_400)
+        );
+        assert_error(
+            transaction
+                .slash_for_breach(800, 0, 1_000, 5_000)
+                .map(|_| ()),
+            GenomeError::SlaDisabled,
+        );
+    }
+
+    #[test]
+    fn purchase_check_reports_the_first_failing_gate() {
+        let mut config = Config::default();
+        let mut genome = Genome {
+            consent_active: true,
+            ..Default::default()
+        };
+        let transaction = Transaction {
+            listing_expires_at: 1_000,
+            ..Default::default()
+        };
+        let blacklist = Blacklist::default();
+        let reason = |config: &Config, genome: &Genome, now| {
+            PurchaseCheck::evaluate(config, genome, &transaction, &blacklist, now).reason
+        };
+
+        let check = PurchaseCheck::evaluate(&config, &genome, &transaction, &blacklist, 500);
+        assert!(check.purchasable);
+        assert_eq!(check.reason, PURCHASE_OK);
+        assert_eq!(reason(&config, &genome, 1_000), PURCHASE_LISTING_EXPIRED);
+
+        genome.consent_active = false;
+        assert_eq!(reason(&config, &genome, 500), PURCHASE_CONSENT_WITHDRAWN);
+        config.paused = true;
+        assert_eq!(reason(&config, &genome, 500), PURCHASE_PAUSED);
+    }
+
+    #[test]
+    fn withdrawn_consent_blocks_new_sales() {
+        let mut genome =
+            Genome::register(Pubkey::new_unique(), GenomeEntry::default(), 0, 1).unwrap();
+        genome.assert_consent().unwrap();
+
+        genome.consent_active = false;
+        assert_error(genome.assert_consent(), GenomeError::ConsentWithdrawn);
+        let successor = genome
+            .successor(
+                Pubkey::new_unique(),
+                "v2".to_string(),
+                String::new(),
+                [0; 32],
+                10,
+                1,
+            )
+            .unwrap();
+        assert_error(successor.assert_consent(), GenomeError::ConsentWithdrawn);
+    }
+
+    #[test]
+    fn conversion_fee_is_charged_on_top_and_kept_out_of_the_split() {
+        assert_eq!(with_conversion_fee(10_007, 50).unwrap(), (10_057, 50));
+        assert_eq!(with_conversion_fee(10_007, 0).unwrap(), (10_007, 0));
+        assert_error(
+            with_conversion_fee(u64::MAX, 1).map(|_| ()),
+            GenomeError::MathOverflow,
+        );
+
+        let config = Config {
+            fee_bps: 250,
+            ..Default::default()
+        };
+        let transaction = Transaction {
+            price: 10_057,
+            conversion_fee: 50,
+            ..Default::default()
+        };
+        let split = SaleSplit::for_sale(&config, &Genome::default(), &transaction).unwrap();
+        assert_eq!(split.fee, 250);
+        assert_eq!(split.conversion_fee, 50);
+        assert_eq!(split.total(), Some(10_057));
+    }
+
+    #[test]
+    fn recovery_reassigns_ownership_after_the_timelock() {
+        let owner = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let new_owner = Pubkey::new_unique();
+        let mut genome = Genome {
+            owner,
+            recovery_authority: Some(authority),
+            ..Default::default()
+        };
+        assert_error(
+            genome.initiate_recovery(Pubkey::new_unique(), new_owner, 0),
+            GenomeError::Unauthorized,
+        );
+
+        genome.initiate_recovery(authority, new_owner, 0).unwrap();
+        assert_error(
+            genome
+                .finalize_recovery(authority, RECOVERY_TIMELOCK - 1)
+                .map(|_| ()),
+            GenomeError::RecoveryTimelocked,
+        );
+        assert_eq!(
+            genome
+                .finalize_recovery(authority, RECOVERY_TIMELOCK)
+                .unwrap(),
+            owner
+        );
+        assert_eq!(genome.owner, new_owner);
+        assert!(genome.recovery_authority.is_none() && genome.pending_recovery.is_none());
+    }
+
+    #[test]
+    fn owner_can_cancel_a_pending_recovery() {
+        let authority = Pubkey::new_unique();
+        let mut genome = Genome {
+            owner: Pubkey::new_unique(),
+            recovery_authority: Some(authority),
+            ..Default::default()
+        };
+        let thief = Pubkey::new_unique();
+        genome.initiate_recovery(authority, thief, 0).unwrap();
+        assert_eq!(genome.cancel_recovery().unwrap().new_owner, thief);
+
+        assert_error(
+            genome
+                .finalize_recovery(authority, RECOVERY_TIMELOCK)
+                .map(|_| ()),
+            GenomeError::NoRecoveryPending,
+        );
+        assert_error(
+            genome.cancel_recovery().map(|_| ()),
+            GenomeError::NoRecoveryPending,
+        );
+    }
+
+    #[test]
+    fn offers_below_the_listing_floor_cannot_be_accepted() {
+        let genome = Genome {
+            active_listings: 1,
+            ..Default::default()
+        };
+        let listing = Transaction {
+            min_acceptable_price: 1_000,
+            ..Default::default()
+        };
+        assert_error(
+            assert_offer_floor(&genome, None, 5_000, None),
+            GenomeError::ListingRequired,
+        );
+        assert_error(
+            assert_offer_floor(&genome, Some(&listing), 999, None),
+            GenomeError::OfferBelowFloor,
+        );
+        assert_error(
+            assert_offer_floor(&genome, Some(&listing), 1_000, Some(Pubkey::new_unique())),
+            GenomeError::MintMismatch,
+        );
+        assert_offer_floor(&genome, Some(&listing), 1_000, None).unwrap();
+        assert_offer_floor(&Genome::default(), None, 1, None).unwrap();
+    }
+
+    #[test]
+    fn sale_split_gives_rounding_dust_to_the_seller() {
+        for price in [1, 7, 9_973, 10_007, 1_000_003] {
+            let split = SaleSplit::new(price, 333, 777)
+                .unwrap()
+                .with_referral(Some(Pubkey::new_unique()), 3_333)
+                .unwrap();
+            assert_eq!(split.total(), Some(price));
+            assert_eq!(split.fee + split.referral, apply_bps(price, 333).unwrap());
+            assert_eq!(split.royalty, apply_bps(price, 777).unwrap());
+        }
+
+        let split = SaleSplit::new(10_007, 250, 1_000).unwrap();
+        assert_eq!((split.fee, split.royalty), (250, 1_000));
+        assert_eq!(split.seller_amount, 8_757);
+    }
+
+    #[test]
+    fn access_log_spills_into_the_next_page() {
+        let mut genome = Genome::default();
+        let record = |buyer| AccessRecord {
+            buyer,
+            timestamp: 10,
+            access_level: ACCESS_READ_ONLY,
+        };
+        let mut log = AccessLog::default();
+        log.open(Pubkey::new_unique(), 0, 1);
+        let filler = vec![record(Pubkey::new_unique()); MAX_ACCESS_LOG_LEN - 1];
+        log_access(&mut genome, &mut log, None, filler).unwrap();
+        assert_eq!(genome.access_log_page, 0);
+
+        let batch = vec![record(Pubkey::new_unique()); 3];
+        assert_error(
+            log_access(&mut genome, &mut log.clone(), None, batch.clone()),
+            GenomeError::AccessLogFull,
+        );
+        let mut next = AccessLog::default();
+        next.open(log.genome, 1, 1);
+        log_access(&mut genome, &mut log, Some(&mut next), batch).unwrap();
+        assert!(log.is_full());
+        assert_eq!(next.records.len(), 2);
+        assert_eq!(genome.access_log_page, 1);
+    }
+
+    #[test]
+    fn only_settled_terminal_transactions_can_be_closed() {
+        let mut transaction = Transaction::default();
+        assert_error(
+            transaction.assert_closable(),
+            GenomeError::InvalidTransactionStatus,
+        );
+        transaction.status = TransactionStatus::Locked;
+        assert_error(
+            transaction.assert_closable(),
+            GenomeError::InvalidTransactionStatus,
+        );
+
+        transaction.status = TransactionStatus::Executed;
+        assert_error(transaction.assert_closable(), GenomeError::EscrowNotEmpty);
+        transaction.settled = true;
+        transaction.assert_closable().unwrap();
+
+        transaction.status = TransactionStatus::Cancelled;
+        transaction.collateral_amount = 10;
+        assert_error(transaction.assert_closable(), GenomeError::EscrowNotEmpty);
+    }
+
+    #[test]
+    fn keeper_relists_expired_listings_a_bounded_number_of_times() {
+        let mut transaction = Transaction {
+            created_at: 1_000,
+            listing_expires_at: 2_000,
+            auto_relist: true,
+            ..Default::default()
+        };
+        assert_error(
+            transaction.auto_relist(1_999),
+            GenomeError::ListingNotExpired,
+        );
+
+        transaction.auto_relist(2_500).unwrap();
+        assert_eq!(transaction.created_at, 2_500);
+        assert_eq!(transaction.listing_expires_at, 3_500);
+        assert_eq!(transaction.auto_relists, 1);
+
+        transaction.auto_relists = MAX_AUTO_RELISTS;
+        assert_error(
+            transaction.auto_relist(4_000),
+            GenomeError::AutoRelistLimitReached,
+        );
+        transaction.auto_relist = false;
+        assert_error(
+            transaction.auto_relist(4_000),
+            GenomeError::AutoRelistDisabled,
+        );
+    }
+
+    #[test]
+    fn reputation_gate_rejects_unrated_and_low_rated_buyers() {
+        let transaction = Transaction {
+            min_buyer_reputation: 400,
+            ..Default::default()
+        };
+        let mut profile = SellerProfile::default();
+        assert_error(
+            transaction.assert_buyer_reputation(None),
+            GenomeError::BuyerReputationTooLow,
+        );
+        assert_error(
+            transaction.assert_buyer_reputation(Some(&profile)),
+            GenomeError::BuyerReputationTooLow,
+        );
+
+        profile.record_rating(5).unwrap();
+        profile.record_rating(2).unwrap();
+        assert_eq!(profile.reputation(), 350);
+        assert_error(
+            transaction.assert_buyer_reputation(Some(&profile)),
+            GenomeError::BuyerReputationTooLow,
+        );
+        profile.record_rating(5).unwrap();
+        transaction.assert_buyer_reputation(Some(&profile)).unwrap();
+        Transaction::default()
+            .assert_buyer_reputation(None)
+            .unwrap();
+    }
+
+    #[test]
+    fn cohort_grants_cover_each_buyer_once() {
+        let genome = Pubkey::new_unique();
+        let buyers = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+
+        let grants = cohort_grants(genome, &buyers, 1_000, 500).unwrap();
+        assert_eq!(grants.len(), 3);
+        for (grant, buyer) in grants.iter().zip(&buyers) {
+            assert_eq!(grant.genome, genome);
+            assert_eq!(grant.buyer, *buyer);
+            assert_eq!(grant.expires_at, 1_500);
+            grant.assert_active(1_500).unwrap();
+        }
+
+        assert_error(
+            cohort_grants(genome, &[buyers[0], buyers[1], buyers[0]], 1_000, 500).map(|_| ()),
+            GenomeError::DuplicateBuyer,
+        );
+        assert_error(
+            cohort_grants(genome, &[], 1_000, 500).map(|_| ()),
+            GenomeError::InvalidBatchSize,
+        );
+        assert_error(
+            cohort_grants(genome, &buyers, 1_000, 0).map(|_| ()),
+            GenomeError::InvalidDuration,
+        );
+    }
+
+    #[test]
+    fn retried_listing_nonce_maps_to_the_same_account() {
+        let genome = Pubkey::new_unique();
+        let seller = Pubkey::new_unique();
+
+        let first = transaction_address(&genome, &seller, 7);
+        assert_eq!(transaction_address(&genome, &seller, 7), first);
+        assert_ne!(transaction_address(&genome, &seller, 8), first);
+        assert_ne!(
+            transaction_address(&genome, &Pubkey::new_unique(), 7),
+            first
+        );
+    }
+
+    #[test]
+    fn encrypted_metadata_is_bounded() {
+        let mut genome = Genome::default();
+        genome
+            .apply_patch(GenomePatch {
+                encrypted_metadata: Some(vec![7; MAX_ENCRYPTED_METADATA_LEN]),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(genome.encrypted_metadata.len(), MAX_ENCRYPTED_METADATA_LEN);
+
+        assert_error(
+            genome.apply_patch(GenomePatch {
+                encrypted_metadata: Some(vec![7; MAX_ENCRYPTED_METADATA_LEN + 1]),
+                ..Default::default()
+            }),
+            GenomeError::EncryptedMetadataTooLong,
+        );
+    }
+
+    #[test]
+    fn lapsed_heartbeat_blocks_purchases_until_refreshed() {
+        let mut genome = Genome {
+            last_heartbeat: 1_000,
+            ..Default::default()
+        };
+        genome.assert_heartbeat_fresh(600, 1_600).unwrap();
+        assert_error(
+            genome.assert_heartbeat_fresh(600, 1_601),
+            GenomeError::HeartbeatStale,
+        );
+        genome.assert_heartbeat_fresh(0, 1_000_000).unwrap();
+
+        genome.last_heartbeat = 1_601;
+        genome.assert_heartbeat_fresh(600, 1_601).unwrap();
+    }
+
+    #[test]
+    fn outdated_clients_are_rejected() {
+        let mut config = Config {
+            version: PROGRAM_VERSION,
+            ..Default::default()
+        };
+        config.assert_client_version(0).unwrap();
+
+        config.min_client_version = PROGRAM_VERSION;
+        assert_error(config.assert_client_version(0), GenomeError::ClientTooOld);
+        config.assert_client_version(PROGRAM_VERSION).unwrap();
+    }
+
+    #[test]
+    fn accepting_one_of_three_offers_rejects_the_others() {
+        let genome = Pubkey::new_unique();
+        let keys = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let offers = keys.map(|_| Offer {
+            genome,
+            amount: 100,
+            ..Default::default()
+        });
+        let accepted = keys[1];
+
+        let refunded: Vec<_> = keys
+            .iter()
+            .zip(&offers)
+            .filter(|(key, offer)| offer.assert_rejectable(**key, genome, accepted).is_ok())
+            .map(|(key, _)| *key)
+            .collect();
+        assert_eq!(refunded, vec![keys[0], keys[2]]);
+        assert_error(
+            offers[1].assert_rejectable(accepted, genome, accepted),
+            GenomeError::InvalidBatchAccounts,
+        );
+        assert_error(
+            offers[0].assert_rejectable(keys[0], Pubkey::new_unique(), accepted),
+            GenomeError::GenomeMismatch,
+        );
+    }
+
+    #[test]
+    fn listing_terms_reject_nonsensical_durations() {
+        let terms = ListingTerms {
+            price: 1,
+            access_duration: 60,
+            listing_expires_at: 100,
+            max_licenses: 1,
+            ..Default::default()
+        };
+        terms.validate(0).unwrap();
+
+        for access_duration in [0, -60, i64::MAX] {
+            assert_error(
+                ListingTerms {
+                    access_duration,
+                    ..terms.clone()
+                }
+                .validate(0),
+                GenomeError::InvalidDuration,
+            );
+        }
+        assert_error(
+            ListingTerms {
+                listing_expires_at: 0,
+                ..terms
+            }
+            .validate(0),
+            GenomeError::InvalidDuration,
+        );
+    }
+
+    #[test]
+    fn pass_revenue_is_shared_pro_rata_among_pool_genomes() {
+        let mut pool = SubscriptionPool::default();
+        let mut early = Genome::default();
+        let mut late = Genome::default();
+        assert_error(pool.record_sale(300), GenomeError::EmptySubscriptionPool);
+
+        pool.join(&mut early).unwrap();
+        assert_error(
+            pool.join(&mut early),
+            GenomeError::AlreadyInSubscriptionPool,
+        );
+        pool.record_sale(300).unwrap();
+        pool.join(&mut late).unwrap();
+        pool.record_sale(300).unwrap();
+
+        assert_eq!(pool.settle(&mut early).unwrap(), 450);
+        assert_eq!(pool.settle(&mut early).unwrap(), 0);
+        assert_eq!(pool.leave(&mut late).unwrap(), 150);
+        assert_eq!(pool.participants, 1);
+
+        let mut pass = SubscriptionPass::default();
+        pass.extend(1_000, 500).unwrap();
+        pass.assert_covers(&early, 1_500).unwrap();
+        assert_error(
+            pass.assert_covers(&late, 1_200),
+            GenomeError::NotInSubscriptionPool,
+        );
+        assert_error(
+            pass.assert_covers(&early, 1_501),
+            GenomeError::AccessExpired,
+        );
+    }
+
+    #[test]
+    fn earnings_cannot_be_overdrawn() {
+        let mut earnings = Earnings::default();
+        earnings.accrue(300).unwrap();
+        earnings.withdraw(100).unwrap();
+        assert_eq!(earnings.balance, 200);
+        assert_error(earnings.withdraw(201), GenomeError::InsufficientEarnings);
+        assert_error(earnings.withdraw(0), GenomeError::InvalidAmount);
+        earnings.withdraw(200).unwrap();
+        assert_eq!(earnings.total_accrued, 300);
+    }
+
+    #[test]
+    fn expired_listing_cannot_be_bought() {
+        let transaction = Transaction {
+            price: 100,
+            listing_expires_at: 1_000,
+            ..Default::default()
+        };
+        transaction.assert_purchasable(999).unwrap();
+        assert_error(
+            transaction.assert_purchasable(1_000),
+            GenomeError::ListingExpired,
+        );
+        assert_error(
+            transaction.assert_purchasable(5_000),
+            GenomeError::ListingExpired,
+        );
+    }
+
+    #[test]
+    fn listing_notes_are_bounded_and_carried_to_relistings() {
+        let mut terms = ListingTerms {
+            price: 100,
+            access_duration: 60,
+            listing_expires_at: 1_000,
+            max_licenses: 1,
+            listing_notes: "includes phenotype annotations".to_string(),
+            ..Default::default()
+        };
+        terms.validate(0).unwrap();
+
+        let mut transaction = Transaction::default();
+        transaction.apply_terms(terms.clone());
+        transaction.status = TransactionStatus::Cancelled;
+        let relisted = transaction.relisting_terms(200, 60, 0).unwrap();
+        assert_eq!(relisted.listing_notes, "includes phenotype annotations");
+
+        terms.listing_notes = "x".repeat(MAX_LISTING_NOTES_LEN + 1);
+        assert_error(terms.validate(0), GenomeError::StringTooLong);
+    }
+
+    #[test]
+    fn grant_quota_runs_out() {
+        let mut quota = GrantQuota {
+            remaining: 2,
+            ..Default::default()
+        };
+        quota.consume().unwrap();
+        quota.consume().unwrap();
+        assert_eq!(quota.remaining, 0);
+        assert_error(quota.consume(), GenomeError::GrantQuotaExhausted);
+    }
+
+    #[test]
+    fn sealed_metadata_is_revealed_only_against_its_hash() {
+        let mut genome = Genome {
+            metadata: "{\"cohort\":\"trial-7\"}".to_string(),
+            ..Default::default()
+        };
+        let metadata_hash = hash(genome.metadata.as_bytes()).to_bytes();
+
+        assert_error(
+            genome.seal(1_000, metadata_hash, 1_000),
+            GenomeError::InvalidDuration,
+        );
+        genome.seal(2_000, metadata_hash, 1_000).unwrap();
+        assert!(genome.metadata.is_empty());
+        assert!(genome.embargoed(1_999));
+        assert!(!genome.embargoed(2_000));
+        assert_error(
+            genome.assert_not_embargoed(1_500),
+            GenomeError::GenomeEmbargoed,
+        );
+        assert_error(
+            genome.apply_patch(GenomePatch {
+                metadata: Some("{}".to_string()),
+                ..Default::default()
+            }),
+            GenomeError::GenomeEmbargoed,
+        );
+
+        assert_error(
+            genome.unseal("{}".to_string()),
+            GenomeError::SealedMetadataMismatch,
+        );
+        genome
+            .unseal("{\"cohort\":\"trial-7\"}".to_string())
+            .unwrap();
+        assert_eq!(genome.embargo_until, None);
+        assert_error(genome.unseal(String::new()), GenomeError::NotEmbargoed);
+    }
+
+    #[test]
+    fn listing_extension_stays_within_max_duration() {
+        let mut transaction = Transaction {
+            created_at: 1_000,
+            listing_expires_at: 2_000,
+            ..Default::default()
+        };
+        assert_error(
+            transaction.extend_listing(-1, 5_000),
+            GenomeError::InvalidDuration,
+        );
+        assert_error(
+            transaction.extend_listing(i64::MAX, 5_000),
+            GenomeError::MathOverflow,
+        );
+        assert_error(
+            transaction.extend_listing(4_001, 5_000),
+            GenomeError::ListingDurationOutOfBounds,
+        );
+
+        transaction.extend_listing(4_000, 5_000).unwrap();
+        assert_eq!(transaction.listing_expires_at, 6_000);
+    }
+
+    #[test]
+    fn transferred_genome_waits_out_resale_lock() {
+        let mut genome = Genome::default();
+        genome.assert_resale_unlocked(3_600, 100).unwrap();
+
+        genome.acquired_at = 1_000;
+        assert_error(
+            genome.assert_resale_unlocked(3_600, 2_000),
+            GenomeError::ResaleLocked,
+        );
+        genome.assert_resale_unlocked(3_600, 4_600).unwrap();
+        genome.assert_resale_unlocked(0, 1_000).unwrap();
+    }
+
+    #[test]
+    fn referral_comes_out_of_the_fee_and_excludes_the_parties() {
+        let seller = Pubkey::new_unique();
+        let buyer = Pubkey::new_unique();
+        let mut transaction = Transaction {
+            seller,
+            ..Default::default()
+        };
+        assert_error(
+            transaction.set_referrer(Some(buyer), buyer),
+            GenomeError::SelfReferral,
+        );
+        assert_error(
+            transaction.set_referrer(Some(seller), buyer),
+            GenomeError::SelfReferral,
+        );
+        let referrer = Pubkey::new_unique();
+        transaction.set_referrer(Some(referrer), buyer).unwrap();
+        assert_eq!(transaction.referrer, Some(referrer));
+
+        let split = SaleSplit::new(10_000, 500, 0)
+            .unwrap()
+            .with_referral(Some(referrer), 2_000)
+            .unwrap();
+        assert_eq!(split.referral, 100);
+        assert_eq!(split.fee, 400);
+        assert_eq!(split.seller_amount, 9_500);
+
+        let unreferred = SaleSplit::new(10_000, 500, 0)
+            .unwrap()
+            .with_referral(None, 2_000)
+            .unwrap();
+        assert_eq!(unreferred.referral, 0);
+        assert_eq!(unreferred.fee, 500);
+    }
+
+    #[test]
+    fn conversion_must_change_format_and_pays_out_once() {
+        let mut grant = AccessGrant::default();
+        assert_error(
+            grant.request_conversion(FORMAT_FASTQ, FORMAT_FASTQ, 100),
+            GenomeError::SameFormat,
+        );
+        assert_error(
+            grant.fulfill_conversion([1; 32]).map(|_| ()),
+            GenomeError::NoConversionRequested,
+        );
+
+        grant
+            .request_conversion(FORMAT_FASTQ, FORMAT_BAM, 100)
+            .unwrap();
+        assert_error(
+            grant.request_conversion(FORMAT_FASTQ, FORMAT_CRAM, 100),
+            GenomeError::ConversionPending,
+        );
+
+        assert_eq!(grant.fulfill_conversion([1; 32]).unwrap(), 100);
+        assert_eq!(grant.conversion_hash, [1; 32]);
+        assert!(!grant.conversion_requested);
+    }
+
+    #[test]
+    fn delivery_is_confirmed_only_after_key_delivery() {
+        let mut access_grant = AccessGrant {
+            expires_at: 1_000,
+            ..Default::default()
+        };
+
+        assert_error(
+            access_grant.confirm_delivery([1; 32], 10),
+            GenomeError::KeyNotDelivered,
+        );
+
+        access_grant.deliver_key(vec![7; 32], 0).unwrap();
+        access_grant.confirm_delivery([1; 32], 10).unwrap();
+        assert!(access_grant.key_status == KeyStatus::Delivered);
+        assert_eq!(access_grant.delivery_hash, [1; 32]);
+        assert_eq!(access_grant.delivered_at, 10);
+        assert!(access_grant.status(10).key_delivered);
+    }
+
+    #[test]
+    fn auto_renewal_is_due_only_near_expiry() {
+        let mut access_grant = AccessGrant {
+            expires_at: 10 * RENEWAL_WINDOW,
+            auto_renew: true,
+            ..Default::default()
+        };
+
+        assert!(!access_grant.renewal_due(8 * RENEWAL_WINDOW));
+        assert!(access_grant.renewal_due(9 * RENEWAL_WINDOW));
+        assert!(!access_grant.renewal_due(10 * RENEWAL_WINDOW + 1));
+
+        access_grant.auto_renew = false;
+        assert!(!access_grant.renewal_due(9 * RENEWAL_WINDOW));
+    }
+
+    #[test]
+    fn epoch_volume_cap_resets_each_epoch() {
+        let mut stats = MarketStats::default();
+        stats.record_epoch_volume(5, 600, 1_000).unwrap();
+        assert_error(
+            stats.record_epoch_volume(5, 500, 1_000),
+            GenomeError::EpochVolumeCapReached,
+        );
+        assert_eq!(stats.epoch_volume, 600);
+
+        stats.record_epoch_volume(6, 500, 1_000).unwrap();
+        assert_eq!(stats.epoch_volume, 500);
+        stats.record_epoch_volume(6, u64::MAX / 2, 0).unwrap();
+    }
+
+    #[test]
+    fn market_stats_saturate_instead_of_failing() {
+        let mut stats = MarketStats {
+            total_volume: u64::MAX - 1,
+            ..Default::default()
+        };
+
+        stats.record_closed_listing();
+        assert_eq!(stats.active_listings, 0);
+
+        stats.record_listing();
+        stats.record_sale(10);
+        stats.record_closed_listing();
+        stats.record_genomes(3);
+        assert_eq!(stats.total_transactions, 1);
+        assert_eq!(stats.total_volume, u64::MAX);
+        assert_eq!(stats.active_listings, 0);
+        assert_eq!(stats.total_genomes, 3);
+    }
+
+    #[test]
+    fn revoked_grant_fails_before_expiry() {
+        let mut access_grant = AccessGrant {
+            granted_at: 100,
+            expires_at: 1_000,
+            ..Default::default()
+        };
+
+        assert!(access_grant.assert_active(500).is_ok());
+        access_grant.revoked = true;
+        assert_error(access_grant.assert_active(500), GenomeError::AccessRevoked);
+    }
+
+    #[test]
+    fn revocation_takes_effect_after_grace_period() {
+        let mut access_grant = AccessGrant {
+            expires_at: 1_000,
+            ..Default::default()
+        };
+
+        assert_eq!(access_grant.revoke(500, 100).unwrap(), 600);
+        assert!(access_grant.assert_active(599).is_ok());
+        assert_error(access_grant.assert_active(600), GenomeError::AccessRevoked);
+
+        access_grant.revoke(500, 0).unwrap();
+        assert_error(access_grant.assert_active(500), GenomeError::AccessRevoked);
+    }
+
+    #[test]
+    fn private_sales_only_admit_approved_buyers() {
+        let approved = Pubkey::new_unique();
+        let stranger = Pubkey::new_unique();
+
+        let private = Transaction {
+            allowed_buyer: Some(approved),
+            ..Default::default()
+        };
+        assert!(private.assert_buyer_allowed(approved, None).is_ok());
+        assert_error(
+            private.assert_buyer_allowed(stranger, None),
+            GenomeError::BuyerNotAllowed,
+        );
+
+        let mut whitelist = Whitelist::default();
+        whitelist.add(approved).unwrap();
+        whitelist.add(approved).unwrap();
+        assert_eq!(whitelist.buyers.len(), 1);
+
+        let gated = Transaction {
+            whitelist: Some(Pubkey::new_unique()),
+            ..Default::default()
+        };
+        assert!(gated
+            .assert_buyer_allowed(approved, Some(&whitelist))
+            .is_ok());
+        assert_error(
+            gated.assert_buyer_allowed(stranger, Some(&whitelist)),
+            GenomeError::BuyerNotAllowed,
+        );
+        assert_error(
+            gated.assert_buyer_allowed(approved, None),
+            GenomeError::BuyerNotAllowed,
+        );
+
+        whitelist.remove(approved);
+        assert_error(
+            gated.assert_buyer_allowed(approved, Some(&whitelist)),
+            GenomeError::BuyerNotAllowed,
+        );
+    }
+
+    #[test]
+    fn key_delivery_is_bounded_and_marks_grant_ready() {
+        let mut access_grant = AccessGrant {
+            expires_at: 1_000,
+            ..Default::default()
+        };
+
+        assert_error(
+            access_grant.deliver_key(vec![0; MAX_ENCRYPTED_KEY_LEN + 1], 0),
+            GenomeError::InvalidKey,
+        );
+        assert!(access_grant.key_status == KeyStatus::AwaitingKey);
+
+        access_grant
+            .deliver_key(vec![7; MAX_ENCRYPTED_KEY_LEN], 0)
+            .unwrap();
+        assert!(access_grant.key_status == KeyStatus::KeyDelivered);
+
+        access_grant.revoked = true;
+        assert_error(
+            access_grant.deliver_key(vec![7; 32], 0),
+            GenomeError::AccessRevoked,
+        );
+    }
+
+    #[test]
+    fn early_cancellation_refunds_unused_time_pro_rata() {
+        let access_grant = AccessGrant {
+            granted_at: 0,
+            expires_at: 100,
+            ..Default::default()
+        };
+
+        assert_eq!(access_grant.unused_refund(1_000, 100, 0).unwrap(), 1_000);
+        assert_eq!(access_grant.unused_refund(1_000, 100, 50).unwrap(), 500);
+        assert_eq!(access_grant.unused_refund(1_000, 100, 67).unwrap(), 330);
+        assert_eq!(
+            access_grant.unused_refund(1_000, 100, 101).unwrap_err(),
+            anchor_lang::error::Error::from(GenomeError::AccessExpired)
+        );
+    }
+
+    #[test]
+    fn renewal_extends_from_expiry_or_now() {
+        let mut access_grant = AccessGrant {
+            granted_at: 0,
+            expires_at: 1_000,
+            ..Default::default()
+        };
+
+        assert_eq!(access_grant.renew(500, 100).unwrap(), 1_100);
+        assert_eq!(access_grant.renew(2_000, 100).unwrap(), 2_100);
+
+        access_grant.revoked = true;
+        assert_eq!(
+            access_grant.renew(2_000, 100).unwrap_err(),
+            anchor_lang::error::Error::from(GenomeError::AccessRevoked)
+        );
+    }
+
+    #[test]
+    fn grant_is_reissued_only_once_the_license_has_ended() {
+        AccessGrant::default().reissue(0).unwrap();
+
+        let mut access_grant = AccessGrant {
+            buyer: Pubkey::new_unique(),
+            expires_at: 1_000,
+            ..Default::default()
+        };
+        assert_error(
+            access_grant.reissue(1_000),
+            GenomeError::AccessAlreadyGranted,
+        );
+        access_grant.revoked = true;
+        access_grant.conversion_requested = true;
+        assert_error(access_grant.reissue(500), GenomeError::ConversionPending);
+
+        access_grant.conversion_requested = false;
+        access_grant.reissue(500).unwrap();
+        assert!(!access_grant.revoked);
+        assert_eq!(access_grant.buyer, Pubkey::default());
+    }
+
+    #[test]
+    fn blacklisted_seller_cannot_list() {
+        let seller = Pubkey::new_unique();
+        let mut blacklist = Blacklist::default();
+
+        assert!(blacklist.assert_allowed(&seller).is_ok());
+
+        blacklist.add(seller).unwrap();
+        blacklist.add(seller).unwrap();
+        assert_eq!(blacklist.sellers.len(), 1);
+        assert_error(
+            blacklist.assert_allowed(&seller),
+            GenomeError::SellerBlacklisted,
+        );
+        assert!(blacklist.assert_allowed(&Pubkey::new_unique()).is_ok());
+
+        blacklist.remove(seller);
+        assert!(blacklist.assert_allowed(&seller).is_ok());
+    }
+
+    #[test]
+    fn user_index_chains_to_a_new_page_when_full() {
+        let owner = Pubkey::new_unique();
+        let mut first = UserIndex::default();
+        first.open(owner, 0, 1, None).unwrap();
+        for _ in 0..MAX_USER_INDEX_LEN {
+            first.push(Pubkey::new_unique()).unwrap();
+        }
+        assert_error(first.push(Pubkey::new_unique()), GenomeError::UserIndexFull);
+
+        assert_error(
+            UserIndex::default().open(owner, 1, 1, None),
+            GenomeError::InvalidIndexPage,
+        );
+        assert_error(
+            UserIndex::default().open(owner, 2, 1, Some(&first)),
+            GenomeError::InvalidIndexPage,
+        );
+        let mut second = UserIndex::default();
+        second.open(owner, 1, 1, Some(&first)).unwrap();
+        assert_eq!(second.page, 1);
+
+        let removed = first.genomes[3];
+        first.remove(&removed).unwrap();
+        assert!(!first.genomes.contains(&removed));
+        assert_error(first.remove(&removed), GenomeError::GenomeNotIndexed);
+    }
+
+    #[test]
+    fn buyer_history_skips_repeated_purchases_and_fills_up() {
+        let mut history = BuyerHistory::default();
+        history.open(Pubkey::new_unique(), 0, 1, None).unwrap();
+        let purchase = |transaction, expires_at| PurchaseRecord {
+            genome: Pubkey::new_unique(),
+            transaction,
+            price: 100,
+            purchased_at: 10,
+            expires_at,
+        };
+
+        let transaction = Pubkey::new_unique();
+        history.record(purchase(transaction, 50)).unwrap();
+        history.record(purchase(transaction, 90)).unwrap();
+        assert_eq!(history.records.len(), 1);
+        assert_eq!(history.records[0].expires_at, 90);
+
+        for _ in 1..MAX_BUYER_HISTORY_LEN {
+            history.record(purchase(Pubkey::new_unique(), 50)).unwrap();
+        }
+        assert_error(
+            history.record(purchase(Pubkey::new_unique(), 50)),
+            GenomeError::BuyerHistoryFull,
+        );
+    }
+
+    #[test]
+    fn listing_terms_reject_unknown_access_levels() {
+        let mut terms = ListingTerms {
+            price: 100,
+            access_duration: 60,
+            listing_expires_at: 1_000,
+            max_licenses: 1,
+            access_level: ACCESS_REDISTRIBUTE,
+            ..Default::default()
+        };
+        terms.validate(0).unwrap();
+
+        let mut transaction = Transaction::default();
+        transaction.apply_terms(terms.clone());
+        assert_eq!(transaction.access_level, ACCESS_REDISTRIBUTE);
+
+        terms.access_level = ACCESS_REDISTRIBUTE + 1;
+        assert_error(terms.validate(0), GenomeError::InvalidAccessLevel);
+    }
+
+    #[test]
+    fn collateral_backs_exclusive_listings_only() {
+        let mut transaction = Transaction {
+            price: 1_000,
+            max_licenses: 1,
+            ..Default::default()
+        };
+        assert_eq!(transaction.required_collateral(500).unwrap(), 50);
+
+        transaction.collateral_amount = 50;
+        assert_eq!(transaction.release_collateral(), 50);
+        assert_eq!(transaction.release_collateral(), 0);
+
+        transaction.max_licenses = 10;
+        assert_eq!(transaction.required_collateral(500).unwrap(), 0);
+    }
+
+    #[test]
+    fn listing_cooldown_spaces_out_a_sellers_listings() {
+        let mut profile = SellerProfile::default();
+        profile.start_listing(1_000, 60).unwrap();
+
+        assert_error(
+            profile.start_listing(1_030, 60),
+            GenomeError::ListingCooldown,
+        );
+        profile.start_listing(1_060, 60).unwrap();
+        assert_eq!(profile.last_listed_at, 1_060);
+
+        profile.start_listing(1_060, 0).unwrap();
+    }
+
+    #[test]
+    fn seller_stats_track_sales_listings_and_tips() {
+        let mut profile = SellerProfile::default();
+        profile.open(Pubkey::new_unique(), 254);
+
+        profile.record_listing().unwrap();
+        profile.record_listing().unwrap();
+        profile.record_sale(100).unwrap();
+        profile.record_closed_listing();
+        profile.record_sale(250).unwrap();
+
+        let stats = profile.stats();
+        assert_eq!(stats.total_sales, 2);
+        assert_eq!(stats.total_volume, 350);
+        assert_eq!(stats.active_listings, 1);
+
+        profile.record_closed_listing();
+        profile.record_closed_listing();
+        assert_eq!(profile.active_listings, 0);
+
+        profile.record_tip(40).unwrap();
+        profile.record_tip(2).unwrap();
+        assert_eq!(profile.stats().tips_received, 42);
+        profile.tips_received = u64::MAX;
+        assert_error(profile.record_tip(1), GenomeError::MathOverflow);
+    }
+
+    #[test]
+    fn usd_price_converts_at_oracle_rate() {
+        // $10 of SOL at $25/SOL, Pyth-style price with eight decimals.
+        assert_eq!(
+            usd_to_token_amount(10_000_000, 2_500_000_000, -8, 9).unwrap(),
+            400_000_000
+        );
+        // $10 of a USDC-like token at par.
+        assert_eq!(
+            usd_to_token_amount(10_000_000, 100_000_000, -8, 6).unwrap(),
+            10_000_000
+        );
+        assert_eq!(
+            usd_to_token_amount(10_000_000, 0, -8, 9).unwrap_err(),
+            anchor_lang::error::Error::from(GenomeError::InvalidOracle)
+        );
+    }
+
+    #[test]
+    fn bundle_needs_two_to_eight_distinct_genomes() {
+        let ids: Vec<String> = (0..MAX_BUNDLE_SIZE + 1)
+            .map(|_| Pubkey::new_unique().to_string())
+            .collect();
+
+        assert!(validate_bundle(&ids[..2]).is_ok());
+        assert!(validate_bundle(&ids[..MAX_BUNDLE_SIZE]).is_ok());
+        assert_error(validate_bundle(&ids[..1]), GenomeError::InvalidBundle);
+        assert_error(validate_bundle(&ids), GenomeError::InvalidBundle);
+        let duplicated = vec![ids[0].clone(), ids[1].clone(), ids[0].clone()];
+        assert_error(validate_bundle(&duplicated), GenomeError::InvalidBundle);
+    }
+
+    #[test]
+    fn sealed_bid_reveals_only_in_window_with_matching_nonce() {
+        let transaction = Transaction {
+            listing_expires_at: 100,
+            auction: Some(Auction {
+                reveal_ends_at: Some(160),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let nonce = [9; 32];
+        let mut bid = Bid {
+            commitment: Some(bid_commitment(50, &nonce)),
+            ..Default::default()
+        };
+
+        assert_error(
+            transaction.assert_reveal_open(99),
+            GenomeError::RevealNotOpen,
+        );
+        assert!(transaction.assert_reveal_open(100).is_ok());
+        assert!(!transaction.bidding_closed(159));
+        assert_error(
+            transaction.assert_reveal_open(160),
+            GenomeError::RevealNotOpen,
+        );
+        assert!(transaction.bidding_closed(160));
+
+        assert_error(bid.reveal(51, &nonce), GenomeError::CommitmentMismatch);
+        assert_error(bid.reveal(50, &[8; 32]), GenomeError::CommitmentMismatch);
+        bid.reveal(50, &nonce).unwrap();
+        assert_eq!(bid.amount, 50);
+        assert_eq!(bid.commitment, None);
+    }
+
+    #[test]
+    fn genome_quota_is_enforced_and_freed_by_deletion() {
+        let mut head = UserIndex::default();
+
+        head.record_genomes(2, 3).unwrap();
+        assert_error(head.record_genomes(2, 3), GenomeError::GenomeLimitReached);
+        head.record_genomes(1, 3).unwrap();
+        assert_error(head.record_genomes(1, 3), GenomeError::GenomeLimitReached);
+
+        head.release_genome();
+        head.record_genomes(1, 3).unwrap();
+        head.record_genomes(10, 0).unwrap();
+        assert_eq!(head.genome_count, 13);
+    }
+
+    #[test]
+    fn access_status_reports_remaining_time_and_expiry() {
+        let mut grant = AccessGrant {
+            expires_at: 1_000,
+            key_status: KeyStatus::KeyDelivered,
+            ..Default::default()
+        };
+
+        let status = grant.status(400);
+        assert!(status.valid && status.key_delivered && !status.revoked);
+        assert_eq!(status.remaining_seconds, 600);
+        assert_eq!(grant.status(1_000).remaining_seconds, 0);
+
+        let expired = grant.status(1_001);
+        assert!(!expired.valid);
+        assert_eq!(expired.remaining_seconds, 0);
+
+        grant.revoked = true;
+        let revoked = grant.status(400);
+        assert!(!revoked.valid && revoked.revoked);
+        assert_eq!(revoked.remaining_seconds, 0);
+    }
+
+    #[test]
+    fn bps_math_holds_near_u64_max() {
+        assert_eq!(apply_bps(u64::MAX, 10_000).unwrap(), u64::MAX);
+        assert_eq!(apply_bps(u64::MAX, 250).unwrap(), u64::MAX / 40);
+        assert_eq!(
+            apply_bps(u64::MAX, 10_001).unwrap_err(),
+            anchor_lang::error::Error::from(GenomeError::MathOverflow)
+        );
+
+        let split = SaleSplit::new(u64::MAX, 250, 1_000).unwrap();
+        assert_eq!(split.fee + split.royalty + split.seller_amount, u64::MAX);
+
+        let share = |share_bps| PayoutShare {
+            recipient: Pubkey::new_unique(),
+            share_bps,
+        };
+        let parts = split_by_shares(&[share(3_333), share(6_667)], u64::MAX).unwrap();
+        assert_eq!(
+            parts.iter().map(|p| u128::from(*p)).sum::<u128>(),
+            u128::from(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn payout_split_shares_total_and_divide_price() {
+        let share = |share_bps| PayoutShare {
+            recipient: Pubkey::new_unique(),
+            share_bps,
+        };
+        let shares = vec![share(5_000), share(3_333), share(1_667)];
+
+        assert!(validate_payout_shares(&shares).is_ok());
+        assert_eq!(
+            split_by_shares(&shares, 1_001).unwrap(),
+            vec![502, 333, 166]
+        );
+        assert_eq!(
+            split_by_shares(&shares, 1_001).unwrap().iter().sum::<u64>(),
+            1_001
+        );
+
+        assert_error(
+            validate_payout_shares(&shares[..2]),
+            GenomeError::InvalidPayoutSplit,
+        );
+        let duplicated = vec![shares[0], shares[0]];
+        assert_error(
+            validate_payout_shares(&duplicated),
+            GenomeError::InvalidPayoutSplit,
+        );
+        let crowded: Vec<PayoutShare> = (0..MAX_PAYOUT_RECIPIENTS + 1).map(|_| share(1)).collect();
+        assert_error(
+            validate_payout_shares(&crowded),
+            GenomeError::TooManyPayoutRecipients,
+        );
+    }
+
+    #[test]
+    fn category_pages_chain_once_full() {
+        let mut first = CategoryIndex::default();
+        first.open(GenomeCategory::Microbial, 0, 255, None).unwrap();
+
+        let mut second = CategoryIndex::default();
+        assert_error(
+            second.open(GenomeCategory::Microbial, 1, 255, Some(&first)),
+            GenomeError::InvalidIndexPage,
+        );
+
+        let listings: Vec<Pubkey> = (0..MAX_CATEGORY_INDEX_LEN)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+        for listing in &listings {
+            first.push(*listing).unwrap();
+        }
+        assert_error(
+            first.push(Pubkey::new_unique()),
+            GenomeError::CategoryIndexFull,
+        );
+        second
+            .open(GenomeCategory::Microbial, 1, 255, Some(&first))
+            .unwrap();
+
+        first.remove(&listings[3]).unwrap();
+        assert!(!first.is_full());
+        assert_error(
+            first.remove(&listings[3]),
+            GenomeError::CategoryIndexMismatch,
+        );
+    }
+
+    #[test]
+    fn migration_resizes_legacy_accounts_to_exact_size() {
+        let exact = 8 + Genome::INIT_SPACE;
+
+        assert_eq!(migration_target(exact + 100, 300).unwrap(), exact);
+        assert_eq!(migration_target(1000, 300).unwrap(), exact);
+        assert_eq!(
+            migration_target(exact, 300).unwrap_err(),
+            anchor_lang::error::Error::from(GenomeError::NothingToMigrate)
+        );
+        assert_eq!(
+            migration_target(exact + 100, exact + 1).unwrap_err(),
+            anchor_lang::error::Error::from(GenomeError::AccountTooSmall)
+        );
+    }
+
+    /// Memory behind an `AccountInfo`, laid out the way the runtime
+    /// serializes accounts: `realloc` reads the original data length from the
+    /// four bytes before the key and writes the new one before the data.
+    struct TestAccount {
+        key: [u8; 36],
+        owner: Pubkey,
+        lamports: u64,
+        data: Vec<u8>,
+    }
+
+    impl TestAccount {
+        fn new(owner: Pubkey, lamports: u64, data: &[u8]) -> Self {
+            let mut key = [0; 36];
+            key[..4].copy_from_slice(&(data.len() as u32).to_le_bytes());
+            key[4..].copy_from_slice(Pubkey::new_unique().as_ref());
+            let mut buffer = (data.len() as u64).to_le_bytes().to_vec();
+            buffer.extend_from_slice(data);
+            Self {
+                key,
+                owner,
+                lamports,
+                data: buffer,
+            }
+        }
+
+        fn info(&mut self) -> AccountInfo<'_> {
+            // SAFETY: a `Pubkey` is a bare 32-byte array.
+            let key = unsafe { &*(self.key[4..].as_ptr() as *const Pubkey) };
+            AccountInfo::new(
+                key,
+                false,
+                true,
+                &mut self.lamports,
+                &mut self.data[8..],
+                &self.owner,
+                false,
+                0,
+            )
+        }
+    }
+
+    #[test]
+    fn closing_a_genome_returns_its_rent_to_the_owner() {
+        assert_error(
+            Genome {
+                active_listings: 1,
+                ..Default::default()
+            }
+            .assert_closable(),
+            GenomeError::ActiveListings,
+        );
+        assert_error(
+            Genome {
+                unsettled_sales: 1,
+                ..Default::default()
+            }
+            .assert_closable(),
+            GenomeError::UnsettledSales,
+        );
+
+        let genome = Genome {
+            owner: Pubkey::new_unique(),
+            ..Default::default()
+        };
+        assert!(genome.assert_closable().is_ok());
+        let mut data = Vec::new();
+        genome.try_serialize(&mut data).unwrap();
+        let mut genome_account = TestAccount::new(ID, 5_000, &data);
+        let mut owner_account = TestAccount::new(system_program::ID, 1_000, &[]);
+        let genome_info = genome_account.info();
+        let owner_info = owner_account.info();
+
+        Account::<Genome>::try_from(&genome_info)
+            .unwrap()
+            .close(owner_info.clone())
+            .unwrap();
+
+        assert_eq!(owner_info.lamports(), 6_000);
+        assert_eq!(genome_info.lamports(), 0);
+        assert!(Account::<Genome>::try_from(&genome_info).is_err());
+    }
+
+    #[test]
+    fn escrow_recoverable_only_when_closed_and_idle() {
+        let mut transaction = Transaction {
+            status: TransactionStatus::Executed,
+            created_at: 0,
+            listing_expires_at: 1_000,
+            executed_at: Some(500),
+            ..Default::default()
+        };
+        let deadline = 1_000 + ESCROW_RECOVERY_DELAY;
+
+        assert_error(
+            transaction.assert_recoverable(deadline),
+            GenomeError::InvalidTransactionStatus,
+        );
+        transaction.settled = true;
+        assert_error(
+            transaction.assert_recoverable(deadline - 1),
+            GenomeError::RecoveryTooEarly,
+        );
+        assert!(transaction.assert_recoverable(deadline).is_ok());
+
+        transaction.status = TransactionStatus::Disputed;
+        assert_error(
+            transaction.assert_recoverable(deadline),
+            GenomeError::InvalidTransactionStatus,
+        );
+    }
+
+    #[test]
+    fn listing_duration_must_fall_within_config_bounds() {
+        let config = Config {
+            min_duration: DEFAULT_MIN_LISTING_DURATION,
+            max_duration: DEFAULT_MAX_LISTING_DURATION,
+            ..Default::default()
+        };
+
+        assert!(config
+            .assert_listing_duration(DEFAULT_MIN_LISTING_DURATION)
+            .is_ok());
+        assert!(config
+            .assert_listing_duration(DEFAULT_MAX_LISTING_DURATION)
+            .is_ok());
+        assert_error(
+            config.assert_listing_duration(30),
+            GenomeError::ListingDurationOutOfBounds,
+        );
+        assert_error(
+            config.assert_listing_duration(DEFAULT_MAX_LISTING_DURATION + 1),
+            GenomeError::ListingDurationOutOfBounds,
+        );
+    }
+
+    #[test]
+    fn buyer_cannot_exceed_per_buyer_license_cap() {
+        let mut held = BuyerLicenses::default();
+
+        held.record(2).unwrap();
+        held.record(2).unwrap();
+        assert_error(held.record(2), GenomeError::BuyerLimitReached);
+        assert_eq!(held.count, 2);
+
+        let terms = ListingTerms {
+            price: 1,
+            access_duration: 60,
+            listing_expires_at: 100,
+            max_licenses: 5,
+            max_per_buyer: 6,
+            ..Default::default()
+        };
+        assert_error(terms.validate(0), GenomeError::InvalidLicenseCount);
+    }
+
+    #[test]
+    fn offer_is_acceptable_until_expiry_then_reclaimable() {
+        let offer = Offer {
+            amount: 100,
+            created_at: 0,
+            expires_at: 1_000,
+            ..Default::default()
+        };
+
+        assert!(offer.assert_open(1_000).is_ok());
+        assert!(!offer.is_expired(1_000));
+        assert_error(offer.assert_open(1_001), GenomeError::OfferExpired);
+        assert!(offer.is_expired(1_001));
+    }
 } 
\ No newline at end of file